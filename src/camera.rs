@@ -6,6 +6,12 @@
 
 use crate::math::*;
 
+/// Default maximal absolute camera pitch (radians)
+pub const DEFAULT_MAX_PITCH: f32 = std::f32::consts::FRAC_PI_4;
+
+/// Default camera space depth edges are clipped to and points are culled at
+pub const DEFAULT_NEAR_PLANE: f32 = 0.001;
+
 /// Camera utility representation structure
 #[derive(Copy, Clone, Debug)]
 pub struct Camera {
@@ -17,9 +23,22 @@ pub struct Camera {
 
     location_dot_direction: f32,
     location_dot_right: f32,
+
+    /// Horizontal field of view (radians)
+    fov: f32,
+    /// Tangent of half of field of view, visible camera space points have |x / y| <= this value
+    tan_half_fov: f32,
+
+    /// Vertical look angle (radians), positive is up
+    pitch: f32,
+    /// Tangent of pitch
+    tan_pitch: f32,
+    /// Maximal absolute pitch
+    max_pitch: f32,
 } // struct Camera
 
 /// Camera state represetnation structure
+#[derive(Copy, Clone, Debug)]
 pub struct State {
     /// Camera location
     pub location: Vec2f,
@@ -27,6 +46,8 @@ pub struct State {
     pub height: f32,
     /// Camera rotation
     pub rotation: f32,
+    /// Camera pitch
+    pub pitch: f32,
 } // pub struct State
 
 impl Camera {
@@ -41,9 +62,70 @@ impl Camera {
             right: Vec2f { x: 0.0, y: -1.0 },
             location_dot_direction: 0.0,
             location_dot_right: 0.0,
+            fov: std::f32::consts::FRAC_PI_2,
+            tan_half_fov: 1.0,
+            pitch: 0.0,
+            tan_pitch: 0.0,
+            max_pitch: DEFAULT_MAX_PITCH,
         }
     } // fn new
 
+    /// Field of view setting function
+    /// * `fov` - horizontal field of view in radians, clamped to (0, PI) range
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(0.01, std::f32::consts::PI - 0.01);
+        self.tan_half_fov = if self.fov == std::f32::consts::FRAC_PI_2 {
+            1.0
+        } else {
+            (self.fov * 0.5).tan()
+        };
+    } // fn set_fov
+
+    /// Field of view getting function
+    /// * Returns horizontal field of view in radians
+    pub fn get_fov(&self) -> f32 {
+        self.fov
+    } // fn get_fov
+
+    /// Half field of view tangent getting function
+    /// * Returns tangent of half of horizontal field of view (camera space x / y ratio at screen border)
+    pub fn get_tan_half_fov(&self) -> f32 {
+        self.tan_half_fov
+    } // fn get_tan_half_fov
+
+    /// Pitch setting function
+    /// * `pitch` - vertical look angle in radians (positive is up), clamped to [-max_pitch, max_pitch] range
+    pub fn set_pitch(&mut self, pitch: f32) {
+        self.pitch = pitch.clamp(-self.max_pitch, self.max_pitch);
+        self.tan_pitch = self.pitch.tan();
+    } // fn set_pitch
+
+    /// Pitch getting function
+    /// * Returns vertical look angle in radians
+    pub fn get_pitch(&self) -> f32 {
+        self.pitch
+    } // fn get_pitch
+
+    /// Maximal pitch setting function, current pitch is clamped to new range
+    /// * `max_pitch` - maximal absolute pitch in radians, clamped to [0, PI / 2) range
+    pub fn set_max_pitch(&mut self, max_pitch: f32) {
+        self.max_pitch = max_pitch.clamp(0.0, std::f32::consts::FRAC_PI_2 - 0.01);
+        self.set_pitch(self.pitch);
+    } // fn set_max_pitch
+
+    /// Maximal pitch getting function
+    /// * Returns maximal absolute pitch in radians
+    pub fn get_max_pitch(&self) -> f32 {
+        self.max_pitch
+    } // fn get_max_pitch
+
+    /// Horizon shift calculation function. Pitch is implemented by y-shearing, so all rows are shifted by same value.
+    /// * `screen_extent` - screen size in pixels
+    /// * Returns horizon shift in [-1, 1] screen space (half of screen height is 1), positive is down
+    pub fn get_horizon_shift(&self, screen_extent: Ext2<usize>) -> f32 {
+        self.tan_pitch * get_vertical_scale(screen_extent, self.tan_half_fov)
+    } // fn get_horizon_shift
+
     /// Camera location setting function
     /// * `location` - camera location
     /// * `height` - camera location height
@@ -76,6 +158,380 @@ impl Camera {
             y: p.x * self.direction.x + p.y * self.direction.y - self.location_dot_direction,
         }
     } // fn to_space
+
+    /// Point from camera space to global transformation function (inverse of `to_space`)
+    /// * `p` - camera space point to transform
+    /// * Returns transformed point
+    pub fn to_world(&self, p: Vec2f) -> Vec2f {
+        self.location + self.right * p.x + self.direction * p.y
+    } // fn to_world
+
+    /// Screen column view ray calculation function (screen to world picking)
+    /// * `screen_x` - screen column
+    /// * `width` - screen width in pixels
+    /// * Returns world space ray direction of column, scaled to have unit projection on camera direction (isn't normalized)
+    pub fn screen_ray(&self, screen_x: usize, width: usize) -> Vec2f {
+        // Matches column direction used by renderer
+        let pixel_dir_x = (screen_x as f32 / width as f32 * 2.0 - 1.0) * self.tan_half_fov;
+
+        self.direction + self.right * pixel_dir_x
+    } // fn screen_ray
+
+    /// World point into screen pixel projection function, matches renderer projection with default near plane
+    /// * `p` - world point, it's projected at camera height
+    /// * `extent` - screen size in pixels
+    /// * Returns screen pixel (may be located outside of screen) or None if point is closer than `DEFAULT_NEAR_PLANE`
+    pub fn world_to_screen(&self, p: Vec2f, extent: Ext2<usize>) -> Option<(isize, isize)> {
+        self.world_to_screen_with_height(p, self.height, extent, DEFAULT_NEAR_PLANE)
+    } // fn world_to_screen
+
+    /// World point with height into screen pixel projection function, matches renderer projection
+    /// * `p` - world point
+    /// * `height` - world point height
+    /// * `extent` - screen size in pixels
+    /// * `near` - positive camera space depth points are culled at (renderer clips edges to the same depth, see `clip_edge_to_view`)
+    /// * Returns screen pixel (may be located outside of screen) or None if point is closer than `near`
+    pub fn world_to_screen_with_height(&self, p: Vec2f, height: f32, extent: Ext2<usize>, near: f32) -> Option<(isize, isize)> {
+        debug_assert!(near > 0.0, "near plane must be located in front of camera");
+
+        let space = self.to_space(p);
+
+        if space.y < near {
+            return None;
+        }
+
+        let inv_distance = 1.0 / space.y;
+        let x = ((space.x * inv_distance / self.tan_half_fov * 0.5 + 0.5) * extent.w as f32) as isize;
+        let y = project_height(self.height, height, inv_distance * get_vertical_scale(extent, self.tan_half_fov), self.get_horizon_shift(extent), extent.h);
+
+        Some((x, y))
+    } // fn world_to_screen_with_height
+
+    /// Sound source relative to camera direction calculation function
+    /// * `source` - sound source location
+    /// * Returns stereo pan in [-1..1] range (-1 is left, 1 is right) and distance to source
+    pub fn relative_to_listener(&self, source: Vec2f) -> (f32, f32) {
+        let relative = self.to_space(source);
+        let distance = relative.length();
+
+        if distance > 0.0 {
+            (relative.x / distance, distance)
+        } else {
+            (0.0, 0.0)
+        }
+    } // fn relative_to_listener
+
+    /// Camera state getting function
+    /// * Returns current camera location, height and rotation
+    pub fn get_state(&self) -> State {
+        State {
+            location: self.location,
+            height: self.height,
+            rotation: self.rotation,
+            pitch: self.pitch,
+        }
+    } // fn get_state
+
+    /// Camera from state construction function
+    /// * `state` - state to construct camera from
+    /// * Returns camera located according to `state`
+    pub fn from_state(state: &State) -> Self {
+        let mut camera = Self::new();
+        camera.set_location(state.location, state.height, state.rotation);
+        camera.set_pitch(state.pitch);
+        camera
+    } // fn from_state
+
+    /// Camera linear interpolation function
+    /// * `other` - camera to interpolate to
+    /// * `t` - interpolation factor, 0 corresponds to `self` and 1 to `other`
+    /// * Returns interpolated camera. Rotation is interpolated by shortest arc.
+    pub fn lerp(&self, other: &Camera, t: f32) -> Camera {
+        let rotation_delta = (other.rotation - self.rotation + std::f32::consts::PI).rem_euclid(std::f32::consts::PI * 2.0) - std::f32::consts::PI;
+
+        // Field of view is taken from `self`
+        let mut camera = *self;
+        camera.set_location(
+            Vec2f {
+                x: self.location.x + (other.location.x - self.location.x) * t,
+                y: self.location.y + (other.location.y - self.location.y) * t,
+            },
+            self.height + (other.height - self.height) * t,
+            self.rotation + rotation_delta * t,
+        );
+        camera.set_pitch(self.pitch + (other.pitch - self.pitch) * t);
+        camera
+    } // fn lerp
 } // impl Camera
 
+/// Camera space edge clipping function. Parts of edge behind camera (y <= 0) are cut by camera plane.
+/// * `p0`, `p1` - edge points in camera space (see `Camera::to_space`)
+/// * `near` - depth cut points are moved to (they're located at y = 0 after cut, so projection would be infinite)
+/// * Returns edge points ordered by x or None if edge is totally behind camera
+pub fn clip_edge_to_view(mut p0: Vec2f, mut p1: Vec2f, near: f32) -> Option<(Vec2f, Vec2f)> {
+    if p0.x > p1.x {
+        std::mem::swap(&mut p0, &mut p1);
+    }
+
+    if p0.y <= 0.0 {
+        if p1.y <= 0.0 {
+            return None;
+        }
+
+        p0 = Vec2f {
+            x: p0.x - p0.y * (p1.x - p0.x) / (p1.y - p0.y),
+            y: near,
+        };
+    } else if p1.y <= 0.0 {
+        p1 = Vec2f {
+            x: p0.x - p0.y * (p1.x - p0.x) / (p1.y - p0.y),
+            y: near,
+        };
+    }
+
+    Some((p0, p1))
+} // fn clip_edge_to_view
+
+/// Clipped edge into screen column span projection function
+/// * `p0`, `p1` - edge points in camera space, located in front of camera (e.g. `clip_edge_to_view` result)
+/// * `tan_half_fov` - camera half field of view tangent (see `Camera::get_tan_half_fov`)
+/// * `screen_width` - screen width in pixels
+/// * `screen_x_begin`, `screen_x_end` - column range span is clamped to
+/// * Returns ordered [begin, end) column span, empty if edge is out of range
+pub fn project_span(p0: Vec2f, p1: Vec2f, tan_half_fov: f32, screen_width: usize, screen_x_begin: usize, screen_x_end: usize) -> (usize, usize) {
+    let to_screen_x = |p: Vec2f| -> usize {
+        (((p.x / p.y / tan_half_fov * 0.5 + 0.5) * screen_width as f32) as isize).clamp(screen_x_begin as isize, screen_x_end as isize) as usize
+    };
+
+    let x0 = to_screen_x(p0);
+    let x1 = to_screen_x(p1);
+
+    (x0.min(x1), x0.max(x1))
+} // fn project_span
+
+/// Height into screen row projection function. Result isn't clamped, so it may be located outside of screen.
+/// * `camera_height` - camera height
+/// * `height` - world height to project
+/// * `inv_distance` - inverse distance to projected point
+/// * `horizon_shift` - camera horizon shift (see `Camera::get_horizon_shift`)
+/// * `screen_height` - screen height in pixels
+/// * Returns screen row
+pub fn project_height(camera_height: f32, height: f32, inv_distance: f32, horizon_shift: f32, screen_height: usize) -> isize {
+    (((camera_height - height) * inv_distance + horizon_shift + 1.0) / 2.0 * screen_height as f32) as isize
+} // fn project_height
+
+/// Vertical projection scale calculation function. Scale keeps ratio of vertical and horizontal projections same as on 4:3 screen
+/// with 90 degree field of view (original projection), so geometry proportions don't depend on screen aspect ratio.
+/// * `screen_extent` - screen size in pixels
+/// * `tan_half_fov` - camera half field of view tangent
+/// * Returns factor inverse distances are multiplied by before height projection
+pub fn get_vertical_scale(screen_extent: Ext2<usize>, tan_half_fov: f32) -> f32 {
+    (screen_extent.w as f32 * 3.0) / (screen_extent.h as f32 * 4.0 * tan_half_fov)
+} // fn get_vertical_scale
+
+/// Camera path (set of timed camera keyframes) representation structure
+#[derive(Default)]
+pub struct CameraPath {
+    /// Keyframes, sorted by time
+    pub keyframes: Vec<(f32, State)>,
+} // struct CameraPath
+
+impl CameraPath {
+    /// Empty camera path create function
+    /// * Returns newly created path without keyframes
+    pub fn new() -> Self {
+        Self {
+            keyframes: Vec::new(),
+        }
+    } // fn new
+
+    /// Camera pose recording function
+    /// * `time` - time to record pose at
+    /// * `camera` - camera to record pose of
+    pub fn record(&mut self, time: f32, camera: &Camera) {
+        let index = self.keyframes.partition_point(|(keyframe_time, _)| *keyframe_time <= time);
+        self.keyframes.insert(index, (time, camera.get_state()));
+    } // fn record
+
+    /// Camera pose sampling function
+    /// * `time` - time to sample camera pose at
+    /// * Returns camera interpolated between neighbour keyframes (clamped to path bounds)
+    pub fn sample(&self, time: f32) -> Camera {
+        let index = self.keyframes.partition_point(|(keyframe_time, _)| *keyframe_time <= time);
+
+        match (index.checked_sub(1).and_then(|i| self.keyframes.get(i)), self.keyframes.get(index)) {
+            (Some((t0, s0)), Some((t1, s1))) => {
+                Camera::from_state(s0).lerp(&Camera::from_state(s1), (time - t0) / (t1 - t0))
+            }
+            (Some((_, state)), None) | (None, Some((_, state))) => Camera::from_state(state),
+            (None, None) => Camera::new(),
+        }
+    } // fn sample
+} // impl CameraPath
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_path_sample_midpoint() {
+        let mut path = CameraPath::new();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.0, 0.0), 0.5, 0.0);
+        path.record(1.0, &camera);
+        camera.set_location(Vec2f::new(2.0, 4.0), 1.5, 1.0);
+        path.record(3.0, &camera);
+
+        let sampled = path.sample(2.0);
+        assert!((sampled.location.x - 1.0).abs() < 1e-5);
+        assert!((sampled.location.y - 2.0).abs() < 1e-5);
+        assert!((sampled.height - 1.0).abs() < 1e-5);
+        assert!((sampled.rotation - 0.5).abs() < 1e-5);
+
+        // Out of path time is clamped to bounds
+        assert!((path.sample(0.0).location.x - 0.0).abs() < 1e-5);
+        assert!((path.sample(10.0).location.y - 4.0).abs() < 1e-5);
+    } // fn camera_path_sample_midpoint
+
+    #[test]
+    fn listener_pan_and_distance() {
+        for rotation in [0.0, 1.0, std::f32::consts::FRAC_PI_2, 4.0] {
+            let mut camera = Camera::new();
+            camera.set_location(Vec2f::new(2.0, 3.0), 0.5, rotation);
+
+            let (pan, distance) = camera.relative_to_listener(camera.location + camera.right * 4.0);
+            assert!((pan - 1.0).abs() < 1e-5 && (distance - 4.0).abs() < 1e-5);
+
+            let (pan, distance) = camera.relative_to_listener(camera.location + camera.direction * 3.0);
+            assert!(pan.abs() < 1e-5 && (distance - 3.0).abs() < 1e-5);
+
+            let (pan, _) = camera.relative_to_listener(camera.location - camera.right * 2.0);
+            assert!((pan + 1.0).abs() < 1e-5);
+        }
+
+        // Source at listener location has no direction
+        let camera = Camera::new();
+        assert_eq!(camera.relative_to_listener(camera.location), (0.0, 0.0));
+    } // fn listener_pan_and_distance
+
+    #[test]
+    fn projection_is_clamped_only_explicitly() {
+        let screen_height = 100;
+
+        // Projection that was clamped to screen before
+        let clamped_projection = |camera_height: f32, height: f32, inv_distance: f32| -> usize {
+            ((((camera_height - height) * inv_distance + 1.0) / 2.0 * screen_height as f32) as isize).clamp(0, screen_height as isize) as usize
+        };
+
+        // Edges of screen
+        assert_eq!(project_height(0.5, 1.5, 1.0, 0.0, screen_height), 0);
+        assert_eq!(project_height(0.5, -0.5, 1.0, 0.0, screen_height), 100);
+
+        // Points out of screen are projected out of screen
+        assert_eq!(project_height(0.5, 2.5, 1.0, 0.0, screen_height), -50);
+        assert_eq!(project_height(0.5, -1.5, 1.0, 0.0, screen_height), 150);
+
+        // Explicit clamping gives same result without pitch
+        for height in [-4.0, -1.5, -0.5, 0.0, 0.25, 0.5, 1.0, 1.5, 2.5, 8.0] {
+            for inv_distance in [0.1, 0.5, 1.0, 4.0] {
+                let row = project_height(0.5, height, inv_distance, 0.0, screen_height);
+                assert_eq!(row.clamp(0, screen_height as isize) as usize, clamped_projection(0.5, height, inv_distance));
+            }
+        }
+
+        // Horizon shift moves rows, so clamping must be applied after it
+        assert_eq!(project_height(0.5, 1.5, 1.0, 0.5, screen_height), 25);
+        assert_eq!(project_height(0.5, -0.5, 1.0, 0.5, screen_height), 125);
+    } // fn projection_is_clamped_only_explicitly
+
+    #[test]
+    fn edge_clipping_and_span_projection() {
+        let near = 0.01;
+
+        // Edge straddling near plane is cut at camera plane, point order doesn't matter
+        let (p0, p1) = clip_edge_to_view(Vec2f::new(-1.0, -1.0), Vec2f::new(1.0, 1.0), near).unwrap();
+        assert!(p0 == Vec2f::new(0.0, near) && p1 == Vec2f::new(1.0, 1.0));
+        let (q0, q1) = clip_edge_to_view(Vec2f::new(1.0, 1.0), Vec2f::new(-1.0, -1.0), near).unwrap();
+        assert!(q0 == p0 && q1 == p1);
+        let (p0, p1) = clip_edge_to_view(Vec2f::new(-2.0, 2.0), Vec2f::new(2.0, -2.0), near).unwrap();
+        assert!(p0 == Vec2f::new(-2.0, 2.0) && p1 == Vec2f::new(0.0, near));
+
+        // Edge behind camera is removed, edge in front of it isn't changed
+        assert!(clip_edge_to_view(Vec2f::new(-1.0, -1.0), Vec2f::new(1.0, 0.0), near).is_none());
+        let (p0, p1) = clip_edge_to_view(Vec2f::new(0.5, 1.0), Vec2f::new(-0.5, 1.0), near).unwrap();
+        assert!(p0 == Vec2f::new(-0.5, 1.0) && p1 == Vec2f::new(0.5, 1.0));
+
+        // Visible edge covers middle half of screen with 90 degree field of view, it's cut by column range
+        assert_eq!(project_span(p0, p1, 1.0, 100, 0, 100), (25, 75));
+        assert_eq!(project_span(p0, p1, 1.0, 100, 10, 50), (25, 50));
+
+        // Edges fully off one side of view give empty spans
+        assert_eq!(project_span(Vec2f::new(-5.0, 1.0), Vec2f::new(-3.0, 1.0), 1.0, 100, 0, 100), (0, 0));
+        assert_eq!(project_span(Vec2f::new(3.0, 1.0), Vec2f::new(5.0, 1.0), 1.0, 100, 0, 100), (100, 100));
+        assert_eq!(project_span(Vec2f::new(0.6, 1.0), Vec2f::new(0.8, 1.0), 1.0, 100, 10, 50), (50, 50));
+    } // fn edge_clipping_and_span_projection
+
+    #[test]
+    fn screen_ray_matches_field_of_view() {
+        let width = 320;
+        let assert_near = |actual: Vec2f, expected: Vec2f| assert!((actual - expected).length() < 1e-5, "{} != {}", actual, expected);
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(3.0, -2.0), 0.5, 0.7);
+
+        for fov in [std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_3] {
+            camera.set_fov(fov);
+
+            assert_near(camera.screen_ray(width / 2, width), camera.direction);
+
+            // Border columns deviate from forward direction by half of field of view to left and right
+            for (screen_x, side) in [(0, -1.0), (width, 1.0)] {
+                let ray = camera.screen_ray(screen_x, width);
+                let angle = (ray % camera.direction).atan2(ray ^ camera.direction).abs();
+                assert!((angle - fov / 2.0).abs() < 1e-5, "angle {} for fov {}", angle, fov);
+
+                // Ray is inverse of camera space projection
+                assert_near(camera.to_space(camera.location + ray), Vec2f::new(side * (fov / 2.0).tan(), 1.0));
+            }
+        }
+    } // fn screen_ray_matches_field_of_view
+
+    #[test]
+    fn point_ahead_is_projected_to_screen_center() {
+        const NEAR: f32 = 0.001;
+        let extent = Ext2 { w: 320, h: 240 };
+
+        let mut camera = Camera::new();
+        for rotation in [0.0, 2.0] {
+            camera.set_location(Vec2f::new(1.0, 2.0), 0.5, rotation);
+            let tolerance = if rotation == 0.0 { 0 } else { 1 };
+
+            for distance in [0.5, 3.0, 100.0] {
+                // Rotated basis may put point slightly left of column boundary
+                let ahead = camera.location + camera.direction * distance;
+                let (x, y) = camera.world_to_screen(ahead, extent).unwrap();
+                assert!((x - 160).abs() <= tolerance && y == 120, "({}, {}) for rotation {}", x, y, rotation);
+                assert_eq!(camera.world_to_screen_with_height(ahead, camera.height, extent, NEAR), Some((x, y)));
+
+                // Points above camera are projected above screen center
+                let (_, y) = camera.world_to_screen_with_height(ahead, camera.height + 0.1, extent, NEAR).unwrap();
+                assert!(y < 120);
+            }
+
+            let right = camera.location + camera.direction * 2.0 + camera.right;
+            assert!(camera.world_to_screen(right, extent).unwrap().0 > 160);
+
+            let behind = camera.location - camera.direction;
+            assert_eq!(camera.world_to_screen(behind, extent), None);
+            assert_eq!(camera.world_to_screen_with_height(behind, camera.height, extent, NEAR), None);
+
+            // Points between camera and near plane are culled too
+            let too_close = camera.location + camera.direction * 0.05;
+            assert!(camera.world_to_screen_with_height(too_close, camera.height, extent, NEAR).is_some());
+            assert_eq!(camera.world_to_screen_with_height(too_close, camera.height, extent, 0.1), None);
+        }
+    } // fn point_ahead_is_projected_to_screen_center
+} // mod tests
+
 // file camera.rs
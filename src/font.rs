@@ -4,8 +4,10 @@
 /// `Author` TioT2
 /// `Last changed` 05.05.2024
 
+use std::collections::BTreeMap;
+
 use crate::Surface;
-use crate::math::Ext2;
+use crate::math::{Ext2, Rect};
 
 /// Font representation structure
 pub struct Font {
@@ -19,6 +21,10 @@ pub struct Font {
     letter_stride: usize,
     /// Font bits
     bits: Vec<u8>,
+    /// Character to display instead of ones font has no glyphs for
+    fallback_char: u8,
+    /// Per-glyph widths in pixels for proportional fonts, `width` is used for all glyphs if None
+    glyph_widths: Option<[u8; 256]>,
 } // struct Font
 
 /// Font loading error
@@ -30,6 +36,8 @@ pub enum FntLoadingError {
     InappropriateStride { minimal_required: usize },
     /// Stride is too large to fit in file
     TooLargeStride,
+    /// Inappropriate size of font grid image
+    InappropriateImageSize { required_width: usize, required_height: usize },
     /// Unknown error
     Other(String),
 } // enum FntLoadingError
@@ -81,9 +89,108 @@ impl Font {
 
                 bits
             },
+            fallback_char: b'?',
+            glyph_widths: None,
         })
     } // fn from_fnt_bytes
 
+    /// From glyph grid image construction function
+    /// * `pixels` - image pixels, nonzero pixel is set glyph bit
+    /// * `img_width` - image width, image should contain 16x16 glyph cells
+    /// * `glyph_w` - glyph width in pixels
+    /// * `glyph_h` - glyph height in pixels
+    /// * Returns font or image loading error
+    pub fn from_grid_image(pixels: &[u32], img_width: usize, glyph_w: usize, glyph_h: usize) -> Result<Self, FntLoadingError> {
+        let (required_width, required_height) = (glyph_w * 16, glyph_h * 16);
+
+        if img_width != required_width || pixels.len() != required_width * required_height || glyph_w == 0 || glyph_h == 0 {
+            return Err(FntLoadingError::InappropriateImageSize { required_width, required_height });
+        }
+
+        // Pack image to .FNT layout, the leftmost glyph pixel is the most significant bit
+        let stride = glyph_w.div_ceil(8);
+        let mut fnt_bytes = vec![0u8; stride * glyph_h * 256];
+
+        for glyph in 0..256 {
+            let (cell_x, cell_y) = (glyph % 16 * glyph_w, glyph / 16 * glyph_h);
+
+            for y in 0..glyph_h {
+                let row = &pixels[(cell_y + y) * img_width + cell_x..(cell_y + y) * img_width + cell_x + glyph_w];
+                let row_offset = (glyph * glyph_h + y) * stride;
+
+                for (x, _) in row.iter().enumerate().filter(|(_, pixel)| **pixel != 0) {
+                    fnt_bytes[row_offset + x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+
+        Self::from_fnt_bytes(glyph_w as u32, glyph_h as u32, stride as u32, &fnt_bytes)
+    } // fn from_grid_image
+
+    /// Proportional font from .FNT file bytes construction function
+    /// * `width_bits` - font (maximal glyph) width in bits
+    /// * `height` - font height
+    /// * `stride` - font stride bytes
+    /// * `fnt_bytes` - font byte data
+    /// * `glyph_widths` - widths of glyphs in pixels, clamped to `width_bits`
+    /// * Returns font or .FNT file loading error
+    pub fn from_fnt_bytes_proportional(width_bits: u32, height: u32, stride: u32, fnt_bytes: &[u8], glyph_widths: [u8; 256]) -> Result<Self, FntLoadingError> {
+        let mut font = Self::from_fnt_bytes(width_bits, height, stride, fnt_bytes)?;
+
+        font.glyph_widths = Some(glyph_widths.map(|width| (width as u32).min(width_bits) as u8));
+
+        Ok(font)
+    } // fn from_fnt_bytes_proportional
+
+    /// To .FNT file bytes conversion function.
+    /// Font keeps only the lower half (128 glyphs) of loaded .FNT data, so these glyphs are reconstructed
+    /// losslessly and the upper half is filled with zeros. Glyph widths aren't part of .FNT layout and aren't saved.
+    /// * Returns .FNT file bytes, `from_fnt_bytes` with font width, height and stride accepts them
+    pub fn to_fnt_bytes(&self) -> Vec<u8> {
+        let mut fnt_bytes = Vec::<u8>::with_capacity(self.letter_stride * 256);
+
+        fnt_bytes.extend(self.bits[0..self.bits.len() - 7].iter().map(|b| b.reverse_bits()));
+        fnt_bytes.resize(self.letter_stride * 256, 0);
+
+        fnt_bytes
+    } // fn to_fnt_bytes
+
+    /// Glyph presence checking function
+    /// * `c` - character to check
+    /// * Returns true if font contains glyph for `c`
+    pub fn has_glyph(&self, c: char) -> bool {
+        (c as usize) < (self.bits.len() - 7) / self.letter_stride
+    } // fn has_glyph
+
+    /// Fallback character setting function
+    /// * `c` - character to display instead of characters font has no glyphs for, ignored if font has no glyph for `c` itself
+    pub fn set_fallback_char(&mut self, c: u8) {
+        if self.has_glyph(c as char) {
+            self.fallback_char = c;
+        }
+    } // fn set_fallback_char
+
+    /// Character to displayed glyph index conversion function
+    /// * `c` - character to get glyph index of
+    /// * Returns glyph index (fallback character if there's no glyph for `c`)
+    fn get_glyph_index(&self, c: char) -> u8 {
+        if self.has_glyph(c) {
+            c as u8
+        } else {
+            self.fallback_char
+        }
+    } // fn get_glyph_index
+
+    /// Glyph width getting function
+    /// * `glyph` - glyph index
+    /// * Returns width of glyph in pixels
+    fn get_glyph_width(&self, glyph: u8) -> usize {
+        match &self.glyph_widths {
+            Some(widths) => widths[glyph as usize] as usize,
+            None => self.width,
+        }
+    } // fn get_glyph_width
+
     /// String to surface putting function
     /// * `surface` - surface to render string to
     /// * `x` - string x coordinate
@@ -91,50 +198,225 @@ impl Font {
     /// * `line` - string to put
     /// * `color` - text color
     pub fn put_string(&self, surface: &mut Surface, x: usize, y: usize, line: &str, color: u32) {
-        // base pointer
+        self.put_string_scaled(surface, x, y, line, color, 1);
+    } // fn put_string
+
+    /// Scaled string to surface putting function
+    /// * `surface` - surface to render string to
+    /// * `x` - string x coordinate
+    /// * `y` - string y coordinate
+    /// * `line` - string to put
+    /// * `color` - text color
+    /// * `scale` - size of block single glyph pixel is rendered as
+    pub fn put_string_scaled(&self, surface: &mut Surface, x: usize, y: usize, line: &str, color: u32, scale: usize) {
         let stride = surface.get_stride();
         let ext = surface.get_extent();
-        let mut base_ptr = unsafe { surface.get_data_mut().as_mut_ptr().add(y * stride + x) };
 
-        if y + self.height > ext.h {
+        // Skip string if it starts outside the surface, clip glyph rows by surface bottom otherwise
+        if y >= ext.h || x >= ext.w || scale == 0 {
             return;
         }
+        let visible_height = (self.height * scale).min(ext.h - y);
+
+        // base pointer
+        let mut base_ptr = unsafe { surface.get_data_mut().as_mut_ptr().add(y * stride + x) };
 
-        for (index, ch_unicode) in line.chars().enumerate() {
-            let ch = if ch_unicode.is_ascii() {
-                ch_unicode as u8
-            } else {
-                b'?'
-            };
+        let mut offset = 0;
+
+        for ch_unicode in line.chars() {
+            let ch = self.get_glyph_index(ch_unicode);
+            let glyph_width = self.get_glyph_width(ch);
 
             // Break the loop if have to enough space to print next letter
-            if (index + 1) * (self.width + 1) - 1 + x >= ext.w {
+            if offset + glyph_width * scale + x >= ext.w {
                 break;
             }
 
             let mut y_ptr = base_ptr;
-            for y in 0..self.height {
-                let mut line_bits: u64 = unsafe { std::mem::transmute::<*const u8, *const u64>(self.bits.as_ptr().add(ch as usize * self.letter_stride + y * self.stride)).read_unaligned() };
+            for y in 0..visible_height {
+                let mut line_bits: u64 = unsafe { std::mem::transmute::<*const u8, *const u64>(self.bits.as_ptr().add(ch as usize * self.letter_stride + y / scale * self.stride)).read_unaligned() };
                 let mut x_ptr = y_ptr;
 
-                for _ in 0..self.width {
+                for _ in 0..glyph_width {
                     if line_bits & 1 == 1 {
-                        unsafe {
-                            *x_ptr = color;
+                        for dx in 0..scale {
+                            unsafe {
+                                *x_ptr.add(dx) = color;
+                            }
                         }
                     }
 
                     line_bits >>= 1;
-                    x_ptr = unsafe { x_ptr.add(1) };
+                    x_ptr = unsafe { x_ptr.add(scale) };
                 }
 
-                y_ptr = unsafe { y_ptr.add(surface.get_stride()) };
+                y_ptr = unsafe { y_ptr.add(stride) };
             }
 
             // put character
-            base_ptr = unsafe { base_ptr.add(self.width + 1) };
+            base_ptr = unsafe { base_ptr.add((glyph_width + 1) * scale) };
+            offset += (glyph_width + 1) * scale;
         }
-    } // fn put_line
+    } // fn put_string_scaled
+
+    /// String with background to surface putting function
+    /// * `surface` - surface to render string to
+    /// * `x` - string x coordinate
+    /// * `y` - string y coordinate
+    /// * `line` - string to put
+    /// * `fg` - text color
+    /// * `bg` - color of glyph cells behind text
+    pub fn put_string_bg(&self, surface: &mut Surface, x: usize, y: usize, line: &str, fg: u32, bg: u32) {
+        let ext = surface.get_extent();
+        let mut offset = 0;
+
+        // Fill cells of glyphs `put_string` will actually display
+        for ch_unicode in line.chars() {
+            let glyph_width = self.get_glyph_width(self.get_glyph_index(ch_unicode));
+
+            if offset + glyph_width + x >= ext.w {
+                break;
+            }
+
+            surface.fill(Rect::new(x + offset..x + offset + glyph_width + 1, y..y + self.height), bg);
+            offset += glyph_width + 1;
+        }
+
+        self.put_string(surface, x, y, line, fg);
+    } // fn put_string_bg
+
+    /// String to surface putting function, safe (and slow) variant of `put_string`.
+    /// Uses checked pixel access and same clipping rules as `put_string`: glyph rows are clipped by surface bottom and
+    /// string is cut at first glyph that doesn't fit into surface width, so both functions produce identical output.
+    /// * `surface` - surface to render string to
+    /// * `x` - string x coordinate
+    /// * `y` - string y coordinate
+    /// * `line` - string to put
+    /// * `color` - text color
+    pub fn put_string_safe(&self, surface: &mut Surface, x: usize, y: usize, line: &str, color: u32) {
+        let ext = surface.get_extent();
+        let mut letter_x = x;
+
+        for ch_unicode in line.chars() {
+            let ch = self.get_glyph_index(ch_unicode);
+            let glyph_width = self.get_glyph_width(ch);
+
+            // Only whole glyphs are rendered
+            if letter_x + glyph_width >= ext.w {
+                break;
+            }
+
+            let letter_offset = ch as usize * self.letter_stride;
+
+            for dy in 0..self.height {
+                let line_offset = letter_offset + dy * self.stride;
+
+                for dx in 0..glyph_width {
+                    if (self.bits[line_offset + dx / 8] >> (dx % 8)) & 1 == 1 {
+                        surface.set_pixel(letter_x + dx, y + dy, color);
+                    }
+                }
+            }
+
+            letter_x += glyph_width + 1;
+        }
+    } // fn put_string_safe
+
+    /// String size measuring function
+    /// * `line` - string to measure
+    /// * Returns extent `line` occupies if rendered with `put_string`, with no surface width limit
+    pub fn measure_string(&self, line: &str) -> Ext2<usize> {
+        let advance = line
+            .chars()
+            .map(|ch| self.get_glyph_width(self.get_glyph_index(ch)) + 1)
+            .sum::<usize>();
+
+        Ext2 {
+            w: advance.saturating_sub(1),
+            h: self.height,
+        }
+    } // fn measure_string
+
+    /// Text to lines fitting into width splitting function
+    /// * `text` - text to split, may contain newlines
+    /// * `max_width` - maximal line width in pixels
+    /// * Returns lines, words longer than `max_width` are broken at character boundaries
+    fn wrap_lines<'a>(&self, text: &'a str, max_width: usize) -> Vec<&'a str> {
+        let mut lines = Vec::new();
+
+        for source_line in text.split('\n') {
+            // Line is collected as subslice of source line to avoid allocations
+            let mut line_start: Option<usize> = None;
+            let mut line_end = 0;
+
+            let mut word_start = 0;
+
+            for word in source_line.split(' ') {
+                let word_end = word_start + word.len();
+                word_start = word_end + 1;
+
+                if word.is_empty() {
+                    continue;
+                }
+
+                if let Some(start) = line_start {
+                    if self.measure_string(&source_line[start..word_end]).w <= max_width {
+                        line_end = word_end;
+                        continue;
+                    }
+
+                    lines.push(&source_line[start..line_end]);
+                }
+
+                // Hard-break words that don't fit into single line, at least one character per line
+                let mut rest = word;
+                while self.measure_string(rest).w > max_width {
+                    let split = rest
+                        .char_indices()
+                        .skip(1)
+                        .map(|(index, _)| index)
+                        .take_while(|index| self.measure_string(&rest[..*index]).w <= max_width)
+                        .last()
+                        .unwrap_or_else(|| rest.chars().next().map_or(rest.len(), char::len_utf8));
+
+                    if split == rest.len() {
+                        break;
+                    }
+
+                    lines.push(&rest[..split]);
+                    rest = &rest[split..];
+                }
+
+                line_start = Some(word_end - rest.len());
+                line_end = word_end;
+            }
+
+            lines.push(match line_start {
+                Some(start) => &source_line[start..line_end],
+                None => "",
+            });
+        }
+
+        lines
+    } // fn wrap_lines
+
+    /// Word-wrapped multi-line text to surface putting function
+    /// * `surface` - surface to render text to
+    /// * `x` - text x coordinate
+    /// * `y` - text top line y coordinate
+    /// * `text` - text to put, may contain newlines
+    /// * `max_width` - maximal line width in pixels
+    /// * `color` - text color
+    /// * Returns count of lines text is split to
+    pub fn put_paragraph(&self, surface: &mut Surface, x: usize, y: usize, text: &str, max_width: usize, color: u32) -> usize {
+        let lines = self.wrap_lines(text, max_width);
+
+        for (index, line) in lines.iter().enumerate() {
+            self.put_string(surface, x, y + index * (self.height + 1), line, color);
+        }
+
+        lines.len()
+    } // fn put_paragraph
 
     /// Font size getting function
     /// * Returns letter extnet
@@ -146,4 +428,331 @@ impl Font {
     } // pub fn size
 } // impl Font
 
+/// Named font collection representation structure
+pub struct FontRegistry {
+    /// Fonts by their names
+    fonts: BTreeMap<String, Font>,
+} // struct FontRegistry
+
+impl FontRegistry {
+    /// Name the default font is registered with
+    pub const DEFAULT_FONT_NAME: &'static str = "default";
+
+    /// New font registry create function
+    /// * Returns registry containing only default font
+    pub fn new() -> Self {
+        let mut fonts = BTreeMap::new();
+        fonts.insert(Self::DEFAULT_FONT_NAME.to_string(), Font::default());
+
+        Self { fonts }
+    } // fn new
+
+    /// Font registering function
+    /// * `name` - name to register font with
+    /// * `font` - font to register
+    /// * Returns font previously registered with `name`, if any
+    pub fn register(&mut self, name: &str, font: Font) -> Option<Font> {
+        self.fonts.insert(name.to_string(), font)
+    } // fn register
+
+    /// Font by name getting function
+    /// * `name` - font name
+    /// * Returns font registered with `name`, if any
+    pub fn get(&self, name: &str) -> Option<&Font> {
+        self.fonts.get(name)
+    } // fn get
+
+    /// Font by name getting function, default font is returned if there's no font with `name`
+    /// * `name` - font name
+    /// * Returns font registered with `name` or default one
+    pub fn get_or_default(&self, name: &str) -> &Font {
+        self.fonts
+            .get(name)
+            .or_else(|| self.fonts.get(Self::DEFAULT_FONT_NAME))
+            .expect("default font must be registered")
+    } // fn get_or_default
+
+    /// Default font getting function
+    /// * Returns default font
+    pub fn get_default(&self) -> &Font {
+        self.get_or_default(Self::DEFAULT_FONT_NAME)
+    } // fn get_default
+} // impl FontRegistry
+
+impl Default for FontRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+} // impl Default for FontRegistry
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn put_string_safe_matches_fast_path() {
+        let font = Font::default();
+        let (width, height) = (53, 21);
+
+        // Positions include strings clipped by right and bottom surface edges
+        for (x, y) in [(0, 0), (3, 2), (20, 5), (40, 10), (47, 16), (52, 20), (60, 0), (0, 30)] {
+            let mut fast = vec![0u32; width * height];
+            let mut safe = vec![0u32; width * height];
+
+            font.put_string(&mut Surface::new(&mut fast, width, height, width), x, y, "Hello, WEIRD!", 0xFFFFFF);
+            font.put_string_safe(&mut Surface::new(&mut safe, width, height, width), x, y, "Hello, WEIRD!", 0xFFFFFF);
+
+            assert!(fast == safe, "outputs differ at ({}, {})", x, y);
+        }
+    } // fn put_string_safe_matches_fast_path
+
+    /// Single line rendering function
+    /// * `font` - font to render line with
+    /// * `line` - line to render
+    /// * Returns 64x16 frame with line rendered at (0, 0)
+    fn render_line(font: &Font, line: &str) -> Vec<u32> {
+        let mut frame = vec![0u32; 64 * 16];
+        font.put_string(&mut Surface::new(&mut frame, 64, 16, 64), 0, 0, line, 0xFFFFFF);
+        frame
+    } // fn render_line
+
+    #[test]
+    fn configured_fallback_is_rendered_for_missing_glyph() {
+        let mut font = Font::default();
+
+        assert!(font.has_glyph('A'));
+        assert!(!font.has_glyph('Ж'));
+
+        // '?' is fallback by default
+        assert!(render_line(&font, "Ж") == render_line(&font, "?"));
+
+        font.set_fallback_char(b'#');
+        assert!(render_line(&font, "Ж") == render_line(&font, "#"));
+        assert!(render_line(&font, "Ж") != render_line(&font, "?"));
+        assert_eq!(font.measure_string("ЖЖ"), font.measure_string("##"));
+    } // fn configured_fallback_is_rendered_for_missing_glyph
+
+    #[test]
+    fn registry_returns_fonts_by_name() {
+        // Fonts with solid glyphs of given size
+        let solid_font = |glyph_w: usize, glyph_h: usize| Font::from_grid_image(&vec![1; glyph_w * glyph_h * 256], glyph_w * 16, glyph_w, glyph_h).unwrap();
+        let size = |font: &Font| (font.get_letter_size().w, font.get_letter_size().h);
+
+        let mut registry = FontRegistry::new();
+        let default_size = size(registry.get_default());
+        assert!(registry.get(FontRegistry::DEFAULT_FONT_NAME).is_some());
+
+        assert!(registry.register("small", solid_font(4, 6)).is_none());
+        assert!(registry.register("large", solid_font(12, 16)).is_none());
+
+        assert_eq!(registry.get("small").map(size), Some((4, 6)));
+        assert_eq!(registry.get("large").map(size), Some((12, 16)));
+        assert!(registry.get("mono").is_none());
+        assert_eq!(size(registry.get_or_default("mono")), default_size);
+
+        // Registering with existing name replaces font
+        assert_eq!(registry.register("small", solid_font(5, 7)).as_ref().map(size), Some((4, 6)));
+        assert_eq!(registry.get("small").map(size), Some((5, 7)));
+        assert_eq!(size(registry.get_default()), default_size);
+    } // fn registry_returns_fonts_by_name
+
+    /// Default font glyphs with narrow 'i' and 'l' building function
+    /// * Returns proportional font, 'i' and 'l' are 5 pixels wide
+    fn narrow_il_font() -> Font {
+        let mut glyph_widths = [8u8; 256];
+        glyph_widths[b'i' as usize] = 5;
+        glyph_widths[b'l' as usize] = 5;
+
+        Font::from_fnt_bytes_proportional(8, 8, 1, &Font::default().to_fnt_bytes(), glyph_widths).unwrap()
+    } // fn narrow_il_font
+
+    #[test]
+    fn proportional_glyphs_advance_by_their_width() {
+        let font = narrow_il_font();
+
+        // Rightmost lit column of rendered line
+        let right_edge = |line: &str| {
+            let frame = render_line(&font, line);
+            (0..64).filter(|x| (0..16).any(|y| frame[y * 64 + x] != 0)).max().unwrap()
+        };
+        assert!(right_edge("il") < right_edge("MM"));
+
+        // 'M' after "il" starts at 5 + 1 + 5 + 1 pixels
+        let (narrow, single) = (render_line(&font, "ilM"), render_line(&font, "M"));
+        for y in 0..16 {
+            assert_eq!(narrow[y * 64 + 12..y * 64 + 64], single[y * 64..y * 64 + 52], "row {} differs", y);
+        }
+
+        // Fixed width font advances by full glyph width
+        let fixed = render_line(&Font::default(), "ilM");
+        for y in 0..16 {
+            assert_eq!(fixed[y * 64 + 18..y * 64 + 64], single[y * 64..y * 64 + 46], "row {} differs", y);
+        }
+    } // fn proportional_glyphs_advance_by_their_width
+
+    #[test]
+    fn measured_width_matches_fixed_width_formula() {
+        let font = Font::default();
+        let letter = font.get_letter_size();
+
+        assert_eq!(font.measure_string("Hello"), Ext2 { w: 5 * letter.w + 4, h: letter.h });
+        assert_eq!(font.measure_string(""), Ext2 { w: 0, h: letter.h });
+        assert_eq!(narrow_il_font().measure_string("il"), Ext2 { w: 11, h: 8 });
+
+        // Rendered glyphs never leave measured extent
+        let frame = render_line(&font, "MMMMM");
+        let width = font.measure_string("MMMMM").w;
+        assert!((0..16).all(|y| frame[y * 64 + width..y * 64 + 64].iter().all(|pixel| *pixel == 0)));
+        assert!((0..16).any(|y| frame[y * 64 + width - 1] != 0));
+    } // fn measured_width_matches_fixed_width_formula
+
+    #[test]
+    fn paragraph_is_wrapped_by_words() {
+        let font = Font::default();
+
+        // 80 pixels fit 9 glyphs of 8x8 font
+        let sentence = "The quick brown fox jumps over the lazy dog";
+        assert_eq!(font.wrap_lines(sentence, 80), ["The quick", "brown fox", "jumps", "over the", "lazy dog"]);
+
+        // Long words are hard-broken, newlines are kept
+        assert_eq!(font.wrap_lines("abcdefghijklmnopqrstuvwxyz", 80), ["abcdefghi", "jklmnopqr", "stuvwxyz"]);
+        assert_eq!(font.wrap_lines("a\n\nb c", 80), ["a", "", "b c"]);
+        assert_eq!(font.wrap_lines("ab", 0), ["a", "b"]);
+
+        let (width, height) = (96, 64);
+        let mut frame = vec![0u32; width * height];
+        assert_eq!(font.put_paragraph(&mut Surface::new(&mut frame, width, height, width), 0, 0, sentence, 80, 0xFFFFFF), 5);
+
+        // Each line occupies its own band of height + 1 rows
+        let band_is_lit = |band: usize| frame[band * 9 * width..(band * 9 + 9).min(height) * width].iter().any(|pixel| *pixel != 0);
+        assert!((0..5).all(band_is_lit));
+        assert!(!band_is_lit(5));
+        assert!(frame.chunks(width).all(|row| row[80..].iter().all(|pixel| *pixel == 0)));
+    } // fn paragraph_is_wrapped_by_words
+
+    #[test]
+    fn text_near_bottom_edge_is_clipped() {
+        let (width, height) = (64, 12);
+        let canary = 0x5A5A5A;
+
+        // Surface covers only first `height` rows of frame, rest rows are canary ones
+        let mut frame = vec![canary; width * (height + 4)];
+        {
+            let mut surface = Surface::new(&mut frame, width, height, width);
+
+            for y in [height - 3, height - 1, height, height + 2] {
+                Font::default().put_string(&mut surface, 0, y, "MMMMMM", 0xFFFFFF);
+            }
+            Font::default().put_string_scaled(&mut surface, 0, height - 5, "MM", 0xFFFFFF, 3);
+        }
+
+        assert!(frame[width * height..].iter().all(|pixel| *pixel == canary), "text is written below surface");
+        assert!(frame[width * (height - 1)..width * height].contains(&0xFFFFFF), "bottom row isn't drawn");
+    } // fn text_near_bottom_edge_is_clipped
+
+    #[test]
+    fn scaled_glyph_pixel_is_block() {
+        let font = Font::default();
+        let (width, height) = (64, 32);
+
+        let single = render_line(&font, "M");
+        let mut scaled = vec![0u32; width * height];
+        font.put_string_scaled(&mut Surface::new(&mut scaled, width, height, width), 0, 0, "MM", 0xFFFFFF, 2);
+
+        // Every glyph pixel becomes 2x2 block
+        for y in 0..8 {
+            for x in 0..9 {
+                let pixel = single[y * 64 + x];
+
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    assert_eq!(scaled[(y * 2 + dy) * width + x * 2 + dx], pixel, "({}, {}) isn't scaled", x, y);
+                }
+            }
+        }
+        assert!(single.contains(&0xFFFFFF));
+
+        // Next glyph is advanced by (width + 1) * scale
+        for y in 0..16 {
+            assert_eq!(scaled[y * width..y * width + 18], scaled[y * width + 18..y * width + 36]);
+        }
+    } // fn scaled_glyph_pixel_is_block
+
+    #[test]
+    fn background_fills_glyph_cell() {
+        let font = Font::default();
+        let (fg, bg, clear) = (0xFFFFFF, 0x0000FF, 0x123456);
+
+        let mut frame = vec![clear; 64 * 16];
+        font.put_string_bg(&mut Surface::new(&mut frame, 64, 16, 64), 0, 0, "i", fg, bg);
+        let glyph = render_line(&font, "i");
+
+        // Cell is width + 1 by height, glyph bits set foreground and unset ones background
+        for y in 0..16 {
+            for x in 0..64 {
+                let expected = match (x < 9 && y < 8, glyph[y * 64 + x] != 0) {
+                    (true, true) => fg,
+                    (true, false) => bg,
+                    (false, _) => clear,
+                };
+
+                assert_eq!(frame[y * 64 + x], expected, "({}, {}) pixel differs", x, y);
+            }
+        }
+        assert!(frame.contains(&fg) && frame.contains(&bg));
+    } // fn background_fills_glyph_cell
+
+    #[test]
+    fn fnt_bytes_round_trip() {
+        let fnt_bytes = include_bytes!("../fonts/8x8t.fnt");
+        let font = Font::from_fnt_bytes(8, 8, 1, fnt_bytes).unwrap();
+        let saved = font.to_fnt_bytes();
+
+        // Lower half is reconstructed losslessly, upper one isn't kept by font
+        assert_eq!(saved.len(), fnt_bytes.len());
+        assert_eq!(saved[..1024], fnt_bytes[..1024]);
+        assert!(saved[1024..].iter().all(|b| *b == 0));
+
+        // Data with empty upper half round-trips exactly
+        let mut lower_half = fnt_bytes.to_vec();
+        lower_half[1024..].fill(0);
+        assert_eq!(Font::from_fnt_bytes(8, 8, 1, &lower_half).unwrap().to_fnt_bytes(), lower_half);
+
+        // Saved font renders same as the loaded one
+        let reloaded = Font::from_fnt_bytes(8, 8, 1, &saved).unwrap();
+        assert!(render_line(&reloaded, "Hello, WEIRD!") == render_line(&font, "Hello, WEIRD!"));
+    } // fn fnt_bytes_round_trip
+
+    #[test]
+    fn grid_image_glyph_is_rendered() {
+        // 2x2 glyphs, 'A' is diagonal and 'B' is solid
+        let (glyph_w, glyph_h) = (2, 2);
+        let img_width = glyph_w * 16;
+        let mut pixels = vec![0u32; img_width * glyph_h * 16];
+        let cell = |glyph: usize, x: usize, y: usize| (glyph / 16 * glyph_h + y) * img_width + glyph % 16 * glyph_w + x;
+
+        pixels[cell(b'A' as usize, 0, 0)] = 0xFFFFFF;
+        pixels[cell(b'A' as usize, 1, 1)] = 1;
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            pixels[cell(b'B' as usize, x, y)] = 0xFFFFFF;
+        }
+
+        let font = Font::from_grid_image(&pixels, img_width, glyph_w, glyph_h).unwrap();
+        assert_eq!(font.get_letter_size(), Ext2 { w: 2, h: 2 });
+
+        let (width, height) = (8, 2);
+        let mut frame = vec![0u32; width * height];
+        font.put_string(&mut Surface::new(&mut frame, width, height, width), 0, 0, "AB", 0xFF);
+        assert_eq!(frame, [
+            0xFF, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00,
+            0x00, 0xFF, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00,
+        ]);
+
+        // Image must consist of 16x16 glyph cells
+        let invalid_size = FntLoadingError::InappropriateImageSize { required_width: 32, required_height: 32 };
+        assert_eq!(Font::from_grid_image(&pixels, img_width + 1, glyph_w, glyph_h).err(), Some(invalid_size.clone()));
+        assert_eq!(Font::from_grid_image(&pixels[1..], img_width, glyph_w, glyph_h).err(), Some(invalid_size));
+        assert!(Font::from_grid_image(&[], 0, 0, 0).is_err());
+    } // fn grid_image_glyph_is_rendered
+} // mod tests
+
 // file font.rs
@@ -0,0 +1,169 @@
+/// WEIRD Project
+/// `File` game.rs
+/// `Description` Player state and gameplay update implementation module
+/// `Author` TioT2
+/// `Last changed` 17.10.2026
+
+use crate::camera::Camera;
+use crate::map::{Map, SectorId};
+use crate::movement::{Movement, MovementConfig, MovementIntent};
+
+/// Player health at game start
+pub const INITIAL_HEALTH: f32 = 100.0;
+
+/// Game state representation structure
+pub struct Game {
+    /// Player camera
+    pub camera: Camera,
+    /// Sector camera is located in
+    pub sector_id: SectorId,
+    /// Player movement state
+    pub movement: Movement,
+    /// Player health, never goes below zero
+    pub health: f32,
+    /// Camera height above sector floor player falls to
+    pub eye_height: f32,
+    /// Debug camera mode, that ignores collisions, sector bounds and sector effects
+    pub free_fly: bool,
+    /// Current vertical (falling) velocity
+    vertical_velocity: f32,
+} // struct Game
+
+impl Game {
+    /// Game create function
+    /// * `camera` - player camera
+    /// * `sector_id` - sector camera is located in
+    /// * Returns game with full health player
+    pub fn new(camera: Camera, sector_id: SectorId) -> Self {
+        Self {
+            camera,
+            sector_id,
+            movement: Movement::new(MovementConfig::default()),
+            health: INITIAL_HEALTH,
+            eye_height: 0.5,
+            free_fly: false,
+            vertical_velocity: 0.0,
+        }
+    } // fn new
+
+    /// Current vertical velocity getting function
+    /// * Returns vertical velocity (negative if falling)
+    pub fn get_vertical_velocity(&self) -> f32 {
+        self.vertical_velocity
+    } // fn get_vertical_velocity
+
+    /// Game state updating function. Moves player, applies current sector gravity (if there's no vertical input) and damage.
+    /// * `map` - map player is located on
+    /// * `intent` - movement input
+    /// * `dt` - time delta
+    pub fn update(&mut self, map: &Map, intent: &MovementIntent, dt: f32) {
+        self.movement.apply_movement(&mut self.camera, &mut self.sector_id, map, intent, dt, self.free_fly);
+
+        if self.free_fly {
+            self.vertical_velocity = 0.0;
+            return;
+        }
+
+        let Some(sector) = map.get_sector(self.sector_id) else {
+            return;
+        };
+        let effects = map.sector_effects(self.sector_id);
+
+        // Vertical input overrides falling
+        let rest_height = (sector.floor + self.eye_height).min(sector.ceiling);
+        if intent.up != 0.0 || self.camera.height <= rest_height {
+            self.vertical_velocity = 0.0;
+        } else {
+            self.vertical_velocity -= effects.gravity * dt;
+
+            let height = (self.camera.height + self.vertical_velocity * dt).clamp(rest_height, sector.ceiling);
+            if height == rest_height {
+                self.vertical_velocity = 0.0;
+            }
+            self.camera.set_location(self.camera.location, height, self.camera.rotation);
+        }
+
+        self.health = (self.health - effects.damage_per_second * dt).max(0.0);
+    } // fn update
+} // impl Game
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::test_maps;
+    use crate::math::Vec2f;
+
+    /// Single square room map building function
+    /// * `tags` - room sector tags
+    /// * Returns map with 2x2 square sector with floor at 0 and ceiling at 2
+    fn room_map(tags: &[&str]) -> Map {
+        test_maps::room_map(2.0, tags)
+    } // fn room_map
+
+    /// Game in room center create function
+    /// * `height` - initial camera height
+    /// * Returns game
+    fn game_at(height: f32) -> Game {
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(1.0, 1.0), height, 0.0);
+        Game::new(camera, SectorId::new(0))
+    } // fn game_at
+
+    #[test]
+    fn standing_in_damage_sector_lowers_health() {
+        let map = room_map(&["damage=10"]);
+        let mut game = game_at(0.5);
+
+        let mut last_health = game.health;
+        for second in 1..=3 {
+            for _ in 0..60 {
+                game.update(&map, &MovementIntent::default(), 1.0 / 60.0);
+            }
+            assert!(game.health < last_health, "health isn't lowered during second {}", second);
+            last_health = game.health;
+        }
+        assert!((game.health - (INITIAL_HEALTH - 30.0)).abs() < 0.01, "health after 3 seconds is {}", game.health);
+
+        // Health doesn't go below zero
+        for _ in 0..60 * 20 {
+            game.update(&map, &MovementIntent::default(), 1.0 / 60.0);
+        }
+        assert_eq!(game.health, 0.0);
+
+        // Sector without tags and free-fly mode don't damage
+        let mut game = game_at(0.5);
+        game.update(&room_map(&[]), &MovementIntent::default(), 1.0);
+        assert_eq!(game.health, INITIAL_HEALTH);
+
+        let mut game = game_at(0.5);
+        game.free_fly = true;
+        game.update(&map, &MovementIntent::default(), 1.0);
+        assert_eq!(game.health, INITIAL_HEALTH);
+    } // fn standing_in_damage_sector_lowers_health
+
+    #[test]
+    fn sector_gravity_is_applied_to_falling() {
+        let fall = |tags: &[&str], time: f32| {
+            let map = room_map(tags);
+            let mut game = game_at(1.5);
+            for _ in 0..(time * 60.0) as usize {
+                game.update(&map, &MovementIntent::default(), 1.0 / 60.0);
+            }
+            game
+        };
+
+        // Camera falls by g*t^2/2 (with explicit Euler integration error)
+        let standard = fall(&[], 0.25);
+        let low = fall(&["gravity=1"], 0.25);
+        assert!((1.5 - standard.camera.height - 9.81 * 0.25 * 0.25 / 2.0).abs() < 0.03, "fell to {}", standard.camera.height);
+        assert!((1.5 - low.camera.height - 0.25 * 0.25 / 2.0).abs() < 0.01, "fell to {}", low.camera.height);
+        assert!(standard.get_vertical_velocity() < low.get_vertical_velocity());
+
+        // Falling stops at eye height above the floor
+        let landed = fall(&[], 2.0);
+        assert_eq!(landed.camera.height, landed.eye_height);
+        assert_eq!(landed.get_vertical_velocity(), 0.0);
+    } // fn sector_gravity_is_applied_to_falling
+} // mod tests
+
+// file game.rs
@@ -0,0 +1,348 @@
+/// WEIRD Project
+/// `File` gif.rs
+/// `Description` Animated GIF recorder implementation module
+/// `Author` TioT2
+/// `Last changed` 17.10.2026
+
+use std::collections::HashMap;
+use std::io::Write;
+use crate::surface::Surface;
+
+/// LSB-first bit stream writer
+struct BitWriter {
+    /// Written bytes
+    bytes: Vec<u8>,
+    /// Not yet flushed bits
+    accumulator: u32,
+    /// Count of bits in accumulator
+    bit_count: u32,
+} // struct BitWriter
+
+impl BitWriter {
+    /// New bit writer create function
+    /// * Returns empty bit writer
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            accumulator: 0,
+            bit_count: 0,
+        }
+    } // fn new
+
+    /// Code writing function
+    /// * `code` - code to write
+    /// * `size` - code size in bits
+    fn write(&mut self, code: u16, size: u32) {
+        self.accumulator |= (code as u32) << self.bit_count;
+        self.bit_count += size;
+
+        while self.bit_count >= 8 {
+            self.bytes.push(self.accumulator as u8);
+            self.accumulator >>= 8;
+            self.bit_count -= 8;
+        }
+    } // fn write
+
+    /// Writer finishing function
+    /// * Returns written bytes with last partial byte flushed
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.bytes.push(self.accumulator as u8);
+        }
+        self.bytes
+    } // fn finish
+} // impl BitWriter
+
+/// GIF LZW compression function (minimal code size is 8)
+/// * `indices` - palette indices to compress
+/// * Returns compressed code stream
+fn lzw_compress(indices: &[u8]) -> Vec<u8> {
+    const CLEAR_CODE: u16 = 256;
+    const END_CODE: u16 = 257;
+    const FIRST_CODE: u16 = 258;
+    const MAX_CODE: u16 = 4096;
+    const MAX_CODE_SIZE: u32 = 12;
+
+    let mut writer = BitWriter::new();
+    let mut dictionary = HashMap::<(u16, u8), u16>::new();
+    let mut code_size = 9;
+    let mut next_code = FIRST_CODE;
+
+    writer.write(CLEAR_CODE, code_size);
+
+    let mut pixels = indices.iter().copied();
+
+    if let Some(first) = pixels.next() {
+        let mut current = first as u16;
+
+        for pixel in pixels {
+            if let Some(code) = dictionary.get(&(current, pixel)) {
+                current = *code;
+                continue;
+            }
+
+            writer.write(current, code_size);
+
+            if next_code == MAX_CODE {
+                writer.write(CLEAR_CODE, code_size);
+                dictionary.clear();
+                code_size = 9;
+                next_code = FIRST_CODE;
+            } else {
+                dictionary.insert((current, pixel), next_code);
+                next_code += 1;
+
+                // Decoder builds it's table one code later, so code size grows one code later too
+                if next_code - 1 == 1 << code_size && code_size < MAX_CODE_SIZE {
+                    code_size += 1;
+                }
+            }
+
+            current = pixel as u16;
+        }
+
+        writer.write(current, code_size);
+
+        if next_code == 1 << code_size && code_size < MAX_CODE_SIZE {
+            code_size += 1;
+        }
+    }
+
+    writer.write(END_CODE, code_size);
+    writer.finish()
+} // fn lzw_compress
+
+/// Animated GIF recorder representation structure
+pub struct GifRecorder {
+    /// Frame width
+    width: usize,
+    /// Frame height
+    height: usize,
+    /// Recorded frames, pairs of palette indices and frame delays (in hundredths of second)
+    frames: Vec<(Vec<u8>, u16)>,
+} // struct GifRecorder
+
+impl GifRecorder {
+    /// New recorder create function
+    /// * `width` - frame width
+    /// * `height` - frame height
+    /// * Returns newly created recorder without frames
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            frames: Vec::new(),
+        }
+    } // fn new
+
+    /// 0x00RRGGBB color into 3-3-2 palette index conversion function
+    /// * `color` - color to convert
+    /// * Returns palette index
+    fn quantize(color: u32) -> u8 {
+        (((color >> 16) & 0xE0) | ((color >> 11) & 0x1C) | ((color >> 6) & 0x03)) as u8
+    } // fn quantize
+
+    /// Frame pushing function. Frame is cropped (or padded with black) to recorder extent.
+    /// * `surface` - surface to record frame from
+    /// * `delta_time` - frame duration in seconds (e.g. `Timer::get_delta_time` result)
+    pub fn push_frame(&mut self, surface: &Surface, delta_time: f32) {
+        let ext = surface.get_extent();
+        let stride = surface.get_stride();
+        let data = surface.get_data();
+
+        let mut indices = vec![0u8; self.width * self.height];
+
+        for y in 0..self.height.min(ext.h) {
+            for x in 0..self.width.min(ext.w) {
+                indices[y * self.width + x] = Self::quantize(data[y * stride + x]);
+            }
+        }
+
+        self.frames.push((indices, (delta_time * 100.0).round().clamp(1.0, u16::MAX as f32) as u16));
+    } // fn push_frame
+
+    /// Recorded frame count getting function
+    /// * Returns count of recorded frames
+    pub fn get_frame_count(&self) -> usize {
+        self.frames.len()
+    } // fn get_frame_count
+
+    /// Recording finishing function
+    /// * `w` - writer to write GIF file to
+    /// * Returns IO operation result
+    pub fn finish<W: Write>(self, w: &mut W) -> std::io::Result<()> {
+        let width = (self.width as u16).to_le_bytes();
+        let height = (self.height as u16).to_le_bytes();
+
+        // Header and logical screen descriptor with 256-color global palette
+        w.write_all(b"GIF89a")?;
+        w.write_all(&width)?;
+        w.write_all(&height)?;
+        w.write_all(&[0xF7, 0x00, 0x00])?;
+
+        // 3-3-2 global palette
+        for index in 0..256u32 {
+            w.write_all(&[
+                (((index >> 5) & 0x7) * 255 / 7) as u8,
+                (((index >> 2) & 0x7) * 255 / 7) as u8,
+                ((index & 0x3) * 255 / 3) as u8,
+            ])?;
+        }
+
+        // Infinite looping extension
+        w.write_all(&[0x21, 0xFF, 0x0B])?;
+        w.write_all(b"NETSCAPE2.0")?;
+        w.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        for (indices, delay) in &self.frames {
+            // Graphic control extension
+            w.write_all(&[0x21, 0xF9, 0x04, 0x04])?;
+            w.write_all(&delay.to_le_bytes())?;
+            w.write_all(&[0x00, 0x00])?;
+
+            // Image descriptor
+            w.write_all(&[0x2C, 0x00, 0x00, 0x00, 0x00])?;
+            w.write_all(&width)?;
+            w.write_all(&height)?;
+            w.write_all(&[0x00])?;
+
+            // Image data, splitted in sub-blocks
+            w.write_all(&[0x08])?;
+            for block in lzw_compress(indices).chunks(255) {
+                w.write_all(&[block.len() as u8])?;
+                w.write_all(block)?;
+            }
+            w.write_all(&[0x00])?;
+        }
+
+        w.write_all(&[0x3B])
+    } // fn finish
+} // impl GifRecorder
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// GIF LZW decompression function (minimal code size is 8), inverse of `lzw_compress`
+    /// * `data` - compressed code stream
+    /// * Returns palette indices
+    fn lzw_decompress(data: &[u8]) -> Vec<u8> {
+        let mut dictionary = Vec::<Vec<u8>>::new();
+        let mut code_size = 9;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut result = Vec::new();
+
+        let (mut accumulator, mut bit_count, mut bytes) = (0u32, 0u32, data.iter());
+
+        loop {
+            while bit_count < code_size {
+                accumulator |= (*bytes.next().expect("no end code") as u32) << bit_count;
+                bit_count += 8;
+            }
+            let code = (accumulator & ((1 << code_size) - 1)) as usize;
+            accumulator >>= code_size;
+            bit_count -= code_size;
+
+            match code {
+                256 => {
+                    dictionary = (0..=255u8).map(|index| vec![index]).chain([Vec::new(), Vec::new()]).collect();
+                    code_size = 9;
+                    previous = None;
+                    continue;
+                }
+                257 => break,
+                _ => {}
+            }
+
+            let entry = match (dictionary.get(code), previous.as_ref()) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [previous.as_slice(), &previous[..1]].concat(),
+                (None, None) => panic!("unknown first code"),
+            };
+
+            if let Some(previous) = previous {
+                dictionary.push([previous.as_slice(), &entry[..1]].concat());
+                if dictionary.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+
+            result.extend_from_slice(&entry);
+            previous = Some(entry);
+        }
+
+        result
+    } // fn lzw_decompress
+
+    /// GIF frame parsing function
+    /// * `bytes` - GIF file contents
+    /// * Returns palette indices of every frame
+    fn parse_frames(bytes: &[u8]) -> Vec<Vec<u8>> {
+        assert_eq!(&bytes[..6], b"GIF89a");
+
+        // Header, logical screen descriptor and global palette
+        let mut offset = 6 + 7 + 256 * 3;
+        let mut frames = Vec::new();
+
+        // Sub-block sequence reading function, returns concatenated sub-block data
+        let read_blocks = |offset: &mut usize| -> Vec<u8> {
+            let mut data = Vec::new();
+            loop {
+                let size = bytes[*offset] as usize;
+                *offset += 1;
+                if size == 0 {
+                    break data;
+                }
+                data.extend_from_slice(&bytes[*offset..*offset + size]);
+                *offset += size;
+            }
+        };
+
+        loop {
+            match bytes[offset] {
+                0x21 => {
+                    offset += 2;
+                    read_blocks(&mut offset);
+                }
+                0x2C => {
+                    offset += 10;
+                    assert_eq!(bytes[offset], 0x08);
+                    offset += 1;
+                    frames.push(lzw_decompress(&read_blocks(&mut offset)));
+                }
+                0x3B => break frames,
+                block => panic!("unexpected block {:#04X}", block),
+            }
+        }
+    } // fn parse_frames
+
+    #[test]
+    fn two_solid_frames_are_recorded() {
+        let (width, height) = (37, 23);
+        let mut recorder = GifRecorder::new(width, height);
+
+        for color in [0xFF0000, 0x00FF00] {
+            let mut data = vec![color; width * height];
+            recorder.push_frame(&Surface::new(&mut data, width, height, width), 0.05);
+        }
+        assert_eq!(recorder.get_frame_count(), 2);
+
+        let mut bytes = Vec::new();
+        recorder.finish(&mut bytes).unwrap();
+
+        let frames = parse_frames(&bytes);
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].len() == width * height && frames[0].iter().all(|index| *index == GifRecorder::quantize(0xFF0000)));
+        assert!(frames[1].len() == width * height && frames[1].iter().all(|index| *index == GifRecorder::quantize(0x00FF00)));
+    } // fn two_solid_frames_are_recorded
+
+    #[test]
+    fn lzw_round_trip_with_dictionary_reset() {
+        // Noise-like data overflows 4096-entry dictionary several times
+        let indices = (0..100_000u32).map(|index| (index.wrapping_mul(2654435761) >> 13) as u8).collect::<Vec<u8>>();
+
+        assert!(lzw_decompress(&lzw_compress(&indices)) == indices);
+    } // fn lzw_round_trip_with_dictionary_reset
+} // mod tests
+
+// file gif.rs
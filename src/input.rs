@@ -8,22 +8,45 @@ use std::collections::BTreeMap;
 use crate::math::Vec2f;
 
 /// Single key state
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq)]
 struct KeyState {
     /// Is key pressed
     pub pressed: bool,
     /// Is key state changed during previous frame
     pub changed: bool,
+    /// Time key is held for (in seconds), zero for released keys
+    pub held_time: f32,
 } // struct KState
 
+impl KeyState {
+    /// Released and unchanged key state
+    const RELEASED: Self = Self { pressed: false, changed: false, held_time: 0.0 };
+
+    /// Key state changing function
+    /// * `is_pressed` - new key state
+    fn set_pressed(&mut self, is_pressed: bool) {
+        if self.pressed != is_pressed {
+            self.held_time = 0.0;
+        }
+        self.pressed = is_pressed;
+        self.changed = true;
+    } // fn set_pressed
+} // impl KeyState
+
 /// Keycode representation structure
 pub type KeyCode = winit::keyboard::KeyCode;
 
+/// Mouse button representation structure
+pub type MouseButton = winit::event::MouseButton;
+
 /// Input state representation structure
+#[derive(Clone, PartialEq)]
 pub struct State {
     keys: BTreeMap<KeyCode, KeyState>,
+    mouse_buttons: BTreeMap<MouseButton, KeyState>,
     mouse_location: Vec2f,
     mouse_motion: Vec2f,
+    scroll_delta: Vec2f,
 } // struct State
 
 impl State {
@@ -31,13 +54,16 @@ impl State {
     /// * `key` - keycode to get state of
     /// * Returns key state
     fn get_key_state(&self, key: KeyCode) -> KeyState {
-        if let Some(state) = self.keys.get(&key) {
-            *state
-        } else {
-            KeyState { pressed: false, changed: false }
-        }
+        self.keys.get(&key).copied().unwrap_or(KeyState::RELEASED)
     } // fn get_key_state
 
+    /// Mouse button state getting function
+    /// * `button` - mouse button to get state of
+    /// * Returns button state
+    fn get_mouse_button_state(&self, button: MouseButton) -> KeyState {
+        self.mouse_buttons.get(&button).copied().unwrap_or(KeyState::RELEASED)
+    } // fn get_mouse_button_state
+
     /// Is key pressed checking function
     /// * `key` - key to check state of
     /// * Returns true if key is pressed
@@ -60,6 +86,36 @@ impl State {
         state.pressed && state.changed
     } // fn is_key_clicked
 
+    /// Is key just released checking function
+    /// * `key` - key to check state of
+    /// * Returns true if key's released during previous frame
+    pub fn is_key_just_released(&self, key: KeyCode) -> bool {
+        let state = self.get_key_state(key);
+        !state.pressed && state.changed
+    } // fn is_key_just_released
+
+    /// Key held time getting function
+    /// * `key` - key to get held time of
+    /// * Returns time key is held for in seconds, zero if key is released
+    pub fn get_key_held_time(&self, key: KeyCode) -> f32 {
+        self.get_key_state(key).held_time
+    } // fn get_key_held_time
+
+    /// Is mouse button pressed checking function
+    /// * `button` - mouse button to check state of
+    /// * Returns true if button is pressed
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.get_mouse_button_state(button).pressed
+    } // fn is_mouse_button_pressed
+
+    /// Is mouse button clicked checking function
+    /// * `button` - mouse button to check state of
+    /// * Returns true if button's clicked
+    pub fn is_mouse_button_clicked(&self, button: MouseButton) -> bool {
+        let state = self.get_mouse_button_state(button);
+        state.pressed && state.changed
+    } // fn is_mouse_button_clicked
+
     /// Mouse location getting function
     /// * Returns mouse location as Vec2f
     pub fn get_mouse_location(&self) -> Vec2f {
@@ -71,11 +127,410 @@ impl State {
     pub fn get_mouse_motion(&self) -> Vec2f {
         self.mouse_motion
     } // fn get_mouse_motion
+
+    /// Scroll delta getting function
+    /// * Returns mouse wheel scroll (in lines) accumulated during previous frame
+    pub fn get_scroll_delta(&self) -> Vec2f {
+        self.scroll_delta
+    } // fn get_scroll_delta
 } // impl State
 
+/// Frozen input state copy representation structure. Snapshot owns its state and provides read-only access only,
+/// so it isn't affected by input events arrived after it was taken.
+#[derive(Clone)]
+pub struct StateSnapshot {
+    state: State,
+} // struct StateSnapshot
+
+impl StateSnapshot {
+    /// Snapshot state getting function
+    /// * Returns input state at the moment snapshot was taken
+    pub fn get_state(&self) -> &State {
+        &self.state
+    } // fn get_state
+} // impl StateSnapshot
+
+/// Action binding representation enumeration
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Binding {
+    /// Keyboard key
+    Key(KeyCode),
+    /// Mouse button
+    MouseButton(MouseButton),
+} // enum Binding
+
+impl From<KeyCode> for Binding {
+    fn from(key: KeyCode) -> Self {
+        Self::Key(key)
+    }
+} // impl From<KeyCode> for Binding
+
+impl From<MouseButton> for Binding {
+    fn from(button: MouseButton) -> Self {
+        Self::MouseButton(button)
+    }
+} // impl From<MouseButton> for Binding
+
+impl Binding {
+    /// Is binding pressed checking function
+    /// * `state` - input state to check binding in
+    /// * Returns true if bound key or button is pressed
+    fn is_pressed(self, state: &State) -> bool {
+        match self {
+            Self::Key(key) => state.is_key_pressed(key),
+            Self::MouseButton(button) => state.is_mouse_button_pressed(button),
+        }
+    } // fn is_pressed
+
+    /// Is binding clicked checking function
+    /// * `state` - input state to check binding in
+    /// * Returns true if bound key or button is clicked
+    fn is_clicked(self, state: &State) -> bool {
+        match self {
+            Self::Key(key) => state.is_key_clicked(key),
+            Self::MouseButton(button) => state.is_mouse_button_clicked(button),
+        }
+    } // fn is_clicked
+} // impl Binding
+
+/// User-defined action to key bindings representation structure
+#[derive(Clone)]
+pub struct ActionMap<A: Ord> {
+    bindings: BTreeMap<A, Vec<Binding>>,
+} // struct ActionMap
+
+impl<A: Ord> ActionMap<A> {
+    /// New empty action map construction function
+    /// * Returns action map with no bindings
+    pub fn new() -> Self {
+        Self { bindings: BTreeMap::new() }
+    } // fn new
+
+    /// Key (or mouse button) to action binding function
+    /// * `action` - action to bind key to
+    /// * `binding` - key or mouse button to add to action bindings
+    pub fn bind(&mut self, action: A, binding: impl Into<Binding>) {
+        let binding = binding.into();
+        let bindings = self.bindings.entry(action).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    } // fn bind
+
+    /// Action bindings overriding function
+    /// * `action` - action to set bindings of
+    /// * `bindings` - keys and mouse buttons action is triggered by, action is unbound if empty
+    pub fn set_bindings(&mut self, action: A, bindings: &[Binding]) {
+        if bindings.is_empty() {
+            self.bindings.remove(&action);
+        } else {
+            self.bindings.insert(action, bindings.to_vec());
+        }
+    } // fn set_bindings
+
+    /// Action bindings getting function
+    /// * `action` - action to get bindings of
+    /// * Returns keys and mouse buttons action is triggered by
+    pub fn get_bindings(&self, action: &A) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], Vec::as_slice)
+    } // fn get_bindings
+
+    /// Is action pressed checking function
+    /// * `state` - input state to check action in
+    /// * `action` - action to check
+    /// * Returns true if any of action bindings is pressed
+    pub fn is_action_pressed(&self, state: &State, action: A) -> bool {
+        self.get_bindings(&action).iter().any(|binding| binding.is_pressed(state))
+    } // fn is_action_pressed
+
+    /// Is action clicked checking function
+    /// * `state` - input state to check action in
+    /// * `action` - action to check
+    /// * Returns true if any of action bindings is clicked
+    pub fn is_action_clicked(&self, state: &State, action: A) -> bool {
+        self.get_bindings(&action).iter().any(|binding| binding.is_clicked(state))
+    } // fn is_action_clicked
+} // impl ActionMap
+
+impl<A: Ord> Default for ActionMap<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+} // impl Default for ActionMap
+
+/// Single input event representation enumeration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    /// Key state change
+    Key { key: KeyCode, pressed: bool },
+    /// Mouse button state change
+    MouseButton { button: MouseButton, pressed: bool },
+    /// Mouse motion to new position
+    MouseMove(Vec2f),
+    /// Mouse wheel scroll
+    Scroll(Vec2f),
+} // enum InputEvent
+
+/// Names key codes are saved to input record with, in order of winit declaration
+const KEY_NAMES: [(KeyCode, &str); 194] = [
+    (KeyCode::Backquote, "Backquote"), (KeyCode::Backslash, "Backslash"), (KeyCode::BracketLeft, "BracketLeft"),
+    (KeyCode::BracketRight, "BracketRight"), (KeyCode::Comma, "Comma"), (KeyCode::Digit0, "Digit0"), (KeyCode::Digit1, "Digit1"),
+    (KeyCode::Digit2, "Digit2"), (KeyCode::Digit3, "Digit3"), (KeyCode::Digit4, "Digit4"), (KeyCode::Digit5, "Digit5"),
+    (KeyCode::Digit6, "Digit6"), (KeyCode::Digit7, "Digit7"), (KeyCode::Digit8, "Digit8"), (KeyCode::Digit9, "Digit9"),
+    (KeyCode::Equal, "Equal"), (KeyCode::IntlBackslash, "IntlBackslash"), (KeyCode::IntlRo, "IntlRo"), (KeyCode::IntlYen, "IntlYen"),
+    (KeyCode::KeyA, "KeyA"), (KeyCode::KeyB, "KeyB"), (KeyCode::KeyC, "KeyC"), (KeyCode::KeyD, "KeyD"), (KeyCode::KeyE, "KeyE"),
+    (KeyCode::KeyF, "KeyF"), (KeyCode::KeyG, "KeyG"), (KeyCode::KeyH, "KeyH"), (KeyCode::KeyI, "KeyI"), (KeyCode::KeyJ, "KeyJ"),
+    (KeyCode::KeyK, "KeyK"), (KeyCode::KeyL, "KeyL"), (KeyCode::KeyM, "KeyM"), (KeyCode::KeyN, "KeyN"), (KeyCode::KeyO, "KeyO"),
+    (KeyCode::KeyP, "KeyP"), (KeyCode::KeyQ, "KeyQ"), (KeyCode::KeyR, "KeyR"), (KeyCode::KeyS, "KeyS"), (KeyCode::KeyT, "KeyT"),
+    (KeyCode::KeyU, "KeyU"), (KeyCode::KeyV, "KeyV"), (KeyCode::KeyW, "KeyW"), (KeyCode::KeyX, "KeyX"), (KeyCode::KeyY, "KeyY"),
+    (KeyCode::KeyZ, "KeyZ"), (KeyCode::Minus, "Minus"), (KeyCode::Period, "Period"), (KeyCode::Quote, "Quote"),
+    (KeyCode::Semicolon, "Semicolon"), (KeyCode::Slash, "Slash"), (KeyCode::AltLeft, "AltLeft"), (KeyCode::AltRight, "AltRight"),
+    (KeyCode::Backspace, "Backspace"), (KeyCode::CapsLock, "CapsLock"), (KeyCode::ContextMenu, "ContextMenu"),
+    (KeyCode::ControlLeft, "ControlLeft"), (KeyCode::ControlRight, "ControlRight"), (KeyCode::Enter, "Enter"),
+    (KeyCode::SuperLeft, "SuperLeft"), (KeyCode::SuperRight, "SuperRight"), (KeyCode::ShiftLeft, "ShiftLeft"),
+    (KeyCode::ShiftRight, "ShiftRight"), (KeyCode::Space, "Space"), (KeyCode::Tab, "Tab"), (KeyCode::Convert, "Convert"),
+    (KeyCode::KanaMode, "KanaMode"), (KeyCode::Lang1, "Lang1"), (KeyCode::Lang2, "Lang2"), (KeyCode::Lang3, "Lang3"),
+    (KeyCode::Lang4, "Lang4"), (KeyCode::Lang5, "Lang5"), (KeyCode::NonConvert, "NonConvert"), (KeyCode::Delete, "Delete"),
+    (KeyCode::End, "End"), (KeyCode::Help, "Help"), (KeyCode::Home, "Home"), (KeyCode::Insert, "Insert"), (KeyCode::PageDown, "PageDown"),
+    (KeyCode::PageUp, "PageUp"), (KeyCode::ArrowDown, "ArrowDown"), (KeyCode::ArrowLeft, "ArrowLeft"), (KeyCode::ArrowRight, "ArrowRight"),
+    (KeyCode::ArrowUp, "ArrowUp"), (KeyCode::NumLock, "NumLock"), (KeyCode::Numpad0, "Numpad0"), (KeyCode::Numpad1, "Numpad1"),
+    (KeyCode::Numpad2, "Numpad2"), (KeyCode::Numpad3, "Numpad3"), (KeyCode::Numpad4, "Numpad4"), (KeyCode::Numpad5, "Numpad5"),
+    (KeyCode::Numpad6, "Numpad6"), (KeyCode::Numpad7, "Numpad7"), (KeyCode::Numpad8, "Numpad8"), (KeyCode::Numpad9, "Numpad9"),
+    (KeyCode::NumpadAdd, "NumpadAdd"), (KeyCode::NumpadBackspace, "NumpadBackspace"), (KeyCode::NumpadClear, "NumpadClear"),
+    (KeyCode::NumpadClearEntry, "NumpadClearEntry"), (KeyCode::NumpadComma, "NumpadComma"), (KeyCode::NumpadDecimal, "NumpadDecimal"),
+    (KeyCode::NumpadDivide, "NumpadDivide"), (KeyCode::NumpadEnter, "NumpadEnter"), (KeyCode::NumpadEqual, "NumpadEqual"),
+    (KeyCode::NumpadHash, "NumpadHash"), (KeyCode::NumpadMemoryAdd, "NumpadMemoryAdd"), (KeyCode::NumpadMemoryClear, "NumpadMemoryClear"),
+    (KeyCode::NumpadMemoryRecall, "NumpadMemoryRecall"), (KeyCode::NumpadMemoryStore, "NumpadMemoryStore"),
+    (KeyCode::NumpadMemorySubtract, "NumpadMemorySubtract"), (KeyCode::NumpadMultiply, "NumpadMultiply"),
+    (KeyCode::NumpadParenLeft, "NumpadParenLeft"), (KeyCode::NumpadParenRight, "NumpadParenRight"), (KeyCode::NumpadStar, "NumpadStar"),
+    (KeyCode::NumpadSubtract, "NumpadSubtract"), (KeyCode::Escape, "Escape"), (KeyCode::Fn, "Fn"), (KeyCode::FnLock, "FnLock"),
+    (KeyCode::PrintScreen, "PrintScreen"), (KeyCode::ScrollLock, "ScrollLock"), (KeyCode::Pause, "Pause"),
+    (KeyCode::BrowserBack, "BrowserBack"), (KeyCode::BrowserFavorites, "BrowserFavorites"), (KeyCode::BrowserForward, "BrowserForward"),
+    (KeyCode::BrowserHome, "BrowserHome"), (KeyCode::BrowserRefresh, "BrowserRefresh"), (KeyCode::BrowserSearch, "BrowserSearch"),
+    (KeyCode::BrowserStop, "BrowserStop"), (KeyCode::Eject, "Eject"), (KeyCode::LaunchApp1, "LaunchApp1"),
+    (KeyCode::LaunchApp2, "LaunchApp2"), (KeyCode::LaunchMail, "LaunchMail"), (KeyCode::MediaPlayPause, "MediaPlayPause"),
+    (KeyCode::MediaSelect, "MediaSelect"), (KeyCode::MediaStop, "MediaStop"), (KeyCode::MediaTrackNext, "MediaTrackNext"),
+    (KeyCode::MediaTrackPrevious, "MediaTrackPrevious"), (KeyCode::Power, "Power"), (KeyCode::Sleep, "Sleep"),
+    (KeyCode::AudioVolumeDown, "AudioVolumeDown"), (KeyCode::AudioVolumeMute, "AudioVolumeMute"), (KeyCode::AudioVolumeUp, "AudioVolumeUp"),
+    (KeyCode::WakeUp, "WakeUp"), (KeyCode::Meta, "Meta"), (KeyCode::Hyper, "Hyper"), (KeyCode::Turbo, "Turbo"), (KeyCode::Abort, "Abort"),
+    (KeyCode::Resume, "Resume"), (KeyCode::Suspend, "Suspend"), (KeyCode::Again, "Again"), (KeyCode::Copy, "Copy"), (KeyCode::Cut, "Cut"),
+    (KeyCode::Find, "Find"), (KeyCode::Open, "Open"), (KeyCode::Paste, "Paste"), (KeyCode::Props, "Props"), (KeyCode::Select, "Select"),
+    (KeyCode::Undo, "Undo"), (KeyCode::Hiragana, "Hiragana"), (KeyCode::Katakana, "Katakana"), (KeyCode::F1, "F1"), (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"), (KeyCode::F4, "F4"), (KeyCode::F5, "F5"), (KeyCode::F6, "F6"), (KeyCode::F7, "F7"), (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"), (KeyCode::F10, "F10"), (KeyCode::F11, "F11"), (KeyCode::F12, "F12"), (KeyCode::F13, "F13"), (KeyCode::F14, "F14"),
+    (KeyCode::F15, "F15"), (KeyCode::F16, "F16"), (KeyCode::F17, "F17"), (KeyCode::F18, "F18"), (KeyCode::F19, "F19"),
+    (KeyCode::F20, "F20"), (KeyCode::F21, "F21"), (KeyCode::F22, "F22"), (KeyCode::F23, "F23"), (KeyCode::F24, "F24"),
+    (KeyCode::F25, "F25"), (KeyCode::F26, "F26"), (KeyCode::F27, "F27"), (KeyCode::F28, "F28"), (KeyCode::F29, "F29"),
+    (KeyCode::F30, "F30"), (KeyCode::F31, "F31"), (KeyCode::F32, "F32"), (KeyCode::F33, "F33"), (KeyCode::F34, "F34"),
+    (KeyCode::F35, "F35"),
+];
+
+/// Key to record name conversion function
+/// * `key` - key to get name of
+/// * Returns key name or None if key can't be saved to record
+fn get_key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(code, _)| *code == key).map(|(_, name)| *name)
+} // fn get_key_name
+
+/// Record name to key conversion function
+/// * `name` - key name in record
+/// * Returns key or None if there's no key with such name
+fn get_key_by_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES.iter().find(|(_, key_name)| *key_name == name).map(|(code, _)| *code)
+} // fn get_key_by_name
+
+/// Mouse button names in record
+const MOUSE_BUTTON_NAMES: [(MouseButton, &str); 5] = [
+    (MouseButton::Left, "Left"),
+    (MouseButton::Right, "Right"),
+    (MouseButton::Middle, "Middle"),
+    (MouseButton::Back, "Back"),
+    (MouseButton::Forward, "Forward"),
+];
+
+/// Input record loading error
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordLoadingError {
+    /// Line has no frame index or event description
+    InvalidLine { line: usize },
+    /// Unknown event type
+    UnknownEvent { line: usize, event: String },
+    /// Unknown key or mouse button name
+    UnknownKey { line: usize, key: String },
+    /// Invalid event parameter
+    InvalidParameter { line: usize, parameter: String },
+    /// Events aren't ordered by frame
+    UnorderedFrames { line: usize },
+} // enum RecordLoadingError
+
+/// Input events recorder
+#[derive(Clone, Default)]
+pub struct Recorder {
+    /// Index of frame events are currently recorded to
+    frame: u64,
+    /// Recorded events with frame indices
+    events: Vec<(u64, InputEvent)>,
+} // struct Recorder
+
+impl Recorder {
+    /// New recorder construction function
+    /// * Returns recorder with no events
+    pub fn new() -> Self {
+        Self { frame: 0, events: Vec::new() }
+    } // fn new
+
+    /// Event recording function
+    /// * `event` - event to record to current frame
+    pub fn record(&mut self, event: InputEvent) {
+        self.events.push((self.frame, event));
+    } // fn record
+
+    /// Next frame starting function
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    } // fn next_frame
+
+    /// Recorded events getting function
+    /// * Returns events with indices of frames they occured at
+    pub fn get_events(&self) -> &[(u64, InputEvent)] {
+        &self.events
+    } // fn get_events
+
+    /// Record to text writing function. Every event is written to separate line as `<frame> <event> <parameters>`,
+    /// events with keys and mouse buttons that have no record name are skipped, as they can't be read back.
+    /// * `writer` - writer to write record to
+    /// * Returns IO error if any occured
+    pub fn write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for (frame, event) in &self.events {
+            match event {
+                InputEvent::Key { key, pressed } => {
+                    if let Some(name) = get_key_name(*key) {
+                        writeln!(writer, "{} key {} {}", frame, name, *pressed as u8)?;
+                    }
+                }
+                InputEvent::MouseButton { button, pressed } => match MOUSE_BUTTON_NAMES.iter().find(|(code, _)| code == button) {
+                    Some((_, name)) => writeln!(writer, "{} button {} {}", frame, name, *pressed as u8)?,
+                    None => match button {
+                        MouseButton::Other(index) => writeln!(writer, "{} button {} {}", frame, index, *pressed as u8)?,
+                        _ => continue,
+                    },
+                }
+                InputEvent::MouseMove(position) => writeln!(writer, "{} move {} {}", frame, position.x, position.y)?,
+                InputEvent::Scroll(delta) => writeln!(writer, "{} scroll {} {}", frame, delta.x, delta.y)?,
+            }
+        }
+
+        Ok(())
+    } // fn write
+} // impl Recorder
+
+/// Recorded input events player
+#[derive(Clone)]
+pub struct Playback {
+    /// Index of next frame to play
+    frame: u64,
+    /// Index of next event to play
+    next_event: usize,
+    /// Events with frame indices, ordered by frame
+    events: Vec<(u64, InputEvent)>,
+} // struct Playback
+
+impl Playback {
+    /// From recorder construction function
+    /// * `recorder` - recorder to play events of
+    /// * Returns playback starting from the first recorded frame
+    pub fn from_recorder(recorder: &Recorder) -> Self {
+        Self { frame: 0, next_event: 0, events: recorder.events.clone() }
+    } // fn from_recorder
+
+    /// From text record (see `Recorder::write`) loading function
+    /// * `source` - record text
+    /// * Returns playback or record loading error
+    pub fn load(source: &str) -> Result<Self, RecordLoadingError> {
+        let mut events = Vec::<(u64, InputEvent)>::new();
+
+        for (index, line_text) in source.lines().enumerate() {
+            let line = index + 1;
+            let mut words = line_text.split_whitespace();
+
+            let frame_text = match words.next() {
+                Some(word) => word,
+                None => continue,
+            };
+            let (frame, event, parameters) = match (frame_text.parse::<u64>(), words.next(), words.next(), words.next()) {
+                (Ok(frame), Some(event), Some(p0), Some(p1)) => (frame, event, [p0, p1]),
+                _ => return Err(RecordLoadingError::InvalidLine { line }),
+            };
+
+            if events.last().is_some_and(|(last_frame, _)| *last_frame > frame) {
+                return Err(RecordLoadingError::UnorderedFrames { line });
+            }
+
+            let parse_pressed = |parameter: &str| match parameter {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                _ => Err(RecordLoadingError::InvalidParameter { line, parameter: parameter.to_string() }),
+            };
+            let parse_vector = |parameters: [&str; 2]| match (parameters[0].parse::<f32>(), parameters[1].parse::<f32>()) {
+                (Ok(x), Ok(y)) => Ok(Vec2f { x, y }),
+                _ => Err(RecordLoadingError::InvalidParameter { line, parameter: parameters.join(" ") }),
+            };
+
+            let event = match event {
+                "key" => InputEvent::Key {
+                    key: get_key_by_name(parameters[0])
+                        .ok_or_else(|| RecordLoadingError::UnknownKey { line, key: parameters[0].to_string() })?,
+                    pressed: parse_pressed(parameters[1])?,
+                },
+                "button" => InputEvent::MouseButton {
+                    button: match MOUSE_BUTTON_NAMES.iter().find(|(_, name)| *name == parameters[0]) {
+                        Some((button, _)) => *button,
+                        None => MouseButton::Other(parameters[0]
+                            .parse::<u16>()
+                            .map_err(|_| RecordLoadingError::UnknownKey { line, key: parameters[0].to_string() })?
+                        ),
+                    },
+                    pressed: parse_pressed(parameters[1])?,
+                },
+                "move" => InputEvent::MouseMove(parse_vector(parameters)?),
+                "scroll" => InputEvent::Scroll(parse_vector(parameters)?),
+                _ => return Err(RecordLoadingError::UnknownEvent { line, event: event.to_string() }),
+            };
+
+            events.push((frame, event));
+        }
+
+        Ok(Self { frame: 0, next_event: 0, events })
+    } // fn load
+
+    /// Current frame events feeding function, should be called once per frame
+    /// * `input` - input to feed events to
+    pub fn feed(&mut self, input: &mut Input) {
+        while let Some((frame, event)) = self.events.get(self.next_event) {
+            if *frame != self.frame {
+                break;
+            }
+
+            input.apply_event(*event);
+            self.next_event += 1;
+        }
+
+        self.frame += 1;
+    } // fn feed
+
+    /// Playback finish checking function
+    /// * Returns true if all events are played
+    pub fn is_finished(&self) -> bool {
+        self.next_event >= self.events.len()
+    } // fn is_finished
+} // impl Playback
+
 // Input getting function
 pub struct Input {
     state: State,
+    /// Recorder input events are captured to, if any
+    recorder: Option<Recorder>,
 } // struct Input
 
 impl Input {
@@ -85,9 +540,12 @@ impl Input {
         Self {
             state: State {
                 keys: BTreeMap::new(),
+                mouse_buttons: BTreeMap::new(),
                 mouse_location: Vec2f { x: 0.0, y: 0.0 },
                 mouse_motion: Vec2f { x: 0.0, y: 0.0 },
+                scroll_delta: Vec2f { x: 0.0, y: 0.0 },
             },
+            recorder: None,
         }
     } // fn new
 
@@ -95,28 +553,84 @@ impl Input {
     /// * `key` - keycode
     /// * `is_pressed` - changed key state
     pub fn on_key_state_change(&mut self, key: KeyCode, is_pressed: bool) {
-        if let Some(key_state) = self.state.keys.get_mut(&key) {
-            key_state.pressed = is_pressed;
-            key_state.changed = true;
-        } else {
-            self.state.keys.insert(key, KeyState { pressed: is_pressed, changed: true });
-        }
+        self.record(InputEvent::Key { key, pressed: is_pressed });
+        self.state.keys.entry(key).or_insert(KeyState::RELEASED).set_pressed(is_pressed);
     } // fn on_key_state_change
 
+    /// Mouse button state change callback
+    /// * `button` - mouse button
+    /// * `is_pressed` - changed button state
+    pub fn on_mouse_button(&mut self, button: MouseButton, is_pressed: bool) {
+        self.record(InputEvent::MouseButton { button, pressed: is_pressed });
+        self.state.mouse_buttons.entry(button).or_insert(KeyState::RELEASED).set_pressed(is_pressed);
+    } // fn on_mouse_button
+
     /// Mouse motion callback
     /// * `new_position` - new mouse position
     pub fn on_mouse_move(&mut self, new_position: Vec2f) {
+        self.record(InputEvent::MouseMove(new_position));
         self.state.mouse_motion.x += new_position.x - self.state.mouse_location.x;
         self.state.mouse_motion.y += new_position.y - self.state.mouse_location.y;
         self.state.mouse_location = new_position;
     } // fn on_mouse_move
 
+    /// Mouse wheel scroll callback
+    /// * `delta` - scroll delta in lines
+    pub fn on_scroll(&mut self, delta: Vec2f) {
+        self.record(InputEvent::Scroll(delta));
+        self.state.scroll_delta.x += delta.x;
+        self.state.scroll_delta.y += delta.y;
+    } // fn on_scroll
+
+    /// Input event applying function
+    /// * `event` - event to apply
+    pub fn apply_event(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key { key, pressed } => self.on_key_state_change(key, pressed),
+            InputEvent::MouseButton { button, pressed } => self.on_mouse_button(button, pressed),
+            InputEvent::MouseMove(position) => self.on_mouse_move(position),
+            InputEvent::Scroll(delta) => self.on_scroll(delta),
+        }
+    } // fn apply_event
+
+    /// Event recording function
+    /// * `event` - event to record if recording is started
+    fn record(&mut self, event: InputEvent) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(event);
+        }
+    } // fn record
+
+    /// Input recording starting function, previous record is discarded
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder::new());
+    } // fn start_recording
+
+    /// Input recording stopping function
+    /// * Returns recorder with events captured since recording start, if recording was started
+    pub fn stop_recording(&mut self) -> Option<Recorder> {
+        self.recorder.take()
+    } // fn stop_recording
+
+    /// Key (and mouse button) held times updating function, should be called once per frame
+    /// * `dt` - time passed since previous update in seconds
+    pub fn response(&mut self, dt: f32) {
+        for state in self.state.keys.values_mut().chain(self.state.mouse_buttons.values_mut()) {
+            state.held_time = if state.pressed { state.held_time + dt } else { 0.0 };
+        }
+    } // fn response
+
     // Changed parameters clearing function
     pub fn clear_changed(&mut self) {
-        for (_, state) in &mut self.state.keys {
+        for state in self.state.keys.values_mut().chain(self.state.mouse_buttons.values_mut()) {
             state.changed = false;
         }
         self.state.mouse_motion = Vec2f { x: 0.0, y: 0.0 };
+        self.state.scroll_delta = Vec2f { x: 0.0, y: 0.0 };
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.next_frame();
+        }
     } // fn clear_changed
 
     /// State getting function
@@ -124,6 +638,224 @@ impl Input {
     pub fn get_state<'a>(&'a self) -> &'a State {
         &self.state
     } // fn get_state
+
+    /// State snapshot getting function
+    /// * Returns owned copy of current input state, unaffected by further input events
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            state: self.state.clone(),
+        }
+    } // fn snapshot
 } // impl Input
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_is_unaffected_by_later_events() {
+        let mut input = Input::new();
+        input.on_key_state_change(KeyCode::KeyW, true);
+
+        let snapshot = input.snapshot();
+
+        input.on_key_state_change(KeyCode::KeyW, false);
+        input.on_key_state_change(KeyCode::KeyA, true);
+        input.on_mouse_move(Vec2f::new(10.0, 20.0));
+
+        assert!(snapshot.get_state().is_key_pressed(KeyCode::KeyW));
+        assert!(!snapshot.get_state().is_key_pressed(KeyCode::KeyA));
+        assert_eq!(snapshot.get_state().get_mouse_location(), Vec2f::new(0.0, 0.0));
+
+        assert!(!input.get_state().is_key_pressed(KeyCode::KeyW));
+        assert!(input.get_state().is_key_pressed(KeyCode::KeyA));
+    } // fn snapshot_is_unaffected_by_later_events
+
+    /// Test action enumeration
+    #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    enum TestAction {
+        Jump,
+        Fire,
+    } // enum TestAction
+
+    #[test]
+    fn action_is_triggered_by_any_binding() {
+        let mut action_map = ActionMap::new();
+        action_map.bind(TestAction::Jump, KeyCode::Space);
+        action_map.bind(TestAction::Jump, KeyCode::KeyJ);
+        action_map.bind(TestAction::Jump, KeyCode::KeyJ);
+        assert_eq!(action_map.get_bindings(&TestAction::Jump), [Binding::Key(KeyCode::Space), Binding::Key(KeyCode::KeyJ)]);
+
+        for key in [KeyCode::Space, KeyCode::KeyJ] {
+            let mut input = Input::new();
+            assert!(!action_map.is_action_pressed(input.get_state(), TestAction::Jump));
+
+            input.on_key_state_change(key, true);
+            assert!(action_map.is_action_pressed(input.get_state(), TestAction::Jump));
+            assert!(action_map.is_action_clicked(input.get_state(), TestAction::Jump));
+            assert!(!action_map.is_action_pressed(input.get_state(), TestAction::Fire));
+
+            input.clear_changed();
+            assert!(action_map.is_action_pressed(input.get_state(), TestAction::Jump));
+            assert!(!action_map.is_action_clicked(input.get_state(), TestAction::Jump));
+        }
+
+        // Bindings are overridable at runtime
+        let mut input = Input::new();
+        input.on_key_state_change(KeyCode::Space, true);
+        action_map.set_bindings(TestAction::Jump, &[MouseButton::Right.into()]);
+        assert!(!action_map.is_action_pressed(input.get_state(), TestAction::Jump));
+        input.on_mouse_button(MouseButton::Right, true);
+        assert!(action_map.is_action_pressed(input.get_state(), TestAction::Jump));
+
+        action_map.set_bindings(TestAction::Jump, &[]);
+        assert!(action_map.get_bindings(&TestAction::Jump).is_empty());
+        assert!(!action_map.is_action_pressed(input.get_state(), TestAction::Jump));
+    } // fn action_is_triggered_by_any_binding
+
+    #[test]
+    fn key_release_is_reported_on_release_frame_only() {
+        let mut input = Input::new();
+        let just_released = |input: &Input| input.get_state().is_key_just_released(KeyCode::KeyE);
+
+        assert!(!just_released(&input));
+
+        input.on_key_state_change(KeyCode::KeyE, true);
+        assert!(!just_released(&input));
+        input.clear_changed();
+        assert!(!just_released(&input));
+
+        input.on_key_state_change(KeyCode::KeyE, false);
+        assert!(just_released(&input));
+        assert!(!input.get_state().is_key_clicked(KeyCode::KeyE));
+        input.clear_changed();
+        assert!(!just_released(&input));
+    } // fn key_release_is_reported_on_release_frame_only
+
+    #[test]
+    fn held_time_is_accumulated_while_pressed() {
+        let mut input = Input::new();
+        let held_time = |input: &Input| input.get_state().get_key_held_time(KeyCode::KeyW);
+
+        input.on_key_state_change(KeyCode::KeyW, true);
+        input.on_mouse_button(MouseButton::Left, true);
+        for _ in 0..3 {
+            input.response(0.25);
+            input.clear_changed();
+        }
+        assert_eq!(held_time(&input), 0.75);
+        assert_eq!(input.get_state().get_key_held_time(KeyCode::KeyS), 0.0);
+
+        // Repeated press event doesn't restart timer
+        input.on_key_state_change(KeyCode::KeyW, true);
+        input.response(0.25);
+        assert_eq!(held_time(&input), 1.0);
+
+        input.on_key_state_change(KeyCode::KeyW, false);
+        assert_eq!(held_time(&input), 0.0);
+        input.response(0.25);
+        assert_eq!(held_time(&input), 0.0);
+
+        input.on_key_state_change(KeyCode::KeyW, true);
+        input.response(0.5);
+        assert_eq!(held_time(&input), 0.5);
+    } // fn held_time_is_accumulated_while_pressed
+
+    #[test]
+    fn scroll_is_accumulated_until_cleared() {
+        let mut input = Input::new();
+
+        input.on_scroll(Vec2f::new(0.0, 1.0));
+        input.on_scroll(Vec2f::new(0.5, -3.0));
+        assert_eq!(input.get_state().get_scroll_delta(), Vec2f::new(0.5, -2.0));
+
+        input.clear_changed();
+        assert_eq!(input.get_state().get_scroll_delta(), Vec2f::new(0.0, 0.0));
+    } // fn scroll_is_accumulated_until_cleared
+
+    #[test]
+    fn mouse_buttons_are_separate_from_keys() {
+        let mut input = Input::new();
+
+        input.on_mouse_button(MouseButton::Left, true);
+        assert!(input.get_state().is_mouse_button_pressed(MouseButton::Left));
+        assert!(input.get_state().is_mouse_button_clicked(MouseButton::Left));
+        assert!(!input.get_state().is_mouse_button_pressed(MouseButton::Right));
+
+        // Buttons don't collide with function keys they were emulated with
+        assert!(!input.get_state().is_key_pressed(KeyCode::F30));
+        input.on_key_state_change(KeyCode::F31, true);
+        assert!(!input.get_state().is_mouse_button_pressed(MouseButton::Right));
+
+        input.clear_changed();
+        assert!(input.get_state().is_mouse_button_pressed(MouseButton::Left));
+        assert!(!input.get_state().is_mouse_button_clicked(MouseButton::Left));
+
+        input.on_mouse_button(MouseButton::Left, false);
+        assert!(!input.get_state().is_mouse_button_pressed(MouseButton::Left));
+        assert!(!input.get_state().is_mouse_button_clicked(MouseButton::Left));
+    } // fn mouse_buttons_are_separate_from_keys
+
+    #[test]
+    fn key_names_are_unique() {
+        for (key, name) in KEY_NAMES {
+            assert_eq!(get_key_by_name(name), Some(key));
+            assert_eq!(get_key_name(key), Some(name));
+        }
+        assert_eq!(get_key_by_name("NotAKey"), None);
+    } // fn key_names_are_unique
+
+    #[test]
+    fn replayed_record_reproduces_state() {
+        let frames: [&[InputEvent]; 4] = [
+            &[InputEvent::Key { key: KeyCode::KeyW, pressed: true }, InputEvent::MouseMove(Vec2f::new(10.0, 20.0))],
+            &[InputEvent::MouseButton { button: MouseButton::Left, pressed: true }, InputEvent::Scroll(Vec2f::new(0.0, -1.5))],
+            &[],
+            &[
+                InputEvent::Key { key: KeyCode::KeyW, pressed: false },
+                InputEvent::Key { key: KeyCode::ShiftLeft, pressed: true },
+                InputEvent::MouseButton { button: MouseButton::Other(7), pressed: true },
+                InputEvent::MouseMove(Vec2f::new(12.5, 18.0)),
+            ],
+        ];
+
+        // Record synthetic input, states are collected after every frame
+        let mut input = Input::new();
+        let mut recorded_states = Vec::new();
+        input.start_recording();
+        for events in frames {
+            for event in events {
+                input.apply_event(*event);
+            }
+            input.response(0.125);
+            recorded_states.push(input.get_state().clone());
+            input.clear_changed();
+        }
+        let recorder = input.stop_recording().unwrap();
+        assert_eq!(recorder.get_events().len(), 8);
+        assert_eq!(recorder.get_events()[7].0, 3);
+
+        let mut text = Vec::new();
+        recorder.write(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("0 key KeyW 1") && text.contains("1 button Left 1") && text.contains("3 button 7 1"));
+
+        // Both in-memory and loaded from text records reproduce states frame by frame
+        for mut playback in [Playback::from_recorder(&recorder), Playback::load(&text).unwrap()] {
+            let mut replayed = Input::new();
+
+            for state in &recorded_states {
+                playback.feed(&mut replayed);
+                replayed.response(0.125);
+                assert!(replayed.get_state() == state);
+                replayed.clear_changed();
+            }
+            assert!(playback.is_finished());
+        }
+
+        assert_eq!(Playback::load("0 key NotAKey 1").err(), Some(RecordLoadingError::UnknownKey { line: 1, key: "NotAKey".to_string() }));
+        assert_eq!(Playback::load("1 key KeyA 1\n0 key KeyA 0").err(), Some(RecordLoadingError::UnorderedFrames { line: 2 }));
+    } // fn replayed_record_reproduces_state
+} // mod tests
+
 // file input.rs
@@ -0,0 +1,55 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// WEIRD Project
+/// `File` lib.rs
+/// `Description` Numeric core (vector math and fixed-point numbers) library module, usable without `std` feature
+/// `Author` TioT2
+/// `Last changed` 17.10.2026
+
+pub mod util;
+pub mod math;
+
+/// Numeric core tests, `core` only is used, so they're built and run both with and without `std` feature
+#[cfg(test)]
+mod no_std_tests {
+    use crate::math::Vec2;
+    use crate::util::fixed::{self, Angle, Fixed};
+
+    #[test]
+    fn fixed_vector_arithmetic() {
+        let f = Fixed::from_f32;
+        let a = Vec2::<Fixed>::new(f(3.0), f(4.0));
+        let b = Vec2::<Fixed>::new(f(1.5), f(0.5));
+
+        assert_eq!(a + b, Vec2::new(f(4.5), f(4.5)));
+        assert_eq!(a - b, Vec2::new(f(1.5), f(3.5)));
+        assert_eq!(a * b, Vec2::new(f(4.5), f(2.0)));
+        assert_eq!(a / b, Vec2::new(f(2.0), f(8.0)));
+
+        // Dot and cross products
+        assert_eq!(a ^ b, f(6.5));
+        assert_eq!(b % a, f(4.5));
+
+        assert_eq!(a.length(), f(5.0));
+        assert_eq!(a.normalized(), Vec2::new(f(0.6), f(0.8)));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+
+        // Fixed-point trigonometry
+        let right = Angle::from_bits(16384);
+        assert!((right.sin() - f(1.0)).abs() < fixed::EPSILON * Fixed::from_i16(2));
+        assert!(right.cos().abs() < f(0.001));
+        assert!((consts_pi_sqrt() - f(1.7724539)).abs() < f(0.0001));
+    } // fn fixed_vector_arithmetic
+
+    /// PI square root getting function
+    /// * Returns square root of PI, calculated in const context
+    const fn consts_pi_sqrt() -> Fixed {
+        fixed::consts::PI.sqrt()
+    } // fn consts_pi_sqrt
+} // mod no_std_tests
+
+// file lib.rs
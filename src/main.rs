@@ -4,53 +4,565 @@
 /// `Author` TioT2
 /// `Last changed` 04.05.2024
 
-pub mod util;
+pub use weird::util;
 pub mod timer;
 pub mod input;
-pub mod math;
+pub use weird::math;
 pub mod camera;
 pub mod map;
 pub mod font;
 pub mod surface;
+pub mod gif;
+pub mod movement;
+pub mod game;
+pub mod texture;
 
 
 pub mod nmap;
 
-use font::Font;
+use font::FontRegistry;
 use map::*;
 use math::*;
-use surface::Surface;
+use surface::{OwnedSurface, Surface};
 use camera::Camera;
+use texture::{Texture, TextureId};
 
 use input::KeyCode;
 
+/// Player action representation enumeration
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Action {
+    /// Move forward
+    Forward,
+    /// Move backward
+    Backward,
+    /// Turn (or strafe) left
+    Left,
+    /// Turn (or strafe) right
+    Right,
+    /// Move up
+    Up,
+    /// Move down
+    Down,
+    /// Strafe instead of turning
+    Strafe,
+    /// Turn by mouse motion
+    MouseLook,
+} // enum Action
+
+/// Default action bindings getting function
+/// * Returns action map with default key bindings
+fn default_action_map() -> input::ActionMap<Action> {
+    let mut action_map = input::ActionMap::new();
+
+    action_map.bind(Action::Forward, KeyCode::KeyW);
+    action_map.bind(Action::Backward, KeyCode::KeyS);
+    action_map.bind(Action::Left, KeyCode::KeyA);
+    action_map.bind(Action::Right, KeyCode::KeyD);
+    action_map.bind(Action::Up, KeyCode::KeyR);
+    action_map.bind(Action::Down, KeyCode::KeyF);
+    action_map.set_bindings(Action::Strafe, &[KeyCode::AltLeft.into(), KeyCode::AltRight.into()]);
+    action_map.bind(Action::MouseLook, input::MouseButton::Left);
+
+    action_map
+} // fn default_action_map
+
+/// Per-channel color multiplication function
+/// * `lhs`, `rhs` - 0x00RRGGBB colors to multiply
+/// * Returns multiplied color
+fn multiply_colors(lhs: u32, rhs: u32) -> u32 {
+    if rhs == 0xFFFFFF {
+        return lhs;
+    }
+
+    let channel = |shift: u32| (((lhs >> shift) & 0xFF) * ((rhs >> shift) & 0xFF) / 255) << shift;
+
+    channel(16) | channel(8) | channel(0)
+} // fn multiply_colors
+
+/// Color blending function
+/// * `dst` - 0x00RRGGBB color to blend over
+/// * `src` - 0xAARRGGBB color to blend, alpha is in high byte
+/// * Returns blended color
+fn blend_colors(dst: u32, src: u32) -> u32 {
+    surface::blend_color(dst, src, (src >> 24) as u8)
+} // fn blend_colors
+
+/// Default maximal portal depth, deep enough for all sectors of `maps/default.wmt` to be visible
+pub const DEFAULT_MAX_PORTAL_DEPTH: usize = 64;
+
+/// Relative distance from camera to subportal, camera closer to subportal is treated as standing on it
+const NMAP_SUBPORTAL_EPSILON: f32 = 1e-5;
+
+/// Distance fog parameters representation structure
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DistanceFog {
+    /// Color surfaces are blended to
+    pub color: u32,
+    /// Camera space depth fog starts at
+    pub start: f32,
+    /// Camera space depth surfaces are totally covered by fog at
+    pub end: f32,
+} // struct DistanceFog
+
+impl DistanceFog {
+    /// Fog blend color calculation function
+    /// * `depth` - camera space depth of surface
+    /// * Returns 0xAARRGGBB color to blend surface color with (see `blend_colors`), alpha is zero if surface isn't fogged
+    fn get_blend_color(&self, depth: f32) -> u32 {
+        let factor = if self.end > self.start {
+            ((depth - self.start) / (self.end - self.start)).clamp(0.0, 1.0)
+        } else if depth >= self.end {
+            1.0
+        } else {
+            0.0
+        };
+
+        (((factor * 255.0) as u32) << 24) | (self.color & 0xFFFFFF)
+    } // fn get_blend_color
+
+    /// Fog applying function
+    /// * `color` - 0x00RRGGBB surface color
+    /// * `depth` - camera space depth of surface
+    /// * Returns fogged color
+    pub fn apply(&self, color: u32, depth: f32) -> u32 {
+        blend_colors(color, self.get_blend_color(depth))
+    } // fn apply
+} // impl DistanceFog
+
+/// Render parameters representation structure
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderConfig {
+    /// Camera space depth parts of edges behind camera are clipped to
+    pub near_plane: f32,
+    /// Maximal count of portals between camera sector and rendered one, farther portals are rendered as walls
+    pub max_portal_depth: usize,
+    /// Wall colors by portal depth (used for edges without own color), last one is used for deeper sectors, white if empty
+    pub depth_colors: Vec<u32>,
+    /// Enables floor and ceiling texturing, sectors are filled with their flat colors otherwise
+    pub textured_flats: bool,
+    /// Fog surfaces are blended with by their depth, disabled if not set
+    pub distance_fog: Option<DistanceFog>,
+} // struct RenderConfig
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            near_plane: camera::DEFAULT_NEAR_PLANE,
+            max_portal_depth: DEFAULT_MAX_PORTAL_DEPTH,
+            depth_colors: vec![0xAACCAA, 0xCCAAAA, 0xAAAACC, 0xBBBBBB],
+            textured_flats: false,
+            distance_fog: None,
+        }
+    }
+} // impl Default for RenderConfig
+
+/// Billboard sprite representation structure
+#[derive(Copy, Clone)]
+pub struct Sprite {
+    /// Sprite bottom center location, z is height
+    pub location: Vec3f,
+    /// Sprite height, width is determined by texture aspect ratio
+    pub height: f32,
+    /// Sprite texture, added to render by `Render::add_texture`
+    pub texture: TextureId,
+} // struct Sprite
+
 /// Render representation structure
 pub struct Render {
+    /// Color used to fill parts of sectors beyond their render distance
+    pub fog_color: u32,
+    /// Color of screen part above horizon if there's no sector to render from
+    sky_color: u32,
+    /// Color of screen part below horizon if there's no sector to render from
+    ground_color: u32,
+    /// Render parameters
+    config: RenderConfig,
+    /// Textures edges may reference
+    textures: Vec<Texture>,
+    /// Per-column inverse depth of last rendered frame, zero for columns without geometry
+    inv_depth_buffer: Vec<f32>,
 } // struct Render
 
+/// Frame rendering statistics representation structure
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Count of sector renderings (sector visible through several portals is counted several times)
+    pub sectors_visited: usize,
+    /// Count of edges located (at least partially) in front of camera
+    pub edges_drawn: usize,
+    /// Maximal count of portals between camera sector and rendered one
+    pub max_depth: usize,
+    /// Count of rendered screen columns of all edges
+    pub columns_filled: usize,
+} // struct RenderStats
+
+/// Sector rendering work item
+enum RenderTask {
+    /// Render sector in screen x range
+    Sector {
+        sector_id: SectorId,
+        screen_x_begin: usize,
+        screen_x_end: usize,
+        tint: u32,
+    },
+    /// All sector neighbours are rendered, sector may be removed from visit stack
+    Leave,
+} // enum RenderTask
+
+/// Wall column texture mapping, texture is repeated every world unit
+struct WallTexturing<'t> {
+    /// Wall texture
+    texture: &'t Texture,
+    /// Horizontal texture coordinate of column
+    u: f32,
+    /// Vertical texture coordinate of screen row 0
+    v_origin: f32,
+    /// Vertical texture coordinate increment per screen row
+    v_step: f32,
+    /// Color texels are multiplied by
+    tint: u32,
+    /// 0xAARRGGBB fog color texels are blended with
+    fog: u32,
+} // struct WallTexturing
+
+/// Floor or ceiling column per-pixel shading (texture mapping and fog), texture is repeated every world unit
+struct FlatShading<'t> {
+    /// Surface texture, surface is filled with color if not set
+    texture: Option<&'t Texture>,
+    /// Fog pixels are blended with by their depth
+    fog: Option<&'t DistanceFog>,
+    /// Camera location
+    origin: Vec2f,
+    /// Column view direction, scaled to have unit camera space depth
+    direction: Vec2f,
+    /// Camera height above surface multiplied by vertical projection scale
+    scaled_height: f32,
+    /// Camera horizon shift
+    horizon_shift: f32,
+    /// Screen height in pixels
+    screen_height: usize,
+    /// Color texels are multiplied by
+    tint: u32,
+} // struct FlatShading
+
+/// Translucent surface, rendered in second pass over opaque geometry
+struct TranslucentSpan {
+    /// Nearest surface depth, used for back-to-front sorting
+    depth: f32,
+    /// 0xAARRGGBB surface color
+    color: u32,
+    /// Covered columns: (screen x, first row, row after last)
+    columns: Vec<(usize, usize, usize)>,
+} // struct TranslucentSpan
+
+/// Single screen column of edge rendering parameters, heights are projected to screen rows
+struct EdgeColumn<'t> {
+    /// Sector ceiling row
+    ceil_y: isize,
+    /// Sector floor row
+    floor_y: isize,
+    /// Neighbour sector ceiling and floor rows, set if edge is rendered as portal
+    neighbour_y: Option<(isize, isize)>,
+    /// Sector ceiling and floor rows at render distance, set if edge is beyond it
+    render_distance_y: Option<(isize, isize)>,
+    /// Wall color, used if wall isn't textured
+    wall_color: u32,
+    /// Ceiling color, used if ceiling isn't shaded
+    ceil_color: u32,
+    /// Floor color, used if floor isn't shaded
+    floor_color: u32,
+    /// Wall texture mapping
+    texturing: Option<WallTexturing<'t>>,
+    /// Ceiling per-pixel shading
+    ceiling_shading: Option<FlatShading<'t>>,
+    /// Floor per-pixel shading
+    floor_shading: Option<FlatShading<'t>>,
+} // struct EdgeColumn
+
 struct RenderContext<'a, 'b> where 'b: 'a {
     surface: &'a mut Surface<'b>,
     map: &'a Map,
     camera: &'a Camera,
     visit_stack: std::collections::VecDeque<SectorId>,
+    task_stack: Vec<RenderTask>,
     floor_buffer: &'a mut [usize],
     ceil_buffer: &'a mut [usize],
     inv_depth_buffer: &'a mut [f32],
+    translucent_spans: Vec<TranslucentSpan>,
+    max_portal_depth: usize,
+    config: &'a RenderConfig,
+    textures: &'a [Texture],
+    fog_color: u32,
+    stats: RenderStats,
 } // struct RenderContext
 
+/// Subsector map rendering work item
+enum NmapRenderTask {
+    /// Render subsector in screen x range
+    Subsector {
+        sector_id: u32,
+        subsector_id: u32,
+        screen_x_begin: usize,
+        screen_x_end: usize,
+        /// Count of sector portals between camera subsector and this one
+        depth: usize,
+    },
+    /// All subsector neighbours are rendered, subsector may be removed from visit stack
+    Leave,
+} // enum NmapRenderTask
+
+struct NmapRenderContext<'a, 'b> where 'b: 'a {
+    surface: &'a mut Surface<'b>,
+    map: &'a nmap::Map,
+    camera: &'a Camera,
+    visit_stack: Vec<(u32, u32)>,
+    task_stack: Vec<NmapRenderTask>,
+    floor_buffer: &'a mut [usize],
+    ceil_buffer: &'a mut [usize],
+    inv_depth_buffer: &'a mut [f32],
+    config: &'a RenderConfig,
+    stats: RenderStats,
+} // struct NmapRenderContext
+
 impl Render {
     /// Render create function
     pub fn new() -> Render {
+        Self::with_config(RenderConfig::default())
+    } // fn new
+
+    /// Render with custom parameters create function
+    /// * `config` - render parameters
+    pub fn with_config(config: RenderConfig) -> Render {
         Render {
+            fog_color: 0x000000,
+            sky_color: 0x87CEEB,
+            ground_color: 0x556B2F,
+            config,
+            textures: Vec::new(),
+            inv_depth_buffer: Vec::new(),
         }
-    } // fn new
+    } // fn with_config
+
+    /// Texture adding function
+    /// * `texture` - texture to add
+    /// * Returns identifier edges may reference texture by
+    pub fn add_texture(&mut self, texture: Texture) -> TextureId {
+        self.textures.push(texture);
+        TextureId::new(self.textures.len() as u32 - 1)
+    } // fn add_texture
+
+    /// Texture getting function
+    /// * `id` - texture identifier
+    /// * Returns texture or None if there's no texture with such identifier
+    pub fn get_texture(&self, id: TextureId) -> Option<&Texture> {
+        self.textures.get(id.as_u32() as usize)
+    } // fn get_texture
+
+    /// Inverse depth buffer getting function
+    /// * Returns per-column inverse camera space depth of nearest full-column geometry of last rendered frame (see `render_sprite`)
+    pub fn get_inv_depth_buffer(&self) -> &[f32] {
+        &self.inv_depth_buffer
+    } // fn get_inv_depth_buffer
+
+    /// Render parameters getting function
+    /// * Returns render parameters
+    pub fn get_config(&self) -> &RenderConfig {
+        &self.config
+    } // fn get_config
+
+    /// Maximal portal depth setting function
+    /// * `max_portal_depth` - maximal count of portals between camera sector and rendered one
+    pub fn set_max_portal_depth(&mut self, max_portal_depth: usize) {
+        self.config.max_portal_depth = max_portal_depth;
+    } // fn set_max_portal_depth
+
+    /// Sky color setting function
+    /// * `color` - color to fill screen part above horizon with if there's no sector to render from
+    pub fn set_sky_color(&mut self, color: u32) {
+        self.sky_color = color;
+    } // fn set_sky_color
+
+    /// Ground color setting function
+    /// * `color` - color to fill screen part below horizon with if there's no sector to render from
+    pub fn set_ground_color(&mut self, color: u32) {
+        self.ground_color = color;
+    } // fn set_ground_color
+
+    /// Wall column span filling function
+    /// * `p_begin` - pointer to first pixel of span
+    /// * `p_end` - pointer to pixel after span
+    /// * `stride` - surface stride
+    /// * `y_begin` - screen row of first span pixel
+    /// * `color` - wall color, used if wall isn't textured
+    /// * `texturing` - wall texture mapping
+    /// * Returns `p_end`
+    unsafe fn fill_wall_span(p_begin: *mut u32, p_end: *mut u32, stride: usize, y_begin: usize, color: u32, texturing: Option<&WallTexturing>) -> *mut u32 {
+        let mut p_current = p_begin;
+
+        match texturing {
+            None => {
+                while p_current < p_end {
+                    *p_current = color;
+                    p_current = p_current.add(stride);
+                }
+            }
+            Some(texturing) => {
+                let mut v = texturing.v_origin + y_begin as f32 * texturing.v_step;
+
+                while p_current < p_end {
+                    let color = multiply_colors(texturing.texture.sample(texturing.u, v), texturing.tint);
+
+                    *p_current = match texturing.fog >> 24 {
+                        0 => color,
+                        _ => blend_colors(color, texturing.fog),
+                    };
+                    v += texturing.v_step;
+                    p_current = p_current.add(stride);
+                }
+            }
+        }
+
+        p_end.max(p_begin)
+    } // fn fill_wall_span
+
+    /// Floor or ceiling column span filling function. Surface point is reconstructed from screen row by inverse of `project_height`.
+    /// * `p_begin` - pointer to first pixel of span
+    /// * `p_end` - pointer to pixel after span
+    /// * `stride` - surface stride
+    /// * `y_begin` - screen row of first span pixel
+    /// * `color` - surface color, used if surface isn't textured
+    /// * `shading` - surface per-pixel shading, span is filled with `color` if not set
+    /// * Returns `p_end`
+    unsafe fn fill_flat_span(p_begin: *mut u32, p_end: *mut u32, stride: usize, y_begin: usize, color: u32, shading: Option<&FlatShading>) -> *mut u32 {
+        let mut p_current = p_begin;
+
+        match shading {
+            None => {
+                while p_current < p_end {
+                    *p_current = color;
+                    p_current = p_current.add(stride);
+                }
+            }
+            Some(shading) => {
+                let mut y = y_begin;
+
+                while p_current < p_end {
+                    // Row center in [-1, 1] range, so its ratio to scaled height is inverse distance to surface point
+                    let row = (y as f32 + 0.5) / shading.screen_height as f32 * 2.0 - 1.0 - shading.horizon_shift;
+                    let depth = shading.scaled_height / row;
+
+                    let color = match shading.texture {
+                        Some(texture) => {
+                            let point = shading.origin + shading.direction * depth;
+                            multiply_colors(texture.sample(point.x, point.y), shading.tint)
+                        }
+                        None => color,
+                    };
+
+                    *p_current = match shading.fog {
+                        Some(fog) => fog.apply(color, depth),
+                        None => color,
+                    };
+                    y += 1;
+                    p_current = p_current.add(stride);
+                }
+            }
+        }
+
+        p_end.max(p_begin)
+    } // fn fill_flat_span
+
+    /// Edge screen column filling function. Column part between visible rows is filled with ceiling, wall (or portal borders) and floor.
+    /// * `p_base` - pointer to column pixel of screen row 0
+    /// * `stride` - surface stride
+    /// * `buf_ceil` - first visible column row, set to portal window start (or to `buf_floor` if column is closed)
+    /// * `buf_floor` - row after last visible one, set to portal window end
+    /// * `fog_color` - color of column part beyond render distance
+    /// * `column` - column rendering parameters
+    /// * Returns portal window rows if column is rendered as portal
+    unsafe fn fill_column(p_base: *mut u32, stride: usize, buf_ceil: &mut usize, buf_floor: &mut usize, fog_color: u32, column: &EdgeColumn) -> Option<(usize, usize)> {
+        if let Some((ceil_cap_y, floor_cap_y)) = column.render_distance_y {
+            let ceil_cap_y = ceil_cap_y.clamp(*buf_ceil as isize, *buf_floor as isize) as usize;
+            let floor_cap_y = floor_cap_y.clamp(ceil_cap_y as isize, *buf_floor as isize) as usize;
+
+            let p_ceil_cap = p_base.add(stride * ceil_cap_y);
+            let p_floor_cap = p_base.add(stride * floor_cap_y);
+            let p_end = p_base.add(stride * *buf_floor);
+
+            let mut p_current = p_base.add(stride * *buf_ceil);
+
+            p_current = Self::fill_flat_span(p_current, p_ceil_cap, stride, *buf_ceil, column.ceil_color, column.ceiling_shading.as_ref());
+
+            while p_current < p_floor_cap {
+                *p_current = fog_color;
+                p_current = p_current.add(stride);
+            }
+
+            Self::fill_flat_span(p_current, p_end, stride, floor_cap_y, column.floor_color, column.floor_shading.as_ref());
+
+            // Close column for neighbour sectors
+            *buf_ceil = *buf_floor;
+            return None;
+        }
+
+        let ceil_y = column.ceil_y.clamp(*buf_ceil as isize, *buf_floor as isize) as usize;
+        let floor_y = column.floor_y.clamp(*buf_ceil as isize, *buf_floor as isize) as usize;
+
+        let p_ceil = p_base.add(stride * ceil_y);
+        let p_floor = p_base.add(stride * floor_y);
+        let p_end = p_base.add(stride * *buf_floor);
+
+        let mut p_current = p_base.add(stride * *buf_ceil);
+        let mut window = None;
+
+        // Ceiling
+        p_current = Self::fill_flat_span(p_current, p_ceil, stride, *buf_ceil, column.ceil_color, column.ceiling_shading.as_ref());
+
+        if let Some((neighbour_ceil_y, neighbour_floor_y)) = column.neighbour_y {
+            // Render neighbour borders
+            let neighbour_ceil_y = neighbour_ceil_y.clamp(ceil_y as isize, floor_y as isize) as usize;
+            let neighbour_floor_y = neighbour_floor_y.clamp(ceil_y as isize, floor_y as isize) as usize;
+
+            let p_neighbour_ceil = p_base.add(stride * neighbour_ceil_y);
+            let p_neighbour_floor = p_base.add(stride * neighbour_floor_y);
+
+            // Upper wall
+            Self::fill_wall_span(p_current, p_neighbour_ceil, stride, ceil_y, column.wall_color, column.texturing.as_ref());
+
+            // Skip portal
+            p_current = p_neighbour_floor;
+
+            // Set hints for inner rendering
+            *buf_ceil = neighbour_ceil_y;
+            *buf_floor = neighbour_floor_y;
+            window = Some((neighbour_ceil_y, neighbour_floor_y));
 
-    /// Sector rendering function
+            // Lower wall
+            p_current = Self::fill_wall_span(p_current, p_floor, stride, neighbour_floor_y, column.wall_color, column.texturing.as_ref());
+        } else {
+            // Middle block
+            p_current = Self::fill_wall_span(p_current, p_floor, stride, ceil_y, column.wall_color, column.texturing.as_ref());
+        }
+
+        // Floor
+        Self::fill_flat_span(p_current, p_end, stride, floor_y, column.floor_color, column.floor_shading.as_ref());
+
+        // Close wall column, so farther geometry overlapping it in screen space can't overdraw it
+        if window.is_none() {
+            *buf_ceil = *buf_floor;
+        }
+
+        window
+    } // fn fill_column
+
+    /// Sector rendering function. Neighbour sectors aren't rendered, but scheduled to context task stack.
     /// * `context` - render context
     /// * `sector_id` - sector to render identifier
     /// * `screen_x_begin` - screen x clipping area start
     /// * `screen_x_end` - screen x clipping area end
-    fn render_sector(context: &mut RenderContext, sector_id: SectorId, screen_x_begin: usize, screen_x_end: usize) {
+    /// * `tint` - color all sector colors are multiplied by
+    fn render_sector(context: &mut RenderContext, sector_id: SectorId, screen_x_begin: usize, screen_x_end: usize, tint: u32) {
         let ext = context.surface.get_extent();
         let stride = context.surface.get_stride();
         let sector = match context.map.get_sector(sector_id) {
@@ -58,58 +570,67 @@ impl Render {
             None => return,
         };
 
-        'edge_loop: for edge in &sector.edges {
-            let mut p0 = context.camera.to_space(edge.p0);
-            let mut p1 = context.camera.to_space(edge.p1);
-
-            if p0.x > p1.x {
-                let tmp = p1;
-                p1 = p0;
-                p0 = tmp;
-            }
-
-            // Check for x or y visibility and clamp'em if not
-            if p0.y <= 0.0 {
-                if p1.y <= 0.0 {
-                    // Clip edge if totally invisible
-                    continue 'edge_loop;
-                } else {
-                    p0 = Vec2f {
-                        x: p0.x - p0.y * (p1.x - p0.x) / (p1.y - p0.y),
-                        y: 0.001,
-                    };
-                }
-            } else if p1.y <= 0.0 {
-                p1 = Vec2f {
-                    x: p0.x - p0.y * (p1.x - p0.x) / (p1.y - p0.y),
-                    y: 0.001,
-                };
-            }
+        context.stats.sectors_visited += 1;
+        context.stats.max_depth = context.stats.max_depth.max(context.visit_stack.len());
 
-            let to_screen_x = |p: Vec2f| -> isize {
-                ((p.x / p.y * 0.5 + 0.5) * ext.w as f32) as isize
-            };
+        // Visible portals: (nearest depth, destination sector, screen x start, screen x end, tint)
+        let mut portals = Vec::<(f32, SectorId, usize, usize, u32)>::new();
 
+        // Last transformed vertex, edges of loop share them, so there's no need to transform them twice
+        let mut last_vertex: Option<(Vec2f, Vec2f)> = None;
 
-            let (xp0, xp1) = unsafe {
-                let x0 = std::mem::transmute::<isize, usize>(to_screen_x(p0).clamp(screen_x_begin as isize, screen_x_end as isize));
-                let x1 = std::mem::transmute::<isize, usize>(to_screen_x(p1).clamp(screen_x_begin as isize, screen_x_end as isize));
+        // Projection constants don't depend on edge, so they're calculated once per sector
+        let vertical_scale = camera::get_vertical_scale(ext, context.camera.get_tan_half_fov());
+        let horizon_shift = context.camera.get_horizon_shift(ext);
 
-                if x0 > x1 {
-                    (x1, x0)
-                } else {
-                    (x0, x1)
-                }
+        // Inverse sector render distance, zero if sector isn't limited by distance
+        let inv_render_distance = sector.render_distance.map_or(0.0, |distance| 1.0 / distance);
+
+        let (floor_texture, ceiling_texture) = match context.config.textured_flats {
+            true => (
+                sector.floor_texture.and_then(|id| context.textures.get(id.as_u32() as usize)),
+                sector.ceiling_texture.and_then(|id| context.textures.get(id.as_u32() as usize)),
+            ),
+            false => (None, None),
+        };
+
+        'edge_loop: for edge in &sector.edges {
+            let p0 = match last_vertex {
+                Some((world, space)) if world == edge.p0 => space,
+                _ => context.camera.to_space(edge.p0),
             };
+            let p1 = context.camera.to_space(edge.p1);
+            last_vertex = Some((edge.p1, p1));
+
+            // Unclipped edge, used for texture mapping
+            let (edge_space_p0, edge_space_p1) = (p0, p1);
 
-            let (color, floor_color, ceil_color) = match context.visit_stack.len() {
-                0 => (0xAACCAA, 0xDDFFDD, 0x779977),
-                1 => (0xCCAAAA, 0xFFDDDD, 0x997777),
-                2 => (0xAAAACC, 0xDDDDFF, 0x777799),
-                _ => (0xBBBBBB, 0xEEEEEE, 0x888888),
+            // Clip edge if totally invisible
+            let (p0, p1) = match camera::clip_edge_to_view(p0, p1, context.config.near_plane) {
+                Some(points) => points,
+                None => continue 'edge_loop,
             };
 
-            // Edge normal and distance form user to edge
+            let (xp0, xp1) = camera::project_span(p0, p1, context.camera.get_tan_half_fov(), ext.w, screen_x_begin, screen_x_end);
+
+            context.stats.edges_drawn += 1;
+            context.stats.columns_filled += xp1 - xp0;
+
+            let color = edge.color.unwrap_or_else(|| context.config.depth_colors
+                .get(context.visit_stack.len())
+                .or(context.config.depth_colors.last())
+                .copied()
+                .unwrap_or(0xFFFFFF)
+            );
+            let (floor_color, ceil_color) = (sector.floor_color, sector.ceiling_color);
+            let (color, floor_color, ceil_color) = (
+                multiply_colors(color, tint),
+                multiply_colors(floor_color, tint),
+                multiply_colors(ceil_color, tint),
+            );
+
+            // Edge normal and distance form user to edge. They're taken from camera space points and depend on camera,
+            // so they aren't cached on edge: world space normal rotated into camera space rounds differently.
             let (edge_norm, inv_edge_distance) = {
                 let edge_norm = Vec2f {
                     x: p1.y - p0.y,
@@ -119,168 +640,655 @@ impl Render {
                 (edge_norm, 1.0 / (edge_norm ^ p0).abs())
             };
 
+            // Portals without vertical overlap with neighbour or beyond maximal depth are rendered as walls
             let neighbour_bounds = match edge.ty {
-                EdgeType::Portal{dst_sector_id} => context.map
+                EdgeType::Portal{dst_sector_id} if context.visit_stack.len() < context.max_portal_depth => context.map
                     .get_sector(dst_sector_id)
-                    .map(|neighbour_sector| (neighbour_sector.floor, neighbour_sector.ceiling)),
-                EdgeType::Wall => None,
+                    .map(|neighbour_sector| (neighbour_sector.floor, neighbour_sector.ceiling))
+                    .filter(|(neighbour_floor, neighbour_ceiling)| neighbour_floor.max(sector.floor) < neighbour_ceiling.min(sector.ceiling)),
+                _ => None,
             };
 
             let surface_data_ptr = context.surface.get_data_mut().as_mut_ptr();
 
+            let project_height = |height: f32, inv_distance: f32| -> isize {
+                camera::project_height(context.camera.height, height, inv_distance * vertical_scale, horizon_shift, ext.h)
+            };
+
+            let texture = edge.texture.and_then(|id| context.textures.get(id.as_u32() as usize));
+            let edge_length = edge.length();
+            let texture_origin = edge.texture_align.get_origin(sector.floor, sector.ceiling);
+
+            // Translucent portal overlay, its columns are collected during opaque pass
+            let mut translucent_span = match (edge.portal_attributes.overlay, neighbour_bounds) {
+                (Some(overlay), Some(_)) => Some(TranslucentSpan {
+                    depth: p0.y.min(p1.y),
+                    color: (overlay & 0xFF000000) | multiply_colors(overlay & 0xFFFFFF, tint),
+                    columns: Vec::new(),
+                }),
+                _ => None,
+            };
+
             for x in xp0..xp1 {
-                let pixel_dir = Vec2f {
-                    x: x as f32 / ext.w as f32 * 2.0 - 1.0,
-                    y: 1.0,
-                };
+                // Column is already closed by nearer wall
+                if context.ceil_buffer[x] >= context.floor_buffer[x] {
+                    continue;
+                }
+
+                // Pixel direction y is 1, so it's omitted
+                let pixel_dir_x = (x as f32 / ext.w as f32 * 2.0 - 1.0) * context.camera.get_tan_half_fov();
 
                 // Inverse distance to pixel
-                let inv_distance = (pixel_dir.x * edge_norm.x + pixel_dir.y * edge_norm.y).abs() * inv_edge_distance;
+                let inv_distance = (pixel_dir_x * edge_norm.x + edge_norm.y).abs() * inv_edge_distance;
 
-                let to_screen_height = |height: f32| -> usize {
-                    ((((context.camera.height - height) * inv_distance + 1.0) / 2.0 * ext.h as f32) as isize).clamp(0, ext.h as isize) as usize
+                let to_screen_height = |height: f32| -> isize {
+                    project_height(height, inv_distance)
+                };
+
+                // Screen rows are mapped to world heights by inverse of `project_height`
+                // Wall depth is constant along column, so fog is applied to its color once
+                let wall_fog = context.config.distance_fog.map_or(0, |fog| fog.get_blend_color(1.0 / inv_distance));
+                let color = match wall_fog >> 24 {
+                    0 => color,
+                    _ => blend_colors(color, wall_fog),
+                };
+
+                let texturing = texture.map(|texture| {
+                    let depth = 1.0 / inv_distance;
+                    let point = Vec2f { x: pixel_dir_x * depth, y: depth };
+                    let row_inv_distance = inv_distance * vertical_scale;
+                    let v_step = 2.0 / (ext.h as f32 * row_inv_distance);
+
+                    WallTexturing {
+                        texture,
+                        u: ((point - edge_space_p0) ^ (edge_space_p1 - edge_space_p0)) / edge_length,
+                        v_origin: texture_origin - context.camera.height - (1.0 + horizon_shift) / row_inv_distance + 0.5 * v_step,
+                        v_step,
+                        tint,
+                        fog: wall_fog,
+                    }
+                });
+
+                let camera = context.camera;
+                let fog = context.config.distance_fog.as_ref();
+                let flat_shading = |texture: Option<_>, height: f32| (texture.is_some() || fog.is_some()).then(|| FlatShading {
+                    texture,
+                    fog,
+                    origin: camera.location,
+                    direction: camera.direction + camera.right * pixel_dir_x,
+                    scaled_height: (camera.height - height) * vertical_scale,
+                    horizon_shift,
+                    screen_height: ext.h,
+                    tint,
+                });
+                let floor_shading = flat_shading(floor_texture, sector.floor);
+                let ceiling_shading = flat_shading(ceiling_texture, sector.ceiling);
+
+                // Edge is beyond render distance, so only near ceiling/floor parts are drawn and rest is filled with fog
+                let beyond_render_distance = inv_distance < inv_render_distance;
+
+                let column = EdgeColumn {
+                    ceil_y: to_screen_height(sector.ceiling),
+                    floor_y: to_screen_height(sector.floor),
+                    neighbour_y: neighbour_bounds.map(|(neighbour_floor, neighbour_ceiling)| (to_screen_height(neighbour_ceiling), to_screen_height(neighbour_floor))),
+                    render_distance_y: beyond_render_distance.then(|| (project_height(sector.ceiling, inv_render_distance), project_height(sector.floor, inv_render_distance))),
+                    wall_color: color,
+                    ceil_color,
+                    floor_color,
+                    texturing,
+                    ceiling_shading,
+                    floor_shading,
                 };
 
                 unsafe {
-                    let buf_floor = context.floor_buffer.get_unchecked_mut(x);
-                    let buf_ceil = context.ceil_buffer.get_unchecked_mut(x);
+                    *context.inv_depth_buffer.get_unchecked_mut(x) = if beyond_render_distance { inv_render_distance } else { inv_distance };
+
+                    let window = Self::fill_column(
+                        surface_data_ptr.add(x),
+                        stride,
+                        context.ceil_buffer.get_unchecked_mut(x),
+                        context.floor_buffer.get_unchecked_mut(x),
+                        context.fog_color,
+                        &column,
+                    );
+
+                    if let (Some((window_ceil_y, window_floor_y)), Some(span)) = (window, translucent_span.as_mut()) {
+                        span.columns.push((x, window_ceil_y, window_floor_y));
+                    }
+                }
+            }
 
-                    let ceil_y = to_screen_height(sector.ceiling).clamp(*buf_ceil, *buf_floor);
-                    let floor_y = to_screen_height(sector.floor).clamp(*buf_ceil, *buf_floor);
+            if let Some(span) = translucent_span {
+                if !span.columns.is_empty() {
+                    context.translucent_spans.push(span);
+                }
+            }
 
-                    let p_base = surface_data_ptr.add(x);
-                    let p_ceil = p_base.add(stride * ceil_y);
-                    let p_floor = p_base.add(stride * floor_y);
-                    let p_end = p_base.add(stride * *buf_floor);
+            // Defer neighbour rendering until all sector edges are drawn
+            if let EdgeType::Portal{ dst_sector_id } = edge.ty {
+                if xp1 - xp0 > 0 && neighbour_bounds.is_some() {
+                    let portal_tint = edge.portal_attributes.tint.map_or(tint, |portal_tint| multiply_colors(tint, portal_tint));
+                    portals.push((p0.y.min(p1.y), dst_sector_id, xp0, xp1, portal_tint));
+                }
+            };
 
-                    let mut p_current = p_base.add(stride * *buf_ceil);
+        } // 'edge_loop
 
-                    *context.inv_depth_buffer.get_unchecked_mut(x) = inv_distance;
+        // Schedule neighbours front-to-back, so nearer portals are resolved first
+        portals.sort_by(|l, r| l.0.total_cmp(&r.0));
+
+        context.visit_stack.push_back(sector_id);
+        context.task_stack.push(RenderTask::Leave);
+        for (_, dst_sector_id, xp0, xp1, portal_tint) in portals.into_iter().rev() {
+            if !context.visit_stack.contains(&dst_sector_id) {
+                context.task_stack.push(RenderTask::Sector {
+                    sector_id: dst_sector_id,
+                    screen_x_begin: xp0,
+                    screen_x_end: xp1,
+                    tint: portal_tint,
+                });
+            }
+        }
+    } // fn render_sector
 
-                    // Ceiling
-                    while p_current < p_ceil {
-                        *p_current = ceil_color;
-                        p_current = p_current.add(stride);
-                    }
+    /// Subsector map subsector rendering function
+    /// * `context` - rendering context
+    /// * `sector_id` - index of sector subsector belongs to
+    /// * `subsector_id` - index of subsector to render
+    /// * `screen_x_begin`, `screen_x_end` - screen column range to render subsector in
+    /// * `depth` - count of sector portals between camera subsector and this one
+    fn render_nmap_subsector(context: &mut NmapRenderContext, sector_id: u32, subsector_id: u32, screen_x_begin: usize, screen_x_end: usize, depth: usize) {
+        let ext = context.surface.get_extent();
+        let stride = context.surface.get_stride();
+        let sector = match context.map.sectors.get(sector_id as usize) {
+            Some(sector) => sector,
+            None => return,
+        };
+        let subsector = match sector.subsectors.get(subsector_id as usize) {
+            Some(subsector) => subsector,
+            None => return,
+        };
 
-                    if let Some((neighbour_floor, neighbour_ceiling)) = neighbour_bounds {
-                        // Render neighbour borders
-                        let neighbour_ceil_y = to_screen_height(neighbour_ceiling).clamp(ceil_y, floor_y);
-                        let neighbour_floor_y = to_screen_height(neighbour_floor).clamp(ceil_y, floor_y);
+        context.stats.sectors_visited += 1;
+        context.stats.max_depth = context.stats.max_depth.max(depth);
 
-                        let p_neighbour_ceil = p_base.add(stride * neighbour_ceil_y);
-                        let p_neighbour_floor = p_base.add(stride * neighbour_floor_y);
+        // Visible neighbours: (nearest depth, destination sector, destination subsector, screen x start, screen x end, destination depth)
+        let mut neighbours = Vec::<(f32, u32, u32, usize, usize, usize)>::new();
 
-                        // Upper wall
-                        while p_current < p_neighbour_ceil {
-                            *p_current = color;
-                            p_current = p_current.add(stride);
-                        }
+        let color = context.config.depth_colors
+            .get(depth)
+            .or(context.config.depth_colors.last())
+            .copied()
+            .unwrap_or(0xFFFFFF);
 
-                        // Skip portal
-                        p_current = p_neighbour_floor;
+        let vertical_scale = camera::get_vertical_scale(ext, context.camera.get_tan_half_fov());
+        let horizon_shift = context.camera.get_horizon_shift(ext);
 
-                        // Set hints for inner rendering
-                        *buf_ceil = neighbour_ceil_y;
-                        *buf_floor = neighbour_floor_y;
+        for edge in &subsector.edges {
+            let edge_p0 = edge.position;
+            let edge_p1 = edge.position + edge.direction;
 
-                        // Lower wall
-                        while p_current < p_floor {
-                            *p_current = color;
-                            p_current = p_current.add(stride);
-                        }
-                    } else {
-                        // Middle block
-                        while p_current < p_floor {
-                            *p_current = color;
-                            p_current = p_current.add(stride);
-                        }
+            // Subportal camera stands on is projected to nothing, but neighbour subsector is visible through all the columns
+            if let nmap::EdgeType::Subportal { subsector_id } = edge.ty {
+                let camera_offset = context.camera.location - edge_p0;
+                let edge_length2 = edge.direction.length2();
+                let along = edge.direction ^ camera_offset;
 
-                        // Don't actually need it, rendering stops at this point
-                            // *buf_ceil = ceil_y;
-                            // *buf_floor = floor_y;
-                    }
+                if (edge.direction % camera_offset).abs() <= NMAP_SUBPORTAL_EPSILON * edge_length2 && along >= 0.0 && along <= edge_length2 {
+                    neighbours.push((0.0, sector_id, subsector_id, screen_x_begin, screen_x_end, depth));
+                    continue;
+                }
+            }
+
+            let (p0, p1) = match camera::clip_edge_to_view(context.camera.to_space(edge_p0), context.camera.to_space(edge_p1), context.config.near_plane) {
+                Some(points) => points,
+                None => continue,
+            };
+
+            let (xp0, xp1) = camera::project_span(p0, p1, context.camera.get_tan_half_fov(), ext.w, screen_x_begin, screen_x_end);
+
+            if xp0 >= xp1 {
+                continue;
+            }
+
+            // Subportals split single sector, so there's nothing to draw on them, columns are just passed to neighbour subsector
+            let neighbour = match edge.ty {
+                nmap::EdgeType::Subportal { subsector_id } => {
+                    neighbours.push((p0.y.min(p1.y), sector_id, subsector_id, xp0, xp1, depth));
+                    continue;
+                }
+                nmap::EdgeType::Portal { sector_id: dst_sector_id } if depth < context.config.max_portal_depth => context.map.sectors
+                    .get(dst_sector_id as usize)
+                    .filter(|neighbour_sector| neighbour_sector.floor.max(sector.floor) < neighbour_sector.ceiling.min(sector.ceiling))
+                    .and_then(|neighbour_sector| neighbour_sector
+                        .find_subsector((edge_p0 + edge_p1) * 0.5)
+                        .map(|dst_subsector_id| (dst_sector_id, dst_subsector_id, neighbour_sector.floor, neighbour_sector.ceiling))
+                    ),
+                _ => None,
+            };
+
+            context.stats.edges_drawn += 1;
+            context.stats.columns_filled += xp1 - xp0;
 
+            // Edge normal and distance form user to edge
+            let (edge_norm, inv_edge_distance) = {
+                let edge_norm = Vec2f {
+                    x: p1.y - p0.y,
+                    y: p0.x - p1.x,
+                }.normalized();
+
+                (edge_norm, 1.0 / (edge_norm ^ p0).abs())
+            };
+
+            let surface_data_ptr = context.surface.get_data_mut().as_mut_ptr();
+
+            for x in xp0..xp1 {
+                // Column is already closed by nearer wall
+                if context.ceil_buffer[x] >= context.floor_buffer[x] {
+                    continue;
+                }
+
+                // Pixel direction y is 1, so it's omitted
+                let pixel_dir_x = (x as f32 / ext.w as f32 * 2.0 - 1.0) * context.camera.get_tan_half_fov();
+
+                // Inverse distance to pixel
+                let inv_distance = (pixel_dir_x * edge_norm.x + edge_norm.y).abs() * inv_edge_distance;
+
+                let to_screen_height = |height: f32| -> isize {
+                    camera::project_height(context.camera.height, height, inv_distance * vertical_scale, horizon_shift, ext.h)
+                };
+
+                let column = EdgeColumn {
+                    ceil_y: to_screen_height(sector.ceiling),
+                    floor_y: to_screen_height(sector.floor),
+                    neighbour_y: neighbour.map(|(_, _, neighbour_floor, neighbour_ceiling)| (to_screen_height(neighbour_ceiling), to_screen_height(neighbour_floor))),
+                    render_distance_y: None,
+                    wall_color: color,
+                    ceil_color: DEFAULT_CEILING_COLOR,
+                    floor_color: DEFAULT_FLOOR_COLOR,
+                    texturing: None,
+                    ceiling_shading: None,
+                    floor_shading: None,
+                };
+
+                unsafe {
                     *context.inv_depth_buffer.get_unchecked_mut(x) = inv_distance;
 
-                    // Floor
-                    while p_current < p_end {
-                        *p_current = floor_color;
-                        p_current = p_current.add(stride);
-                    }
+                    Self::fill_column(
+                        surface_data_ptr.add(x),
+                        stride,
+                        context.ceil_buffer.get_unchecked_mut(x),
+                        context.floor_buffer.get_unchecked_mut(x),
+                        0x000000,
+                        &column,
+                    );
                 }
             }
 
-            // Deferred neighbour rendering
-            if let EdgeType::Portal{ dst_sector_id } = edge.ty {
-                context.visit_stack.push_back(sector_id);
+            if let Some((dst_sector_id, dst_subsector_id, _, _)) = neighbour {
+                neighbours.push((p0.y.min(p1.y), dst_sector_id, dst_subsector_id, xp0, xp1, depth + 1));
+            }
+        }
 
-                if !context.visit_stack.contains(&dst_sector_id) {
-                    if xp1 - xp0 > 0 {
-                        Self::render_sector(context, dst_sector_id, xp0, xp1);
-                    }
+        // Schedule neighbours front-to-back, so nearer ones are resolved first
+        neighbours.sort_by(|l, r| l.0.total_cmp(&r.0));
+
+        context.visit_stack.push((sector_id, subsector_id));
+        context.task_stack.push(NmapRenderTask::Leave);
+        for (_, dst_sector_id, dst_subsector_id, xp0, xp1, dst_depth) in neighbours.into_iter().rev() {
+            if !context.visit_stack.contains(&(dst_sector_id, dst_subsector_id)) {
+                context.task_stack.push(NmapRenderTask::Subsector {
+                    sector_id: dst_sector_id,
+                    subsector_id: dst_subsector_id,
+                    screen_x_begin: xp0,
+                    screen_x_end: xp1,
+                    depth: dst_depth,
+                });
+            }
+        }
+    } // fn render_nmap_subsector
+
+    /// Subsector map frame rendering function. Map has no colors and textures, so walls are colored by portal depth and flats by default sector colors.
+    /// * `surface` - surface to render frame to
+    /// * `map` - map to render
+    /// * `camera` - camera to render map from
+    /// * `sector_id` - index of sector camera is located in
+    /// * `subsector_id` - index of camera sector subsector camera is located in
+    /// * Returns frame rendering statistics, every subsector rendering is counted as sector visit
+    pub fn render_nmap(&mut self, surface: &mut Surface, map: &nmap::Map, camera: &Camera, sector_id: u32, subsector_id: u32) -> RenderStats {
+        let ext = surface.get_extent();
+        let mut floor_buffer = vec![ext.h; ext.w];
+        let mut ceil_buffer = vec![0usize; ext.w];
+        let mut inv_depth_buffer = std::mem::take(&mut self.inv_depth_buffer);
+        inv_depth_buffer.clear();
+        inv_depth_buffer.resize(ext.w, 0f32);
+
+        let mut context = NmapRenderContext {
+            surface,
+            map,
+            camera,
+            visit_stack: Vec::new(),
+            task_stack: vec![NmapRenderTask::Subsector {
+                sector_id,
+                subsector_id,
+                screen_x_begin: 0,
+                screen_x_end: ext.w,
+                depth: 0,
+            }],
+            floor_buffer: &mut floor_buffer,
+            ceil_buffer: &mut ceil_buffer,
+            inv_depth_buffer: &mut inv_depth_buffer,
+            config: &self.config,
+            stats: RenderStats::default(),
+        };
+
+        while let Some(task) = context.task_stack.pop() {
+            match task {
+                NmapRenderTask::Subsector { sector_id, subsector_id, screen_x_begin, screen_x_end, depth } => {
+                    Self::render_nmap_subsector(&mut context, sector_id, subsector_id, screen_x_begin, screen_x_end, depth);
+                }
+                NmapRenderTask::Leave => {
+                    context.visit_stack.pop();
                 }
+            }
+        }
 
-                context.visit_stack.pop_back();
-            };
+        let stats = context.stats;
 
-        } // 'edge_loop
-    } // fn render_sector
+        // Keep depth buffer for sprite rendering
+        self.inv_depth_buffer = inv_depth_buffer;
+
+        stats
+    } // fn render_nmap
 
     /// Next frame rendering function
     /// `surface` - surface to render frame to
     /// `map` - map to render
     /// `sector_id` - id of sector to start rendering from
-    pub fn render(&mut self, surface: &mut Surface, map: &Map, camera: &Camera, sector_id: SectorId) {
+    /// `max_portal_depth` - maximal count of portals between camera sector and rendered one (farther portals are rendered as walls), `RenderConfig::max_portal_depth` is used if None
+    /// * Returns frame rendering statistics
+    pub fn render(&mut self, surface: &mut Surface, map: &Map, camera: &Camera, sector_id: SectorId, max_portal_depth: Option<usize>) -> RenderStats {
+        self.render_with_sprites(surface, map, camera, sector_id, max_portal_depth, &[])
+    } // fn render
+
+    /// Next frame with sprites rendering function. Opaque geometry is rendered first, then translucent portals and sprites are rendered back-to-front over it.
+    /// * `surface` - surface to render frame to
+    /// * `map` - map to render
+    /// * `camera` - camera to render map from
+    /// * `sector_id` - id of sector to start rendering from
+    /// * `max_portal_depth` - maximal count of portals between camera sector and rendered one, `RenderConfig::max_portal_depth` is used if None
+    /// * `sprites` - sprites to render, sprites with textures unknown to render are skipped
+    /// * Returns frame rendering statistics
+    pub fn render_with_sprites(&mut self, surface: &mut Surface, map: &Map, camera: &Camera, sector_id: SectorId, max_portal_depth: Option<usize>, sprites: &[Sprite]) -> RenderStats {
         // Render only if sector actually exists
         if map.get_sector(sector_id).is_some() {
             let ext = surface.get_extent();
-            let mut floor_buffer = {
-                let mut buffer = Vec::with_capacity(ext.w);
-                buffer.resize(ext.w, ext.h);
-                buffer
-            };
-            let mut ceil_buffer = {
-                let mut buffer = Vec::with_capacity(ext.w);
-                buffer.resize(ext.w, 0usize);
-                buffer
-            };
-            let mut inv_depth_buffer = {
-                let mut buffer = Vec::with_capacity(ext.w);
-                buffer.resize(ext.w, 0f32);
-                buffer
-            };
+            let mut floor_buffer = vec![ext.h; ext.w];
+            let mut ceil_buffer = vec![0usize; ext.w];
+            let mut inv_depth_buffer = std::mem::take(&mut self.inv_depth_buffer);
+            inv_depth_buffer.clear();
+            inv_depth_buffer.resize(ext.w, 0f32);
 
             let mut context = RenderContext {
                 map,
                 camera,
                 visit_stack: std::collections::VecDeque::new(),
+                task_stack: vec![RenderTask::Sector {
+                    sector_id,
+                    screen_x_begin: 0,
+                    screen_x_end: ext.w,
+                    tint: 0xFFFFFF,
+                }],
                 floor_buffer: &mut floor_buffer,
                 ceil_buffer: &mut ceil_buffer,
                 inv_depth_buffer: &mut inv_depth_buffer,
-                surface,
+                translucent_spans: Vec::new(),
+                max_portal_depth: max_portal_depth.unwrap_or(self.config.max_portal_depth),
+                config: &self.config,
+                textures: &self.textures,
+                fog_color: self.fog_color,
+                stats: RenderStats::default(),
+                surface: &mut *surface,
             };
 
-            Self::render_sector(&mut context, sector_id, 0, ext.w);
+            while let Some(task) = context.task_stack.pop() {
+                match task {
+                    RenderTask::Sector { sector_id, screen_x_begin, screen_x_end, tint } => {
+                        Self::render_sector(&mut context, sector_id, screen_x_begin, screen_x_end, tint);
+                    }
+                    RenderTask::Leave => {
+                        context.visit_stack.pop_back();
+                    }
+                }
+            }
+
+            let RenderContext { translucent_spans, stats, .. } = context;
+
+            // Translucent pass: all opaque geometry is rendered and depth buffer is filled, so translucent portals and sprites are rendered over it back-to-front
+            enum TranslucentItem<'t> {
+                Span(&'t TranslucentSpan),
+                Sprite(&'t Sprite),
+            }
+
+            let mut items = translucent_spans
+                .iter()
+                .map(|span| (span.depth, TranslucentItem::Span(span)))
+                .chain(sprites
+                    .iter()
+                    .map(|sprite| (camera.to_space(Vec2f { x: sprite.location.x, y: sprite.location.y }).y, TranslucentItem::Sprite(sprite)))
+                )
+                .collect::<Vec<_>>();
+            items.sort_by(|l, r| r.0.total_cmp(&l.0));
+
+            for (_, item) in items {
+                match item {
+                    TranslucentItem::Span(span) => {
+                        let stride = surface.get_stride();
+                        let surface_data = surface.get_data_mut();
+
+                        for &(x, y_begin, y_end) in &span.columns {
+                            for y in y_begin..y_end {
+                                let pixel = &mut surface_data[y * stride + x];
+                                *pixel = blend_colors(*pixel, span.color);
+                            }
+                        }
+                    }
+                    TranslucentItem::Sprite(sprite) => if let Some(texture) = self.get_texture(sprite.texture) {
+                        self.render_sprite(surface, camera, &inv_depth_buffer, sprite.location, sprite.height, texture);
+                    }
+                }
+            }
+
+            // Keep depth buffer for sprite rendering
+            self.inv_depth_buffer = inv_depth_buffer;
+
+            stats
+        } else {
+            // Render horizon instead of leaving stale frame
+            let ext = surface.get_extent();
+
+            self.inv_depth_buffer.clear();
+            self.inv_depth_buffer.resize(ext.w, 0f32);
+
+            let horizon_y = (((camera.get_horizon_shift(ext) + 1.0) / 2.0 * ext.h as f32) as isize).clamp(0, ext.h as isize);
+
+            surface.draw_bar(0, 0, ext.w as isize, horizon_y, self.sky_color);
+            surface.draw_bar(0, horizon_y, ext.w as isize, ext.h as isize, self.ground_color);
+
+            RenderStats::default()
+        }
+    } // fn render_with_sprites
+
+    /// Billboard sprite rendering function. Sprite always faces camera, its columns behind geometry of depth buffer are skipped.
+    /// * `surface` - surface to render sprite to
+    /// * `camera` - camera to render sprite from
+    /// * `inv_depth_buffer` - per-column inverse depth buffer of rendered frame (e.g. `get_inv_depth_buffer` after `render`)
+    /// * `location` - sprite bottom center location, z is height
+    /// * `height` - sprite height, width is determined by texture aspect ratio
+    /// * `texture` - sprite texture
+    pub fn render_sprite(&self, surface: &mut Surface, camera: &Camera, inv_depth_buffer: &[f32], location: Vec3f, height: f32, texture: &Texture) {
+        let ext = surface.get_extent();
+        let stride = surface.get_stride();
+        let base = Vec2f { x: location.x, y: location.y };
+        let center = camera.to_space(base);
+
+        if height <= 0.0 {
+            return;
         }
-    } // fn next_frame
+
+        let inv_distance = 1.0 / center.y;
+        let half_width = camera.right * (height * texture.get_width() as f32 / texture.get_height() as f32 * 0.5);
+
+        // Unclamped sprite screen bounds, sprite is culled by near plane the same way as walls are
+        let (Some((x_begin, y_end)), Some((x_end, y_begin))) = (
+            camera.world_to_screen_with_height(base - half_width, location.z, ext, self.config.near_plane),
+            camera.world_to_screen_with_height(base + half_width, location.z + height, ext, self.config.near_plane),
+        ) else {
+            return;
+        };
+        let (x_begin, x_end, y_begin, y_end) = (x_begin as f32, x_end as f32, y_begin as f32, y_end as f32);
+
+        let fog = self.config.distance_fog.map_or(0, |fog| fog.get_blend_color(center.y));
+
+        let column_range = (x_begin.max(0.0) as usize)..(x_end.clamp(0.0, ext.w.min(inv_depth_buffer.len()) as f32) as usize);
+        let row_range = (y_begin.max(0.0) as usize)..(y_end.clamp(0.0, ext.h as f32) as usize);
+        let surface_data = surface.get_data_mut();
+
+        for x in column_range {
+            // Column is occluded by nearer geometry
+            if inv_depth_buffer[x] > inv_distance {
+                continue;
+            }
+
+            let u = (x as f32 + 0.5 - x_begin) / (x_end - x_begin);
+
+            for y in row_range.clone() {
+                let v = (y as f32 + 0.5 - y_begin) / (y_end - y_begin);
+                let color = texture.sample(u, v);
+
+                surface_data[y * stride + x] = match fog >> 24 {
+                    0 => color,
+                    _ => blend_colors(color, fog),
+                };
+            }
+        }
+    } // fn render_sprite
+
+    /// Portal visibility flood function, sector set matches one `render` traverses (without per-pixel occlusion)
+    /// * `map` - map to flood sectors of
+    /// * `camera` - camera to flood sectors from
+    /// * `sector_id` - camera sector identifier
+    /// * Returns reached sectors with their portal depths in traversal (front-to-back) order
+    pub fn flood_sectors(&self, map: &Map, camera: &Camera, sector_id: SectorId) -> Vec<(SectorId, usize)> {
+        enum FloodTask {
+            Sector { sector_id: SectorId, x_begin: f32, x_end: f32 },
+            Leave,
+        }
+
+        let mut reached = Vec::new();
+        let mut visit_stack = Vec::<SectorId>::new();
+        let mut task_stack = vec![FloodTask::Sector { sector_id, x_begin: -1.0, x_end: 1.0 }];
+
+        while let Some(task) = task_stack.pop() {
+            let (sector_id, x_begin, x_end) = match task {
+                FloodTask::Sector { sector_id, x_begin, x_end } => (sector_id, x_begin, x_end),
+                FloodTask::Leave => {
+                    visit_stack.pop();
+                    continue;
+                }
+            };
+
+            let sector = match map.get_sector(sector_id) {
+                Some(sector) => sector,
+                None => continue,
+            };
+
+            reached.push((sector_id, visit_stack.len()));
+
+            // Visible portals: (nearest depth, destination sector, screen x start, screen x end)
+            let mut portals = Vec::<(f32, SectorId, f32, f32)>::new();
+
+            for edge in &sector.edges {
+                let dst_sector_id = match edge.ty {
+                    EdgeType::Portal { dst_sector_id } => dst_sector_id,
+                    EdgeType::Wall => continue,
+                };
+
+                // Portals without vertical overlap with neighbour or beyond maximal depth aren't traversed
+                if visit_stack.len() >= self.config.max_portal_depth {
+                    continue;
+                }
+                match map.get_sector(dst_sector_id) {
+                    Some(neighbour) if neighbour.floor.max(sector.floor) < neighbour.ceiling.min(sector.ceiling) => {}
+                    _ => continue,
+                }
+
+                let (p0, p1) = match camera::clip_edge_to_view(camera.to_space(edge.p0), camera.to_space(edge.p1), self.config.near_plane) {
+                    Some(points) => points,
+                    None => continue,
+                };
+
+                let x0 = (p0.x / p0.y / camera.get_tan_half_fov()).clamp(x_begin, x_end);
+                let x1 = (p1.x / p1.y / camera.get_tan_half_fov()).clamp(x_begin, x_end);
+
+                if x0 != x1 {
+                    portals.push((p0.y.min(p1.y), dst_sector_id, x0.min(x1), x0.max(x1)));
+                }
+            }
+
+            portals.sort_by(|l, r| l.0.total_cmp(&r.0));
+
+            visit_stack.push(sector_id);
+            task_stack.push(FloodTask::Leave);
+            for (_, dst_sector_id, x_begin, x_end) in portals.into_iter().rev() {
+                if !visit_stack.contains(&dst_sector_id) {
+                    task_stack.push(FloodTask::Sector { sector_id: dst_sector_id, x_begin, x_end });
+                }
+            }
+        }
+
+        reached
+    } // fn flood_sectors
 
     /// Next frame rendering function
     /// `surface` - surface to render frame to
     /// `map` - map to render
     pub fn render_minimap(&mut self, surface: &mut Surface, map: &Map, camera: &Camera, camera_sector: SectorId) {
         let ext = surface.get_extent();
-        let mut render_sector = |sector: &Sector, color_scale: u32| {
+
+        let to_minimap = |p: Vec2f| -> (isize, isize) {
+            let p = camera.to_space(p);
+            (ext.w as isize / 2 + (p.x * 6.0) as isize, ext.h as isize / 2 - (p.y * 6.0) as isize)
+        };
+
+        // Fill sectors visible from camera back-to-front, sectors are convex, so fan triangulation is enough
+        for (sector_id, depth) in self.flood_sectors(map, camera, camera_sector).into_iter().rev() {
+            let sector = match map.get_sector(sector_id) {
+                Some(sector) => sector,
+                None => continue,
+            };
+            let color = (0x40 >> depth.min(3)) * 0x010101;
+
+            if let Some((first, rest)) = sector.edges.split_first() {
+                let origin = to_minimap(first.p0);
+
+                for edge in rest {
+                    surface.fill_triangle(origin, to_minimap(edge.p0), to_minimap(edge.p1), color);
+                }
+            }
+        }
+
+        let mut render_sector = |sector: &Sector, intensity: u8| {
             for edge in &sector.edges {
                 // Calculate edge projection
                 let p0 = camera.to_space(edge.p0);
                 let p1 = camera.to_space(edge.p1);
 
-                // Project edge to pixel space and render, actually
-                let edge_color = match edge.ty {
-                    EdgeType::Wall => 0x001100,
-                    EdgeType::Portal { .. } => 0x110000,
-                } * color_scale;
+                // Project edge to pixel space and render, actually (scale channels separately to keep them from wrapping)
+                let edge_color = surface::blend_color(0x000000, match edge.ty {
+                    EdgeType::Wall => 0x00FF00,
+                    EdgeType::Portal { .. } => 0xFF0000,
+                }, intensity);
 
                 surface.draw_line(
                     ext.w as isize / 2 + (p0.x * 6.0) as isize,
@@ -308,9 +1316,9 @@ impl Render {
             ;
 
             for sector in adjacent_sectors {
-                render_sector(sector, 6);
+                render_sector(sector, MINIMAP_ADJACENT_EDGE_INTENSITY);
             }
-            render_sector(sector, 15);
+            render_sector(sector, MINIMAP_CURRENT_EDGE_INTENSITY);
         }
 
         // Render player
@@ -321,8 +1329,96 @@ impl Render {
     } // impl fn render_minimap
 } // impl Render
 
+/// Minimap buffer color that isn't copied to screen
+const MINIMAP_TRANSPARENT_COLOR: u32 = 0xFF00FF;
+
+/// Minimap background color, blended over screen
+const MINIMAP_BACKGROUND_COLOR: u32 = 0x000000;
+
+/// Minimap background opacity
+const MINIMAP_BACKGROUND_ALPHA: u8 = 0x80;
+
+/// Minimap edge color intensity for sectors adjacent to camera one
+const MINIMAP_ADJACENT_EDGE_INTENSITY: u8 = 0x66;
+
+/// Minimap edge color intensity for sector camera is located in
+const MINIMAP_CURRENT_EDGE_INTENSITY: u8 = 0xFF;
+
+/// Maximal frame rate of main loop, 0 means no limit
+const TARGET_FPS: f32 = 0.0;
+
+/// Count of logical pixels of touchpad scroll treated as single mouse wheel line
+const SCROLL_PIXELS_PER_LINE: f32 = 16.0;
+
+/// Headless single frame rendering function
+/// * `map_path` - path to WMT map to render
+/// * `output_path` - path to write image to, BMP is written if extension is `bmp` and PPM otherwise
+/// * `width`, `height` - image size
+/// * Returns Ok if image is written, error description otherwise
+fn render_to_file(map_path: &str, output_path: &str, width: usize, height: usize) -> Result<(), String> {
+    let map_source = std::fs::read_to_string(map_path).map_err(|error| format!("can't read map '{}': {}", map_path, error))?;
+    let map = Map::load_from_wmt(map_source.as_str()).map_err(|error| format!("can't load map '{}': {}", map_path, error))?;
+
+    let mut camera = Camera::new();
+    camera.set_location(map.camera_location, map.camera_height, map.camera_rotation);
+    let camera_sector_id = map.find_sector(camera.location).ok_or("camera is located outside of map sectors".to_string())?;
+
+    let mut buffer = vec![0u32; width * height];
+    let mut surface = Surface::new(&mut buffer, width, height, width);
+
+    Render::new().render(&mut surface, &map, &camera, camera_sector_id, None);
+
+    let file = std::fs::File::create(output_path).map_err(|error| format!("can't create '{}': {}", output_path, error))?;
+    let mut writer = std::io::BufWriter::new(file);
+
+    let is_bmp = std::path::Path::new(output_path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("bmp"));
+
+    if is_bmp {
+        surface.write_bmp(&mut writer)
+    } else {
+        surface.write_ppm(&mut writer)
+    }.and_then(|_| std::io::Write::flush(&mut writer)).map_err(|error| format!("can't write '{}': {}", output_path, error))
+} // fn render_to_file
+
+/// Screenshot writing function
+/// * `surface` - surface to write
+/// * `directory` - directory to write screenshot to
+/// * `time` - time screenshot is taken at, it's part of file name to avoid collisions
+/// * Returns path of written PPM file, IO error otherwise
+fn write_screenshot(surface: &Surface, directory: &std::path::Path, time: f32) -> std::io::Result<std::path::PathBuf> {
+    let path = directory.join(format!("screenshot_{}.ppm", time));
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(&path)?);
+
+    surface.write_ppm(&mut writer)?;
+    std::io::Write::flush(&mut writer)?;
+
+    Ok(path)
+} // fn write_screenshot
+
 /// Main program function
 fn main() {
+    // Headless mode: weird --render <map.wmt> <output.ppm|output.bmp> [width] [height]
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(String::as_str) == Some("--render") {
+        let (map_path, output_path) = match (args.get(2), args.get(3)) {
+            (Some(map_path), Some(output_path)) => (map_path, output_path),
+            _ => {
+                eprintln!("usage: {} --render <map.wmt> <output.ppm|output.bmp> [width] [height]", args[0]);
+                std::process::exit(2);
+            }
+        };
+        let width = args.get(4).and_then(|arg| arg.parse::<usize>().ok()).unwrap_or(800);
+        let height = args.get(5).and_then(|arg| arg.parse::<usize>().ok()).unwrap_or(600);
+
+        if let Err(error) = render_to_file(map_path, output_path, width, height) {
+            eprintln!("render failed: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let event_loop = winit::event_loop::EventLoop::new().unwrap();
     let screen_size = winit::dpi::PhysicalSize::<u32>::new(800, 600);
     let window = winit::window::WindowBuilder::new()
@@ -359,35 +1455,34 @@ fn main() {
 
     let mut camera = Camera::new();
 
-    camera.set_location(map.camera_location, 0.5, map.camera_rotation);
-    let mut camera_sector_id = map.find_sector(camera.location).unwrap();
+    camera.set_location(map.camera_location, map.camera_height, map.camera_rotation);
+    let camera_sector_id = map.find_sector(camera.location).unwrap();
+    let mut game = game::Game::new(camera, camera_sector_id);
 
     let mut render = Render::new();
 
     let mut timer = timer::Timer::new();
     let mut input = input::Input::new();
+    let action_map = default_action_map();
+
+    let fonts = FontRegistry::new();
+    let font = fonts.get_default();
 
-    let font = Font::default();
+    let mut gif_recorder: Option<gif::GifRecorder> = None;
+    let mut minimap = OwnedSurface::new(0, 0);
 
     event_loop.run(|event, target| {
         match event {
-            winit::event::Event::DeviceEvent { device_id: _, event } => {
-                match event {
-                    winit::event::DeviceEvent::Button { button, state } => {
-                        let key_code = match button {
-                            0 => Some(KeyCode::F30),
-                            1 => Some(KeyCode::F31),
-                            2 => Some(KeyCode::F32),
-                            _ => None,
-                        };
-                        if let Some(key_code) = key_code {
-                            input.on_key_state_change(key_code, state == winit::event::ElementState::Pressed);
-                        }
-                    }
-                    _ => {}
-                }
+            winit::event::Event::DeviceEvent { device_id: _, event: winit::event::DeviceEvent::Button { button, state } } => {
+                let button = match button {
+                    0 => input::MouseButton::Left,
+                    1 => input::MouseButton::Right,
+                    2 => input::MouseButton::Middle,
+                    _ => input::MouseButton::Other(button as u16),
+                };
+                input.on_mouse_button(button, state == winit::event::ElementState::Pressed);
             }
-            winit::event::Event::WindowEvent { window_id, event } => if window.id() == window_id {
+            winit::event::Event::WindowEvent { window_id, event } if window.id() == window_id => {
                 match event {
                     winit::event::WindowEvent::CloseRequested => {
                         target.exit();
@@ -403,6 +1498,19 @@ fn main() {
                             y: motion.y,
                         });
                     }
+                    winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                        input.on_scroll(match delta {
+                            winit::event::MouseScrollDelta::LineDelta(x, y) => Vec2f { x, y },
+                            winit::event::MouseScrollDelta::PixelDelta(position) => {
+                                let position: winit::dpi::LogicalPosition<f32> = position.to_logical(window.scale_factor());
+
+                                Vec2f {
+                                    x: position.x / SCROLL_PIXELS_PER_LINE,
+                                    y: position.y / SCROLL_PIXELS_PER_LINE,
+                                }
+                            }
+                        });
+                    }
                     winit::event::WindowEvent::Resized(size) => {
                         surface_size = size;
                         if let Some((width, height)) = surface_size.width.try_into().ok().zip(surface_size.height.try_into().ok()) {
@@ -411,6 +1519,7 @@ fn main() {
                     }
                     winit::event::WindowEvent::RedrawRequested => 'redraw: {
                         timer.response();
+                        input.response(timer.get_delta_time());
 
                         let mut mut_buffer = match surface.buffer_mut() {
                             Ok(buffer) => buffer,
@@ -446,66 +1555,42 @@ fn main() {
                             }
                         }
 
-                        'input_control: {
-                            let input = input.get_state();
-                            let dt = timer.get_delta_time();
+                        if input.get_state().is_key_clicked(KeyCode::F3) {
+                            game.free_fly = !game.free_fly;
+                        }
 
-                            let ox = (input.is_key_pressed(KeyCode::KeyA) as i32 - input.is_key_pressed(KeyCode::KeyD) as i32) as f32 +
-                                input.get_mouse_motion().x * (input.is_key_pressed(KeyCode::F30)) as i32 as f32;
-                            let oy = (input.is_key_pressed(KeyCode::KeyW) as i32 - input.is_key_pressed(KeyCode::KeyS) as i32) as f32;
-                            let oz = (input.is_key_pressed(KeyCode::KeyR) as i32 - input.is_key_pressed(KeyCode::KeyF) as i32) as f32;
-                            let strafe = input.is_key_pressed(KeyCode::AltLeft) || input.is_key_pressed(KeyCode::AltRight);
+                        {
+                            let input = input.get_state();
 
-                            if ox == 0.0 && oy == 0.0 && oz == 0.0 {
-                                break 'input_control;
-                            }
+                            let axis = |positive: Action, negative: Action| (action_map.is_action_pressed(input, positive) as i32 - action_map.is_action_pressed(input, negative) as i32) as f32;
 
-                            let mut camera_location_delta = Vec2f {
-                                x: camera.direction.x * oy * 3.0,
-                                y: camera.direction.y * oy * 3.0,
-                            };
-                            let mut new_rotation = camera.rotation;
-                            if strafe {
-                                camera_location_delta.x -= camera.right.x * ox * 3.0;
-                                camera_location_delta.y -= camera.right.y * ox * 3.0;
-                            } else {
-                                new_rotation += ox * 2.0 * dt;
-                            }
-                            let new_height = camera.height + oz * 3.0 * dt;
+                            let ox = axis(Action::Left, Action::Right);
+                            let oy = axis(Action::Forward, Action::Backward);
+                            let oz = axis(Action::Up, Action::Down);
+                            let strafe = action_map.is_action_pressed(input, Action::Strafe);
 
-                            let new_location = Vec2f {
-                                x: camera.location.x + camera_location_delta.x * dt,
-                                y: camera.location.y + camera_location_delta.y * dt,
+                            let intent = movement::MovementIntent {
+                                forward: oy,
+                                left: if strafe { ox } else { 0.0 },
+                                turn: if strafe { 0.0 } else { ox },
+                                up: oz,
                             };
 
-                            // Fixed DT For proper check
-                            let new_test_location = Vec2f {
-                                x: camera.location.x + camera_location_delta.x * 0.01,
-                                y: camera.location.y + camera_location_delta.y * 0.01,
-                            };
+                            // Mouse look is applied once per frame, so it doesn't depend on count of fixed steps done during it
+                            if action_map.is_action_pressed(input, Action::MouseLook) {
+                                let rotation = game.camera.rotation + input.get_mouse_motion().x * game.movement.config.turn_speed * timer.get_delta_time();
+                                game.camera.set_location(game.camera.location, game.camera.height, rotation);
+                            }
 
-                            if let Some(new_camera_sector_id) = map.find_adjacent_sector(new_test_location, camera_sector_id) {
-                                let new_camera_sector = map.get_sector(new_camera_sector_id).unwrap();
-
-                                if camera_sector_id == new_camera_sector_id {
-                                    camera.set_location(
-                                        new_location,
-                                        new_height.clamp(new_camera_sector.floor, new_camera_sector.ceiling),
-                                        new_rotation,
-                                    );
-                                } else {
-                                    if camera.height >= new_camera_sector.floor && camera.height <= new_camera_sector.ceiling {
-                                        camera.set_location(
-                                            new_location,
-                                            new_height.clamp(new_camera_sector.floor, new_camera_sector.ceiling),
-                                            new_rotation,
-                                        );
-                                        camera_sector_id = new_camera_sector_id;
-                                    }
-                                }
+                            // Movement and collisions are simulated with fixed time step
+                            for _ in 0..timer.accumulate() {
+                                game.update(&map, &intent, timer.get_fixed_dt());
                             }
                         }
 
+                        let camera = &game.camera;
+                        let camera_sector_id = game.sector_id;
+
                         let mut_buffer_slice = unsafe {
                             std::slice::from_raw_parts_mut(mut_buffer.as_mut_ptr(), mut_buffer.len())
                         };
@@ -530,31 +1615,85 @@ fn main() {
                             surface_size.width as usize,
                             surface_size.height as usize,
                             surface_size.width as usize,
-                        ), &map, &camera, camera_sector_id);
+                        ), &map, camera, camera_sector_id, None);
 
-                        let mut minimap_surface = Surface::new(
-                            mut_buffer_slice,
-                            surface_size.width as usize / 3,
-                            surface_size.height as usize / 3,
-                            surface_size.width as usize,
-                        );
+                        // Minimap is rendered to its own buffer and composited over top left frame corner
+                        let minimap_extent = Ext2 { w: surface_size.width as usize / 3, h: surface_size.height as usize / 3 };
+                        if minimap.get_extent() != minimap_extent {
+                            minimap = OwnedSurface::new(minimap_extent.w, minimap_extent.h);
+                        }
 
-                        // Render minimap on subframe
-                        // TODO: Fix minimap itself & it's style
-                        render.render_minimap(&mut minimap_surface, &map, &camera, camera_sector_id);
+                        let mut minimap_surface = minimap.as_surface();
+                        minimap_surface.clear(MINIMAP_TRANSPARENT_COLOR);
+                        render.render_minimap(&mut minimap_surface, &map, camera, camera_sector_id);
 
                         let font_size = font.get_letter_size();
-                        font.put_string(&mut minimap_surface, 4, (font_size.h + 1) * 0 + 4, format!("FPS: {}", timer.get_fps()).as_str(), 0xFFFFFF);
-                        font.put_string(&mut minimap_surface, 4, (font_size.h + 1) * 1 + 4, format!("X: {}", camera.location.x).as_str(), 0xFFFFFF);
-                        font.put_string(&mut minimap_surface, 4, (font_size.h + 1) * 2 + 4, format!("Y: {}", camera.location.y).as_str(), 0xFFFFFF);
-                        font.put_string(&mut minimap_surface, 4, (font_size.h + 1) * 3 + 4, format!("H: {}", camera.height    ).as_str(), 0xFFFFFF);
-                        font.put_string(&mut minimap_surface, 4, (font_size.h + 1) * 4 + 4, format!("R: {}", camera.rotation  ).as_str(), 0xFFFFFF);
-                        font.put_string(&mut minimap_surface, 4, (font_size.h + 1) * 5 + 4, format!("Sector: {}", camera_sector_id.as_u32()).as_str(), 0xFFFFFF);
+                        let hud_lines = [
+                            format!("X: {}", camera.location.x),
+                            format!("Y: {}", camera.location.y),
+                            format!("H: {}", camera.height),
+                            format!("R: {}", camera.rotation),
+                            format!("Sector: {}", camera_sector_id.as_u32()),
+                            format!("HP: {}", game.health.ceil()),
+                        ];
+                        for (index, line) in hud_lines.iter().enumerate() {
+                            font.put_string_bg(&mut minimap_surface, 4, (font_size.h + 1) * index + 4, line, 0xFFFFFF, 0x000000);
+                        }
+
+                        {
+                            let mut screen = Surface::new(
+                                mut_buffer_slice,
+                                surface_size.width as usize,
+                                surface_size.height as usize,
+                                surface_size.width as usize,
+                            );
+
+                            screen.draw_bar_blended(0, 0, minimap_extent.w as isize, minimap_extent.h as isize, MINIMAP_BACKGROUND_COLOR, MINIMAP_BACKGROUND_ALPHA);
+                            screen.blit_keyed(&minimap_surface, 0, 0, MINIMAP_TRANSPARENT_COLOR);
+
+                            // FPS counter is right-aligned to top right frame corner
+                            let fps_line = format!("FPS: {}", timer.get_fps());
+                            let fps_line_size = font.measure_string(fps_line.as_str());
+                            font.put_string(&mut screen, (surface_size.width as usize).saturating_sub(fps_line_size.w + 4), 4, fps_line.as_str(), 0xFFFFFF);
+                        }
+
+                        // Record clip while F9 is held, write it on release
+                        if input.get_state().is_key_pressed(KeyCode::F9) {
+                            gif_recorder
+                                .get_or_insert_with(|| gif::GifRecorder::new(surface_size.width as usize, surface_size.height as usize))
+                                .push_frame(&Surface::new(
+                                    mut_buffer_slice,
+                                    surface_size.width as usize,
+                                    surface_size.height as usize,
+                                    surface_size.width as usize,
+                                ), timer.get_delta_time());
+                        } else if let Some(recorder) = gif_recorder.take() {
+                            let file_name = format!("record_{}.gif", timer.get_time());
+
+                            if let Err(error) = std::fs::File::create(&file_name).and_then(|file| recorder.finish(&mut std::io::BufWriter::new(file))) {
+                                eprintln!("Error writing {}: {}", file_name, error);
+                            }
+                        }
+
+                        // Write screenshot of finished frame (buffer is consumed by presentation, so it's done before)
+                        if input.get_state().is_key_clicked(KeyCode::F2) {
+                            let screen = Surface::new(
+                                mut_buffer_slice,
+                                surface_size.width as usize,
+                                surface_size.height as usize,
+                                surface_size.width as usize,
+                            );
+
+                            if let Err(error) = write_screenshot(&screen, std::path::Path::new("."), timer.get_time()) {
+                                eprintln!("Error writing screenshot: {}", error);
+                            }
+                        }
 
                         _ = mut_buffer.present();
 
                         input.clear_changed();
 
+                        timer.sleep_to_target(TARGET_FPS);
                         window.request_redraw();
                     }
                     _ => {},
@@ -565,4 +1704,1089 @@ fn main() {
     }).unwrap();
 } // fn main
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearer_portal_chain_is_rendered_first() {
+        let (width, height) = (160, 120);
+        let (a, b, c, d) = (SectorId::new(0), SectorId::new(1), SectorId::new(2), SectorId::new(3));
+
+        // Far portal `a`-`c` precedes near portal `a`-`b` in edge order, `b` leads to `d`
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(-1.0, -1.0), EdgeType::Wall),
+            (Vec2f::new(5.0, -3.0), EdgeType::Portal { dst_sector_id: c }),
+            (Vec2f::new(5.0, 3.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(-1.0, 1.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(-1.0, 1.0), EdgeType::Portal { dst_sector_id: a }),
+            (Vec2f::new(5.0, 3.0), EdgeType::Portal { dst_sector_id: d }),
+            (Vec2f::new(-1.0, 5.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(5.0, -3.0), EdgeType::Wall),
+            (Vec2f::new(7.0, -3.0), EdgeType::Wall),
+            (Vec2f::new(7.0, 3.0), EdgeType::Wall),
+            (Vec2f::new(5.0, 3.0), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(-1.0, 5.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(5.0, 3.0), EdgeType::Wall),
+            (Vec2f::new(5.0, 7.0), EdgeType::Wall),
+            (Vec2f::new(-1.0, 7.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        let map = builder.build().unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.0, 0.0), 0.5, 0.0);
+
+        // Whole near portal chain is traversed before farther portal
+        let render = Render::new();
+        assert_eq!(render.flood_sectors(&map, &camera, a), vec![(a, 0), (b, 1), (d, 2), (c, 1)]);
+
+        let mut render = Render::new();
+        let mut frame = vec![0u32; width * height];
+        let stats = render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, a, None);
+        assert_eq!(stats.sectors_visited, 4);
+        assert_eq!(stats.max_depth, 2);
+
+        // Far wall of `c` is seen through far portal at screen center
+        let inv_depth = render.get_inv_depth_buffer()[width / 2];
+        assert!((inv_depth - 1.0 / 7.0).abs() < 1e-3, "inverse depth {} isn't one of far sector wall", inv_depth);
+    } // fn nearer_portal_chain_is_rendered_first
+
+    #[test]
+    fn nearer_overlapping_portal_wins() {
+        let (width, height) = (160, 120);
+        let (a, b, c) = (SectorId::new(0), SectorId::new(1), SectorId::new(2));
+
+        // Camera is located outside of `a`, so its far portal to `c` (depth 4) overlaps near portal to `b` (depth 2) in screen space
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(4.0, -1.0), EdgeType::Portal { dst_sector_id: c }),
+            (Vec2f::new(4.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 1.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(2.0, -1.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(1.0, -1.0), EdgeType::Wall),
+            (Vec2f::new(2.0, -1.0), EdgeType::Portal { dst_sector_id: a }),
+            (Vec2f::new(2.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(1.0, 1.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(4.0, -1.0), EdgeType::Wall),
+            (Vec2f::new(6.0, -1.0), EdgeType::Wall),
+            (Vec2f::new(6.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 1.0), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.0, 1.0);
+        let map = builder.build().unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.0, 0.0), 0.5, 0.0);
+
+        let mut render = Render::new();
+        let mut frame = vec![0u32; width * height];
+        let stats = render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, a, None);
+        assert_eq!(stats.sectors_visited, 3);
+
+        // Columns of far portal are shared by both portals, back wall of `b` (depth 1) hides far wall of `c` (depth 6) in all of them
+        let shared_columns = (0..width)
+            .filter(|x| ((*x as f32 + 0.5) / width as f32 * 2.0 - 1.0).abs() * camera.get_tan_half_fov() < 0.25)
+            .collect::<Vec<_>>();
+        assert!(!shared_columns.is_empty());
+
+        for x in shared_columns {
+            let inv_depth = render.get_inv_depth_buffer()[x];
+            assert!((inv_depth - 1.0).abs() < 1e-3, "inverse depth {} of column {} isn't one of nearer sector wall", inv_depth, x);
+        }
+    } // fn nearer_overlapping_portal_wins
+
+    #[test]
+    fn column_beyond_render_distance_is_fogged() {
+        let (width, height) = (160, 120);
+
+        let mut builder = MapBuilder::new();
+        let sector_id = builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(100.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(100.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(0.0, 1.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.set_sector_render_distance(sector_id, Some(5.0));
+        let map = builder.build().unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(1.0, 0.5), 0.5, 0.0);
+
+        let mut render = Render::new();
+        render.fog_color = 0x123456;
+
+        let mut frame = vec![0u32; width * height];
+        render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None);
+
+        // Far wall is located at distance 99, so screen center is filled with fog, but near floor isn't
+        assert_eq!(frame[height / 2 * width + width / 2], 0x123456);
+        assert_eq!(frame[(height - 1) * width + width / 2], DEFAULT_FLOOR_COLOR);
+        assert!((render.get_inv_depth_buffer()[width / 2] - 1.0 / 5.0).abs() < 1e-6);
+    } // fn column_beyond_render_distance_is_fogged
+
+    #[test]
+    fn invalid_sector_renders_horizon() {
+        let (width, height) = (64, 48);
+        let map = Map::load_from_wmt(include_str!("../maps/default.wmt")).unwrap();
+
+        let mut render = Render::new();
+        render.set_sky_color(0x0000FF);
+        render.set_ground_color(0x00FF00);
+
+        let mut frame = vec![0xDEADBEEFu32; width * height];
+        let stats = render.render(&mut Surface::new(&mut frame, width, height, width), &map, &Camera::new(), SectorId::new(u32::MAX), None);
+
+        assert_eq!(stats.sectors_visited, 0);
+        assert!(frame[..width * height / 2].iter().all(|pixel| *pixel == 0x0000FF));
+        assert!(frame[width * height / 2..].iter().all(|pixel| *pixel == 0x00FF00));
+        assert!(render.get_inv_depth_buffer().iter().all(|inv_depth| *inv_depth == 0.0));
+    } // fn invalid_sector_renders_horizon
+
+    #[test]
+    fn height_disjoint_portal_is_rendered_as_wall() {
+        let (width, height) = (64, 48);
+
+        // Build pair of sectors, second one is located above first one
+        let build = |portal: bool| {
+            let mut builder = MapBuilder::new();
+            let ty = |dst: u32| match portal {
+                true => EdgeType::Portal { dst_sector_id: SectorId::new(dst) },
+                false => EdgeType::Wall,
+            };
+            builder.add_sector(&[
+                (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+                (Vec2f::new(2.0, 0.0), ty(1)),
+                (Vec2f::new(2.0, 2.0), EdgeType::Wall),
+                (Vec2f::new(0.0, 2.0), EdgeType::Wall),
+            ], 0.0, 1.0);
+            builder.add_sector(&[
+                (Vec2f::new(2.0, 0.0), EdgeType::Wall),
+                (Vec2f::new(4.0, 0.0), EdgeType::Wall),
+                (Vec2f::new(4.0, 2.0), EdgeType::Wall),
+                (Vec2f::new(2.0, 2.0), ty(0)),
+            ], 2.0, 3.0);
+            builder.build().unwrap()
+        };
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.5, 1.0), 0.5, 0.0);
+
+        let mut render = Render::new();
+        let mut portal_frame = vec![0u32; width * height];
+        let stats = render.render(&mut Surface::new(&mut portal_frame, width, height, width), &build(true), &camera, SectorId::new(0), None);
+        let mut wall_frame = vec![0u32; width * height];
+        render.render(&mut Surface::new(&mut wall_frame, width, height, width), &build(false), &camera, SectorId::new(0), None);
+
+        assert_eq!(stats.sectors_visited, 1);
+        assert!(portal_frame == wall_frame);
+    } // fn height_disjoint_portal_is_rendered_as_wall
+
+    #[test]
+    fn built_two_sector_map_is_rendered() {
+        let (width, height) = (64, 48);
+        let (a, b) = (SectorId::new(0), SectorId::new(1));
+
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(1.0, 0.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(1.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(0.0, 1.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(1.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(3.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(3.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(1.0, 1.0), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.0, 1.0);
+        builder.set_camera(Vec2f::new(0.2, 0.5), 0.5, 0.0);
+        let map = builder.build().unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(map.camera_location, map.camera_height, map.camera_rotation);
+
+        let mut render = Render::new();
+        let mut frame = vec![0xDEADBEEFu32; width * height];
+        let stats = render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, map.find_sector(camera.location).unwrap(), None);
+
+        // Far wall of second sector is visible through portal
+        assert_eq!(stats.sectors_visited, 2);
+        assert!(frame.iter().all(|pixel| *pixel != 0xDEADBEEF));
+        assert!((render.get_inv_depth_buffer()[width / 2] - 1.0 / 2.8).abs() < 1e-3);
+    } // fn built_two_sector_map_is_rendered
+
+    #[test]
+    fn ceiling_aligned_texture_top_row_is_at_ceiling() {
+        let (width, height) = (160, 120);
+        const ROWS: [u32; 4] = [0xFF0000, 0x00FF00, 0x0000FF, 0xFFFFFF];
+
+        // Texture row of topmost wall pixel in center column
+        let top_row = |ceiling: f32, texture_align: TextureAlign| -> Option<usize> {
+            let mut builder = MapBuilder::new();
+            let sector_id = builder.add_sector(&[
+                (Vec2f::new(-1.0, -3.0), EdgeType::Wall),
+                (Vec2f::new(8.0, -3.0), EdgeType::Wall),
+                (Vec2f::new(8.0, 3.0), EdgeType::Wall),
+                (Vec2f::new(-1.0, 3.0), EdgeType::Wall),
+            ], 0.0, ceiling);
+            let mut map = builder.build().unwrap();
+
+            let mut render = Render::new();
+            let texture_id = render.add_texture(Texture::new(1, ROWS.len(), ROWS.to_vec()).unwrap());
+            map.set_edge_texture(sector_id, 1, Some(texture_id)).unwrap();
+            map.set_edge_texture_align(sector_id, 1, texture_align).unwrap();
+
+            let mut camera = Camera::new();
+            camera.set_location(Vec2f::new(0.0, 0.0), 0.5, 0.0);
+
+            let mut frame = vec![0u32; width * height];
+            render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None);
+
+            (0..height).find_map(|y| ROWS.iter().position(|row| *row == frame[y * width + width / 2] & 0xFFFFFF))
+        };
+
+        // Top row stays at ceiling for walls of any height
+        assert_eq!(top_row(1.0, TextureAlign::Ceiling), Some(0));
+        assert_eq!(top_row(2.5, TextureAlign::Ceiling), Some(0));
+
+        // Floor-aligned texture of taller wall is cut at the ceiling
+        assert_eq!(top_row(1.0, TextureAlign::Floor), Some(0));
+        assert_eq!(top_row(2.5, TextureAlign::Floor), Some(2));
+    } // fn ceiling_aligned_texture_top_row_is_at_ceiling
+
+    #[test]
+    fn checker_texture_is_mapped_to_wall() {
+        let (width, height) = (160, 120);
+        let (a, b) = (0xFF0000, 0x0000FF);
+
+        let mut builder = MapBuilder::new();
+        let sector_id = builder.add_sector(&[
+            (Vec2f::new(0.0, -0.5), EdgeType::Wall),
+            (Vec2f::new(4.0, -0.5), EdgeType::Wall),
+            (Vec2f::new(4.0, 0.5), EdgeType::Wall),
+            (Vec2f::new(0.0, 0.5), EdgeType::Wall),
+        ], 0.0, 1.0);
+        let mut map = builder.build().unwrap();
+
+        let mut render = Render::new();
+        let texture_id = render.add_texture(Texture::new(2, 2, vec![a, b, b, a]).unwrap());
+        map.set_edge_texture(sector_id, 1, Some(texture_id)).unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.1, 0.0), 0.5, 0.0);
+
+        let mut frame = vec![0u32; width * height];
+        render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None);
+
+        // Unit wall at distance 3.9 covers about 20x15 pixels at screen center, every quarter of it is single checker cell
+        let pixel = |dx: isize, dy: isize| frame[(height as isize / 2 + dy) as usize * width + (width as isize / 2 + dx) as usize] & 0xFFFFFF;
+        let (top_left, top_right, bottom_left, bottom_right) = (pixel(-5, -4), pixel(5, -4), pixel(-5, 3), pixel(5, 3));
+
+        assert!([(a, b), (b, a)].contains(&(top_left, top_right)), "{:06X} {:06X}", top_left, top_right);
+        assert_eq!((bottom_left, bottom_right), (top_right, top_left));
+
+        // Side walls aren't textured
+        assert!(![a, b].contains(&pixel(-70, 0)));
+    } // fn checker_texture_is_mapped_to_wall
+
+    #[test]
+    fn floor_pixel_maps_to_expected_texel() {
+        let (width, height) = (160, 120);
+        let camera_height = 1.0;
+
+        let mut builder = MapBuilder::new();
+        let sector_id = builder.add_sector(&[
+            (Vec2f::new(-10.0, -10.0), EdgeType::Wall),
+            (Vec2f::new(10.0, -10.0), EdgeType::Wall),
+            (Vec2f::new(10.0, 10.0), EdgeType::Wall),
+            (Vec2f::new(-10.0, 10.0), EdgeType::Wall),
+        ], 0.0, 2.0);
+        let mut map = builder.build().unwrap();
+
+        // Every texel is unique, so sampled texel identifies floor point
+        let texture = Texture::new(4, 4, (1..=16).map(|index| index * 0x0F0F0F).collect()).unwrap();
+        let mut render = Render::with_config(RenderConfig { textured_flats: true, ..RenderConfig::default() });
+        let texture_id = render.add_texture(texture.clone());
+        map.set_sector_textures(sector_id, Some(texture_id), None).unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.1, 0.3), camera_height, 0.0);
+
+        let mut frame = vec![0u32; width * height];
+        render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None);
+
+        // Center column looks along x axis, floor point of a row is `height / row` ahead of camera (4:3 screen with 90 degree fov isn't scaled)
+        let mut checked_rows = 0;
+        for y in height * 3 / 4..height {
+            let row = (y as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+            let point = camera.location + Vec2f::new(camera_height / row, 0.0);
+
+            // Rows near texel borders are ambiguous
+            let texel_offset = (point.x * 4.0).fract();
+            if !(0.05..0.95).contains(&texel_offset) {
+                continue;
+            }
+
+            assert_eq!(frame[y * width + width / 2] & 0xFFFFFF, texture.sample(point.x, point.y), "row {}", y);
+            checked_rows += 1;
+        }
+        assert!(checked_rows > 10);
+
+        // Flat texturing is disabled by default
+        Render::new().render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None);
+        assert_eq!(frame[(height - 1) * width + width / 2] & 0xFFFFFF, map.get_sector(sector_id).unwrap().floor_color);
+    } // fn floor_pixel_maps_to_expected_texel
+
+    #[test]
+    fn farther_wall_is_closer_to_fog_color() {
+        let (width, height) = (160, 120);
+        let fog = DistanceFog { color: 0x204060, start: 1.0, end: 10.0 };
+
+        // White wall at depth 1.5 and white wall at depth 5.5 seen through portal
+        let source = "#sectors\na:0/1[0/-5,2/-5#FFFFFF,2/0:b,2/5,0/5]\nb:0/1[2/0,6/0#FFFFFF,6/5,2/5:a]\n\n#camera\n0.5, 0, 0.5, 0\n";
+        let map = Map::load_from_wmt(source).unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.5, 0.0), 0.5, 0.0);
+
+        let render_frame = |render: &mut Render| {
+            let mut frame = vec![0u32; width * height];
+            render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), None);
+            frame
+        };
+
+        // Columns at screen quarters look at different walls
+        let (left, right) = (height / 2 * width + width / 4, height / 2 * width + width * 3 / 4);
+        let mut render = Render::new();
+        let plain = render_frame(&mut render);
+        let inv_depths = (render.get_inv_depth_buffer()[width / 4], render.get_inv_depth_buffer()[width * 3 / 4]);
+        let (near, far) = if inv_depths.0 > inv_depths.1 { (left, right) } else { (right, left) };
+        assert_eq!(plain[near] & 0xFFFFFF, 0xFFFFFF);
+        assert_eq!(plain[far] & 0xFFFFFF, 0xFFFFFF);
+
+        let fogged = render_frame(&mut Render::with_config(RenderConfig { distance_fog: Some(fog), ..RenderConfig::default() }));
+        let fog_distance = |color: u32| (0..3)
+            .map(|channel| ((color >> (channel * 8)) & 0xFF).abs_diff((fog.color >> (channel * 8)) & 0xFF))
+            .sum::<u32>();
+
+        assert!(fog_distance(fogged[far]) < fog_distance(fogged[near]), "{:06X} isn't foggier than {:06X}", fogged[far], fogged[near]);
+        assert!(fog_distance(fogged[near]) < fog_distance(0xFFFFFF));
+        assert_eq!(fogged[near] & 0xFFFFFF, fog.apply(0xFFFFFF, 1.5) & 0xFFFFFF);
+    } // fn farther_wall_is_closer_to_fog_color
+
+    #[test]
+    fn sprite_is_occluded_by_wall() {
+        let (width, height) = (160, 120);
+        let sprite_color = 0xFF00FF;
+        let sprite_texture = Texture::new(1, 1, vec![sprite_color]).unwrap();
+
+        let mut builder = MapBuilder::new();
+        let sector_id = builder.add_sector(&[
+            (Vec2f::new(-1.0, -3.0), EdgeType::Wall),
+            (Vec2f::new(4.0, -3.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 3.0), EdgeType::Wall),
+            (Vec2f::new(-1.0, 3.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        let map = builder.build().unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.0, 0.0), 0.5, 0.0);
+
+        let mut render = Render::new();
+        let mut frame = vec![0u32; width * height];
+        render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None);
+        let inv_depth_buffer = render.get_inv_depth_buffer().to_vec();
+
+        // Sprite behind far wall writes nothing
+        let mut sprite_frame = frame.clone();
+        render.render_sprite(&mut Surface::new(&mut sprite_frame, width, height, width), &camera, &inv_depth_buffer, Vec3f::new(6.0, 0.0, 0.0), 1.0, &sprite_texture);
+        assert!(sprite_frame == frame);
+
+        // Unit sprite at depth 2 covers quarter of screen width and height, centered horizontally and standing on floor
+        render.render_sprite(&mut Surface::new(&mut sprite_frame, width, height, width), &camera, &inv_depth_buffer, Vec3f::new(2.0, 0.0, 0.0), 1.0, &sprite_texture);
+        for y in 0..height {
+            for x in 0..width {
+                let expected = match (60..100).contains(&x) && (45..75).contains(&y) {
+                    true => sprite_color,
+                    false => frame[y * width + x],
+                };
+                assert_eq!(sprite_frame[y * width + x], expected, "pixel ({}, {})", x, y);
+            }
+        }
+    } // fn sprite_is_occluded_by_wall
+
+    #[test]
+    fn default_map_render_matches_golden_hashes() {
+        let (width, height) = (320, 200);
+        let map = Map::load_from_wmt(include_str!("../maps/default.wmt")).unwrap();
+
+        // Frame hashes recorded from current renderer, render optimizations must keep them unchanged
+        let views = [
+            ((10.0, 20.0, 3.77), 8327274198508570684u64),
+            ((3.0, 12.0, 1.5), 13885601882586661123),
+            ((8.0, 5.0, 2.5), 4776126058168626763),
+            ((2.0, 16.0, 0.3), 4647640018349214188),
+            ((12.0, 10.0, 4.0), 1665991682661800405),
+        ];
+
+        for ((x, y, rotation), expected_hash) in views {
+            let mut camera = Camera::new();
+            camera.set_location(Vec2f::new(x, y), 0.5, rotation);
+            let sector_id = map.find_sector(camera.location).unwrap();
+
+            let mut frame = vec![0u32; width * height];
+            Render::new().render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None);
+
+            let hash = frame.iter().fold(0u64, |hash, pixel| hash.wrapping_mul(1099511628211).wrapping_add(*pixel as u64));
+            assert_eq!(hash, expected_hash, "frame at ({}, {}, {}) differs", x, y, rotation);
+        }
+    } // fn default_map_render_matches_golden_hashes
+
+    /// Benchmark, run with `cargo test --release render_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn render_benchmark() {
+        let (width, height) = (800, 600);
+        let map = Map::load_from_wmt(include_str!("../maps/default.wmt")).unwrap();
+        let frame_count = 1500;
+
+        let mut render = Render::new();
+        let mut frame = vec![0u32; width * height];
+        let mut camera = Camera::new();
+
+        let start = std::time::Instant::now();
+        let mut edges_drawn = 0;
+        for index in 0..frame_count {
+            // Camera turns around in map start point
+            camera.set_location(map.camera_location, map.camera_height, index as f32 / frame_count as f32 * std::f32::consts::TAU);
+            let sector_id = map.find_sector(camera.location).unwrap();
+
+            edges_drawn += render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None).edges_drawn;
+        }
+        let elapsed = start.elapsed();
+
+        println!(
+            "render of {} frames at {}x{} ({} edges drawn): {:?} ({:?} per frame)",
+            frame_count,
+            width,
+            height,
+            edges_drawn,
+            elapsed,
+            elapsed / frame_count,
+        );
+    } // fn render_benchmark
+
+    #[test]
+    fn default_map_stats_cover_reachable_sectors() {
+        let (width, height) = (320, 200);
+        let map = Map::load_from_wmt(include_str!("../maps/default.wmt")).unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(map.camera_location, map.camera_height, map.camera_rotation);
+        let sector_id = map.find_sector(camera.location).unwrap();
+
+        // Sectors reachable through portals from camera sector
+        let mut reachable = vec![sector_id];
+        let mut index = 0;
+        while let Some(&current) = reachable.get(index) {
+            for (_, dst_sector_id, _) in map.iter_portals().filter(|(src_sector_id, _, _)| *src_sector_id == current) {
+                if !reachable.contains(&dst_sector_id) {
+                    reachable.push(dst_sector_id);
+                }
+            }
+            index += 1;
+        }
+
+        let mut frame = vec![0u32; width * height];
+        let stats = Render::new().render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, None);
+
+        assert!(stats.sectors_visited >= reachable.len(), "{} sectors visited, {} reachable", stats.sectors_visited, reachable.len());
+        assert!(stats.max_depth >= 1 && stats.max_depth < stats.sectors_visited);
+        assert!(stats.edges_drawn >= stats.sectors_visited);
+        assert!(stats.columns_filled >= width);
+    } // fn default_map_stats_cover_reachable_sectors
+
+    #[test]
+    fn render_to_file_uses_map_camera() {
+        let (width, height) = (64, 48);
+        let map_path = concat!(env!("CARGO_MANIFEST_DIR"), "/maps/default.wmt");
+        let output_path = std::env::temp_dir().join(format!("weird_render_to_file_{}.ppm", std::process::id()));
+        let output_path = output_path.to_str().unwrap();
+
+        render_to_file(map_path, output_path, width, height).unwrap();
+        let written = std::fs::read(output_path).unwrap();
+        std::fs::remove_file(output_path).unwrap();
+
+        // Frame is rendered from map camera, including its height
+        let map = Map::load_from_wmt(include_str!("../maps/default.wmt")).unwrap();
+        assert_ne!(map.camera_height, 0.5);
+        let mut camera = Camera::new();
+        camera.set_location(map.camera_location, map.camera_height, map.camera_rotation);
+
+        let mut frame = vec![0u32; width * height];
+        let mut surface = Surface::new(&mut frame, width, height, width);
+        Render::new().render(&mut surface, &map, &camera, map.find_sector(camera.location).unwrap(), None);
+        let mut expected = Vec::new();
+        surface.write_ppm(&mut expected).unwrap();
+        assert!(written == expected);
+
+        // Loading errors are described by their display text
+        let broken_path = std::env::temp_dir().join(format!("weird_render_to_file_{}.wmt", std::process::id()));
+        std::fs::write(&broken_path, "#sectors\na:0/1[0/0,1/0,1/1:c]\n").unwrap();
+        let error = render_to_file(broken_path.to_str().unwrap(), output_path, width, height).err().unwrap();
+        std::fs::remove_file(&broken_path).unwrap();
+        let load_error = Map::load_from_wmt("#sectors\na:0/1[0/0,1/0,1/1:c]\n").err().unwrap();
+        assert!(error.ends_with(&format!(": {}", load_error)), "{}", error);
+    } // fn render_to_file_uses_map_camera
+
+    #[test]
+    fn screenshot_is_written_to_timestamped_file() {
+        let (width, height) = (5, 4);
+        let directory = std::env::temp_dir().join(format!("weird_screenshots_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let mut frame = (0..width * height).map(|index| index as u32 * 0x010203).collect::<Vec<_>>();
+        let surface = Surface::new(&mut frame, width, height, width);
+
+        let first = write_screenshot(&surface, &directory, 1.5).unwrap();
+        let second = write_screenshot(&surface, &directory, 2.25).unwrap();
+        assert_ne!(first, second);
+
+        let mut expected = Vec::new();
+        surface.write_ppm(&mut expected).unwrap();
+        assert!(std::fs::read(&first).unwrap() == expected);
+        assert!(std::fs::read(&second).unwrap() == expected);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // Missing directory is reported as error
+        assert!(write_screenshot(&surface, &directory, 3.0).is_err());
+    } // fn screenshot_is_written_to_timestamped_file
+
+    #[test]
+    fn occluded_room_isnt_filled_on_minimap() {
+        let (a, b, c) = (SectorId::new(0), SectorId::new(1), SectorId::new(2));
+
+        // Room `b` is seen from `a` through narrow portal, room `c` is next to `b`, but out of the portal view
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 0.95), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(2.0, 1.05), EdgeType::Wall),
+            (Vec2f::new(0.0, 2.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(2.0, 0.95), EdgeType::Wall),
+            (Vec2f::new(4.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 2.0), EdgeType::Portal { dst_sector_id: c }),
+            (Vec2f::new(3.0, 2.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 1.05), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(3.0, 2.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(4.0, 2.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 3.0), EdgeType::Wall),
+            (Vec2f::new(3.0, 3.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        let map = builder.build().unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.5, 1.0), 0.5, 0.0);
+
+        let flooded = Render::new().flood_sectors(&map, &camera, a).into_iter().map(|(sector_id, _)| sector_id).collect::<Vec<_>>();
+        assert_eq!(flooded, vec![a, b]);
+
+        // Minimap pixel color at world point
+        let minimap_pixel = |camera: &Camera, sector_id: SectorId, p: Vec2f| -> u32 {
+            let (width, height) = (64, 64);
+            let mut frame = vec![MINIMAP_TRANSPARENT_COLOR; width * height];
+            Render::new().render_minimap(&mut Surface::new(&mut frame, width, height, width), &map, camera, sector_id);
+
+            let p = camera.to_space(p);
+            frame[(height as isize / 2 - (p.y * 6.0) as isize) as usize * width + (width as isize / 2 + (p.x * 6.0) as isize) as usize]
+        };
+
+        // Visible room is filled with its portal depth color, occluded one isn't filled at all
+        assert_eq!(minimap_pixel(&camera, a, Vec2f::new(3.2, 1.0)), 0x202020);
+        assert_eq!(minimap_pixel(&camera, a, Vec2f::new(3.5, 2.5)), MINIMAP_TRANSPARENT_COLOR);
+
+        // Same room is filled if it's visible
+        camera.set_location(Vec2f::new(3.5, 1.0), 0.5, std::f32::consts::FRAC_PI_2);
+        assert_eq!(minimap_pixel(&camera, b, Vec2f::new(3.5, 2.5)), 0x202020);
+    } // fn occluded_room_isnt_filled_on_minimap
+
+    /// Two 2x2 rooms connected by portal building function
+    /// * Returns map with rooms [0, 2]x[0, 2] and [2, 4]x[0, 2]
+    fn two_rooms_map() -> Map {
+        let (a, b) = (SectorId::new(0), SectorId::new(1));
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 0.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(2.0, 2.0), EdgeType::Wall),
+            (Vec2f::new(0.0, 2.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(2.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 2.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 2.0), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.0, 1.0);
+        builder.build().unwrap()
+    } // fn two_rooms_map
+
+    #[test]
+    fn minimap_is_composited_with_player_marker_at_center() {
+        let map = two_rooms_map();
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(1.0, 1.0), 0.5, 0.0);
+
+        let mut minimap = OwnedSurface::new(48, 40);
+        let mut minimap_surface = minimap.as_surface();
+        minimap_surface.clear(MINIMAP_TRANSPARENT_COLOR);
+        Render::new().render_minimap(&mut minimap_surface, &map, &camera, SectorId::new(0));
+
+        // Player marker and its direction line
+        assert_eq!(minimap_surface.get_pixel(24, 20), Some(0xFFFFFF));
+        assert_eq!(minimap_surface.get_pixel(24, 16), Some(0xFFFFFF));
+        assert_eq!(minimap_surface.get_pixel(0, 0), Some(MINIMAP_TRANSPARENT_COLOR));
+
+        // Minimap is composited over frame corner, its transparent pixels only darken frame
+        let (width, height) = (120, 90);
+        let mut frame = vec![0x123456u32; width * height];
+        let mut screen = Surface::new(&mut frame, width, height, width);
+        screen.draw_bar_blended(0, 0, 48, 40, MINIMAP_BACKGROUND_COLOR, MINIMAP_BACKGROUND_ALPHA);
+        screen.blit_keyed(&minimap_surface, 0, 0, MINIMAP_TRANSPARENT_COLOR);
+
+        assert_eq!(screen.get_pixel(24, 20), Some(0xFFFFFF));
+        assert_eq!(screen.get_pixel(0, 0), Some(surface::blend_color(0x123456, MINIMAP_BACKGROUND_COLOR, MINIMAP_BACKGROUND_ALPHA)));
+        assert_eq!(screen.get_pixel(48, 20), Some(0x123456));
+        assert_eq!(screen.get_pixel(24, 40), Some(0x123456));
+    } // fn minimap_is_composited_with_player_marker_at_center
+
+    #[test]
+    fn minimap_edge_colors_stay_in_their_channels() {
+        let map = two_rooms_map();
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(1.0, 1.0), 0.5, 0.0);
+
+        let (width, height) = (64, 64);
+        let mut frame = vec![MINIMAP_TRANSPARENT_COLOR; width * height];
+        Render::new().render_minimap(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0));
+
+        // Walls are green and portals are red, current sector edges are brighter than adjacent ones
+        let current_wall = (MINIMAP_CURRENT_EDGE_INTENSITY as u32) << 8;
+        let adjacent_wall = (MINIMAP_ADJACENT_EDGE_INTENSITY as u32) << 8;
+        let current_portal = (MINIMAP_CURRENT_EDGE_INTENSITY as u32) << 16;
+        let adjacent_portal = (MINIMAP_ADJACENT_EDGE_INTENSITY as u32) << 16;
+        assert!(adjacent_wall < current_wall && adjacent_portal < current_portal);
+
+        for color in [current_wall, adjacent_wall, current_portal] {
+            assert!(frame.contains(&color), "there's no {:06X} edge pixels", color);
+        }
+
+        // Every edge pixel has single nonzero channel
+        for &pixel in frame.iter().filter(|&&pixel| pixel & 0xFF00FF == 0 || pixel & 0x00FFFF == 0) {
+            assert!(pixel == 0 || [current_wall, adjacent_wall, current_portal, adjacent_portal].contains(&pixel), "unexpected edge color {:06X}", pixel);
+        }
+
+        // Scaling never wraps into neighbour channels
+        for intensity in [0u8, 1, 0x66, 0x80, 0xFE, 0xFF] {
+            assert_eq!(surface::blend_color(0x000000, 0x00FF00, intensity) & !0x00FF00, 0);
+            assert_eq!(surface::blend_color(0x000000, 0xFF0000, intensity) & !0xFF0000, 0);
+        }
+    } // fn minimap_edge_colors_stay_in_their_channels
+
+    /// Straight corridor map building function
+    /// * `count` - count of corridor sectors
+    /// * `slice_length` - length of single sector along x axis, corridor is 1 unit wide along y axis
+    /// * Returns corridor map, sector `i` is connected by portals with sectors `i - 1` and `i + 1`
+    fn corridor_map(count: usize, slice_length: f32) -> Map {
+        let mut builder = MapBuilder::new();
+
+        for index in 0..count {
+            let (x0, x1) = (index as f32 * slice_length, (index + 1) as f32 * slice_length);
+            let next = match index + 1 < count {
+                true => EdgeType::Portal { dst_sector_id: SectorId::new(index as u32 + 1) },
+                false => EdgeType::Wall,
+            };
+            let prev = match index > 0 {
+                true => EdgeType::Portal { dst_sector_id: SectorId::new(index as u32 - 1) },
+                false => EdgeType::Wall,
+            };
+
+            builder.add_sector(&[
+                (Vec2f::new(x0, 0.0), EdgeType::Wall),
+                (Vec2f::new(x1, 0.0), next),
+                (Vec2f::new(x1, 1.0), EdgeType::Wall),
+                (Vec2f::new(x0, 1.0), prev),
+            ], 0.0, 1.0);
+        }
+
+        builder.build().unwrap()
+    } // fn corridor_map
+
+    /// Straight WMT corridor map source building function
+    /// * `count` - count of corridor sectors, 0.01 units long along x axis each
+    /// * Returns corridor map source (see `corridor_map`), sector colors are selected by sector index like portal depth colors of recursive renderer
+    fn corridor_wmt(count: usize) -> String {
+        let coordinate = |index: usize| format!("{}.{:02}", index / 100, index % 100);
+        let mut source = String::from("#sectors\n");
+
+        for index in 0..count {
+            let (x0, x1) = (coordinate(index), coordinate(index + 1));
+            let next = if index + 1 < count { format!(":s{:03}", index + 1) } else { String::new() };
+            let prev = if index > 0 { format!(":s{:03}", index - 1) } else { String::new() };
+            let colors = match index {
+                0 => "DDFFDD/779977",
+                1 => "FFDDDD/997777",
+                2 => "DDDDFF/777799",
+                _ => "EEEEEE/888888",
+            };
+
+            source.push_str(&format!("s{:03}:0/1#{}[{}/0,{}/0{},{}/1,{}/1{}]\n", index, colors, x0, x1, next, x1, x0, prev));
+        }
+
+        source
+    } // fn corridor_wmt
+
+    #[test]
+    fn task_stack_matches_recursive_traversal() {
+        let (width, height) = (160, 120);
+        let count = 300;
+        let map = Map::load_from_wmt(&corridor_wmt(count)).unwrap();
+        let mut render = Render::new();
+
+        // Frame hashes recorded from recursive renderer (97d61c3^) on the same corridor without sector colors
+        let views = [
+            ((0.005, 0.3, 0.2), 8520392161049422556u64),
+            ((0.005, 0.5, 0.0), 12373465381954930228),
+            ((0.005, 0.7, -0.3), 1222949453359937975),
+        ];
+
+        for ((x, y, rotation), expected_hash) in views {
+            let mut camera = Camera::new();
+            camera.set_location(Vec2f::new(x, y), 0.5, rotation);
+            let sector_id = map.find_sector(camera.location).unwrap();
+            assert_eq!(sector_id, SectorId::new(0));
+
+            let mut frame = vec![0u32; width * height];
+            let stats = render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, Some(count));
+            assert!(stats.max_depth > 100, "corridor isn't traversed deep enough");
+
+            let hash = frame.iter().fold(0u64, |hash, pixel| hash.wrapping_mul(1099511628211).wrapping_add(*pixel as u64));
+            assert_eq!(hash, expected_hash, "frame at ({}, {}, {}) differs", x, y, rotation);
+        }
+    } // fn task_stack_matches_recursive_traversal
+
+    #[test]
+    fn deep_portal_chain_is_rendered_without_overflow() {
+        let (width, height) = (64, 48);
+        let count = 5000;
+        let map = corridor_map(count, 0.002);
+        let mut render = Render::new();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.001, 0.5), 0.5, 0.0);
+
+        let mut frame = vec![0u32; width * height];
+        let stats = render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), Some(count));
+
+        // Every corridor sector is visible through its neighbours
+        assert_eq!(stats.max_depth, count - 1);
+    } // fn deep_portal_chain_is_rendered_without_overflow
+
+    #[test]
+    fn max_portal_depth_stops_recursion() {
+        let (width, height) = (64, 48);
+        let map = corridor_map(3, 1.0);
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.5, 0.5), 0.5, 0.0);
+
+        // Portal beyond the limit is rendered as wall, so center column depth is one of the last rendered sector far edge
+        for (max_portal_depth, expected_max_depth, expected_depth) in [(0, 0, 0.5), (1, 1, 1.5), (DEFAULT_MAX_PORTAL_DEPTH, 2, 2.5)] {
+            let mut render = Render::with_config(RenderConfig { max_portal_depth, ..RenderConfig::default() });
+            let mut frame = vec![0u32; width * height];
+            let stats = render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), None);
+
+            assert_eq!(stats.max_depth, expected_max_depth);
+            assert_eq!(stats.sectors_visited, expected_max_depth + 1);
+            let inv_depth = render.get_inv_depth_buffer()[width / 2];
+            assert!((inv_depth - 1.0 / expected_depth).abs() < 1e-3, "inverse depth {} for depth limit {}", inv_depth, max_portal_depth);
+
+            // Per-call limit overrides configured one
+            let mut override_frame = vec![0u32; width * height];
+            let override_stats = Render::new().render(&mut Surface::new(&mut override_frame, width, height, width), &map, &camera, SectorId::new(0), Some(max_portal_depth));
+            assert_eq!(override_stats.max_depth, expected_max_depth);
+            assert!(override_frame == frame);
+        }
+    } // fn max_portal_depth_stops_recursion
+
+    #[test]
+    fn corridor_render_respects_portal_depth_limit() {
+        let (width, height) = (64, 48);
+        let count = 64;
+        let map = corridor_map(count, 0.05);
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.025, 0.5), 0.5, 0.0);
+
+        let mut render = Render::new();
+        for max_portal_depth in [0, 1, 8, count - 1, DEFAULT_MAX_PORTAL_DEPTH, usize::MAX] {
+            let mut frame = vec![0u32; width * height];
+            let stats = render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), Some(max_portal_depth));
+
+            // Corridor is straight, so every sector up to the limit is visited exactly once
+            let expected_depth = max_portal_depth.min(count - 1);
+            assert_eq!(stats.max_depth, expected_depth, "depth limit {}", max_portal_depth);
+            assert_eq!(stats.sectors_visited, expected_depth + 1, "depth limit {}", max_portal_depth);
+
+            render.set_max_portal_depth(max_portal_depth);
+            let flooded = render.flood_sectors(&map, &camera, SectorId::new(0));
+            assert_eq!(flooded.len(), expected_depth + 1);
+            assert!(flooded.iter().all(|&(_, depth)| depth <= max_portal_depth));
+            assert_eq!(render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), None).max_depth, expected_depth);
+        }
+    } // fn corridor_render_respects_portal_depth_limit
+
+    #[test]
+    fn translucent_portal_blends_over_geometry_behind() {
+        let (width, height) = (160, 120);
+        let overlay = 0x80FF0000;
+
+        let render_frame = |portal_chunk: &str| {
+            let source = format!("#sectors\na:0/1[0/0,1/0:b,1/1,0/1]\nb:0/1[1/0,4/0,4/1,1/1:a]\n\n#portal\n{}\n#camera\n0.2, 0.5, 0.5, 0\n", portal_chunk);
+            let map = Map::load_from_wmt(&source).unwrap();
+
+            let mut camera = Camera::new();
+            camera.set_location(Vec2f::new(0.2, 0.5), 0.5, 0.0);
+
+            let mut frame = vec![0u32; width * height];
+            Render::new().render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), None);
+            frame
+        };
+
+        let opaque = render_frame("");
+        let translucent = render_frame(&format!("a:1:overlay={:08X}", overlay));
+
+        // Every pixel is either untouched or blended over the opaque frame pixel
+        let mut blended_count = 0;
+        for (opaque_pixel, translucent_pixel) in opaque.iter().zip(translucent.iter()) {
+            if opaque_pixel != translucent_pixel {
+                assert_eq!(*translucent_pixel, blend_colors(*opaque_pixel, overlay));
+                blended_count += 1;
+            }
+        }
+        assert!(blended_count > 0);
+
+        // Sector `b` far wall is seen through portal, so it's blended, and sector `a` side walls aren't
+        let center = height / 2 * width + width / 2;
+        assert_eq!(translucent[center], blend_colors(opaque[center], overlay));
+        assert_ne!(translucent[center], opaque[center]);
+        assert_eq!(translucent[height / 2 * width], opaque[height / 2 * width]);
+    } // fn translucent_portal_blends_over_geometry_behind
+
+    #[test]
+    fn sprite_behind_translucent_portal_is_tinted() {
+        let (width, height) = (160, 120);
+        let overlay = 0x80FF0000;
+        let sprite_color = 0xFF00FF;
+
+        let map = Map::load_from_wmt(&format!(
+            "#sectors\na:0/1[0/0,1/0:b,1/1,0/1]\nb:0/1[1/0,4/0,4/1,1/1:a]\n\n#portal\na:1:overlay={:08X}\n#camera\n0.2, 0.5, 0.5, 0\n",
+            overlay
+        )).unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.2, 0.5), 0.5, 0.0);
+
+        let mut render = Render::new();
+        let texture = render.add_texture(Texture::new(1, 1, vec![sprite_color]).unwrap());
+        let mut render_frame = |sprites: &[Sprite]| {
+            let mut frame = vec![0u32; width * height];
+            render.render_with_sprites(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), None, sprites);
+            frame
+        };
+
+        let empty = render_frame(&[]);
+        let behind = render_frame(&[Sprite { location: Vec3f::new(2.5, 0.5, 0.0), height: 1.0, texture }]);
+        let in_front = render_frame(&[Sprite { location: Vec3f::new(0.6, 0.5, 0.0), height: 1.0, texture }]);
+
+        // Sprite behind portal is blended with portal overlay
+        let center = height / 2 * width + width / 2;
+        assert_eq!(behind[center], blend_colors(sprite_color, overlay));
+        for (empty_pixel, behind_pixel) in empty.iter().zip(behind.iter()) {
+            if empty_pixel != behind_pixel {
+                assert_eq!(*behind_pixel, blend_colors(sprite_color, overlay));
+            }
+        }
+
+        // Sprite in front of portal is drawn over it as is
+        assert_eq!(in_front[center], sprite_color);
+        for (empty_pixel, in_front_pixel) in empty.iter().zip(in_front.iter()) {
+            if empty_pixel != in_front_pixel {
+                assert_eq!(*in_front_pixel, sprite_color);
+            }
+        }
+
+        // Frame without sprites matches plain render
+        let mut frame = vec![0u32; width * height];
+        render.render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), None);
+        assert!(frame == empty);
+    } // fn sprite_behind_translucent_portal_is_tinted
+
+    /// Room with colored edge in front of camera rendering function
+    /// * `camera` - camera to render room with
+    /// * `width`, `height` - frame size
+    /// * Returns screen x range of colored edge in frame middle row
+    fn render_colored_edge_columns(camera: &Camera, width: usize, height: usize) -> std::ops::Range<usize> {
+        let source = "#sectors\na:0/1[-1/-5,4/-5,4/-0.5#FF0000,4/0.5,4/5,-1/5]\n\n#camera\n0, 0, 0.5, 0\n";
+        let map = Map::load_from_wmt(source).unwrap();
+
+        let mut frame = vec![0u32; width * height];
+        Render::new().render(&mut Surface::new(&mut frame, width, height, width), &map, camera, SectorId::new(0), None);
+
+        let row = &frame[height / 2 * width..(height / 2 + 1) * width];
+        let edge_color = row[width / 2];
+        let begin = row.iter().position(|&pixel| pixel == edge_color).unwrap();
+        let end = row.iter().rposition(|&pixel| pixel == edge_color).unwrap() + 1;
+        assert!(row[begin..end].iter().all(|&pixel| pixel == edge_color));
+
+        begin..end
+    } // fn render_colored_edge_columns
+
+    #[test]
+    fn narrower_fov_widens_projected_edge() {
+        let (width, height) = (200, 150);
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.0, 0.0), 0.5, 0.0);
+        let wide = render_colored_edge_columns(&camera, width, height);
+        camera.set_fov(std::f32::consts::PI / 3.0);
+        let narrow = render_colored_edge_columns(&camera, width, height);
+
+        // Edge is centered, its half-width is 0.5 / 4 of half-screen at 90 degrees and scaled by 1 / tan(30 degrees) at 60
+        let expected_half_width = |tan_half_fov: f32| 0.125 / tan_half_fov * (width / 2) as f32;
+        for (columns, tan_half_fov) in [(&wide, 1.0), (&narrow, camera.get_tan_half_fov())] {
+            assert!(columns.start < width / 2 && columns.end > width / 2);
+            assert!((columns.len() as f32 / 2.0 - expected_half_width(tan_half_fov)).abs() <= 1.0, "{:?} for {}", columns, tan_half_fov);
+        }
+        assert!(narrow.start < wide.start && narrow.end > wide.end);
+    } // fn narrower_fov_widens_projected_edge
+
+    #[test]
+    fn wall_angular_height_doesnt_depend_on_aspect_ratio() {
+        let source = "#sectors\na:0/1[-1/-5,4/-5#FF0000,4/5,-1/5]\n\n#camera\n0, 0, 0.5, 0\n";
+        let map = Map::load_from_wmt(source).unwrap();
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.0, 0.0), 0.5, 0.0);
+
+        // Wall rows per horizontal focal length pixel is angular height measure, it's same as on 4:3 screen
+        let mut reference_angular_height = None;
+        for (width, height) in [(192, 144), (256, 144), (320, 180), (144, 192)] {
+            let mut frame = vec![0u32; width * height];
+            Render::new().render(&mut Surface::new(&mut frame, width, height, width), &map, &camera, SectorId::new(0), None);
+
+            let wall_color = frame[height / 2 * width + width / 2];
+            let wall_rows = (0..height).filter(|y| frame[y * width + width / 2] == wall_color).count();
+
+            let focal_length = (width / 2) as f32 / camera.get_tan_half_fov();
+            let angular_height = wall_rows as f32 / focal_length;
+            let reference_angular_height = *reference_angular_height.get_or_insert(angular_height);
+            assert!((angular_height - reference_angular_height).abs() * focal_length <= 1.0, "{} rows at {}x{}", wall_rows, width, height);
+        }
+    } // fn wall_angular_height_doesnt_depend_on_aspect_ratio
+
+    #[test]
+    fn positive_pitch_moves_horizon_down() {
+        let (width, height) = (160, 120);
+        let source = "#sectors\na:0/1[-1/-5,4/-5#FF0000,4/5,-1/5]\n\n#camera\n0, 0, 0.5, 0\n";
+        let map = Map::load_from_wmt(source).unwrap();
+
+        // Wall rows of center column
+        let wall_rows = |camera: &Camera| {
+            let mut frame = vec![0u32; width * height];
+            Render::new().render(&mut Surface::new(&mut frame, width, height, width), &map, camera, SectorId::new(0), None);
+
+            let column = (0..height).map(|y| frame[y * width + width / 2] & 0xFFFFFF).collect::<Vec<_>>();
+            let begin = column.iter().position(|&pixel| pixel == 0xFF0000).unwrap();
+            let end = column.iter().rposition(|&pixel| pixel == 0xFF0000).unwrap() + 1;
+            (begin, end)
+        };
+
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(0.0, 0.0), 0.5, 0.0);
+        let neutral = wall_rows(&camera);
+
+        // Horizon shift is tan(pitch) of half-screen height on 4:3 screen with 90 degree field of view
+        let pitch = 0.2;
+        camera.set_pitch(pitch);
+        let expected_shift = pitch.tan() * (height / 2) as f32;
+        let pitched = wall_rows(&camera);
+
+        for (neutral_row, pitched_row) in [(neutral.0, pitched.0), (neutral.1, pitched.1)] {
+            let shift = pitched_row as f32 - neutral_row as f32;
+            assert!((shift - expected_shift).abs() <= 1.0, "rows are shifted by {} instead of {}", shift, expected_shift);
+        }
+
+        let horizon = camera.world_to_screen(Vec2f::new(100.0, 0.0), Ext2 { w: width, h: height }).unwrap().1;
+        assert!((horizon as f32 - (height / 2) as f32 - expected_shift).abs() <= 1.0, "horizon row is {}", horizon);
+
+        // Pitch is clamped
+        camera.set_pitch(10.0);
+        assert_eq!(camera.get_pitch(), camera::DEFAULT_MAX_PITCH);
+    } // fn positive_pitch_moves_horizon_down
+
+    #[test]
+    fn concave_nmap_sector_is_rendered_without_gaps() {
+        let l_shape = [
+            Vec2f::new(0.0, 0.0), Vec2f::new(2.0, 0.0), Vec2f::new(2.0, 1.0),
+            Vec2f::new(1.0, 1.0), Vec2f::new(1.0, 2.0), Vec2f::new(0.0, 2.0),
+        ];
+        let map = nmap::Map {
+            sectors: vec![nmap::Sector {
+                subsectors: nmap::decompose_into_subsectors(&l_shape),
+                floor: 0.0,
+                ceiling: 1.0,
+            }],
+        };
+        let wall_color = RenderConfig::default().depth_colors[0];
+        let (width, height) = (160, 120);
+        assert_eq!(map.sectors[0].subsectors.iter().filter(|subsector| subsector.contains(Vec2f::new(0.25, 0.25))).count(), 2);
+
+        // Cameras in both arms looking at the concave corner and along the arms, the first one stands on subportal
+        for (location, rotation) in [
+            (Vec2f::new(0.25, 0.25), std::f32::consts::FRAC_PI_4),
+            (Vec2f::new(0.3, 0.2), std::f32::consts::FRAC_PI_4),
+            (Vec2f::new(1.75, 0.5), std::f32::consts::PI),
+            (Vec2f::new(0.5, 1.75), -std::f32::consts::FRAC_PI_2),
+            (Vec2f::new(0.5, 0.5), 0.0),
+        ] {
+            let mut camera = Camera::new();
+            camera.set_location(location, 0.5, rotation);
+            let (sector_id, subsector_id) = map.find_subsector(location).unwrap();
+
+            let mut frame = vec![0xFF00FFu32; width * height];
+            let stats = Render::new().render_nmap(&mut Surface::new(&mut frame, width, height, width), &map, &camera, sector_id, subsector_id);
+
+            // Every pixel is filled and every column has wall on the horizon
+            assert!(!frame.contains(&0xFF00FF), "gap from {:?}", location);
+            assert!(frame[height / 2 * width..(height / 2 + 1) * width].iter().all(|pixel| *pixel == wall_color), "horizon isn't covered by walls from {:?}", location);
+            assert_eq!(stats.max_depth, 0);
+            assert!(stats.sectors_visited <= 2);
+        }
+    } // fn concave_nmap_sector_is_rendered_without_gaps
+} // mod tests
+
 // file main.rs
@@ -4,8 +4,10 @@
 /// `Author` TioT2
 /// `Last changed` 04.05.2024
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+use crate::util::unordered_pair::UnorderedPair;
 use crate::math::*;
+use crate::texture::TextureId;
 
 /// Sector type representation structure
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -18,6 +20,43 @@ pub enum EdgeType {
     },
 } // enum Edge
 
+/// Wall texture vertical alignment
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum TextureAlign {
+    /// Texture tiles start at sector floor
+    #[default]
+    Floor,
+    /// Texture tiles start at sector ceiling
+    Ceiling,
+    /// Texture tiles start at fixed world height
+    World(f32),
+} // enum TextureAlign
+
+impl TextureAlign {
+    /// Texture origin getting function
+    /// * `floor` - floor height of wall sector
+    /// * `ceiling` - ceiling height of wall sector
+    /// * Returns world height texture top row is located at
+    pub fn get_origin(&self, floor: f32, ceiling: f32) -> f32 {
+        match self {
+            Self::Floor => floor,
+            Self::Ceiling => ceiling,
+            Self::World(height) => *height,
+        }
+    } // fn get_origin
+} // impl TextureAlign
+
+/// Portal extended attributes representation structure (teleport portals aren't supported yet)
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PortalAttributes {
+    /// Color everything visible through portal is multiplied by
+    pub tint: Option<u32>,
+    /// Portal can't be passed through
+    pub blocking: bool,
+    /// 0xAARRGGBB color blended over everything visible through portal, portal is translucent if set
+    pub overlay: Option<u32>,
+} // struct PortalAttributes
+
 /// Edge math data container
 #[derive(Copy, Clone, Debug)]
 pub struct Edge {
@@ -29,8 +68,18 @@ pub struct Edge {
     pub direction: Vec2f,
     /// d and p0 cross product
     pub d_cross_p0: f32,
+    /// p0 -> p1 distance, it doesn't depend on camera, so renderer doesn't recompute it every frame
+    pub length: f32,
     /// Edge type
     pub ty: EdgeType,
+    /// Wall texture vertical alignment
+    pub texture_align: TextureAlign,
+    /// Extended attributes, used only if edge is portal
+    pub portal_attributes: PortalAttributes,
+    /// Wall color, renderer default is used if not set
+    pub color: Option<u32>,
+    /// Wall texture, wall is filled with color if not set
+    pub texture: Option<TextureId>,
 } // struct Edge
 
 impl Edge {
@@ -41,7 +90,7 @@ impl Edge {
     /// * Returns new edge
     pub fn new(p0: Vec2f, p1: Vec2f, ty: EdgeType) -> Self {
         let direction = p1 - p0;
-        Self { p0, p1, d_cross_p0: direction % p0, direction, ty }
+        Self { p0, p1, d_cross_p0: direction % p0, length: direction.length(), direction, ty, texture_align: TextureAlign::Floor, portal_attributes: PortalAttributes::default(), color: None, texture: None }
     } // fn new
 
     /// Build edge loop from points
@@ -53,6 +102,12 @@ impl Edge {
             .zip(points.map(|t| t.0).cycle().skip(1))
             .map(|((left, ty), right)| Edge::new(left, right, ty))
     } // fn loop_from_points
+
+    /// Edge length getting function
+    /// * Returns distance between edge points
+    pub fn length(&self) -> f32 {
+        self.length
+    } // fn length
 }
 
 impl std::fmt::Display for EdgeType {
@@ -64,6 +119,12 @@ impl std::fmt::Display for EdgeType {
     } // fn fmt
 } // impl std::fmt::Display for Edge
 
+/// Floor color of sectors without explicitly set one
+pub const DEFAULT_FLOOR_COLOR: u32 = 0xDDFFDD;
+
+/// Ceiling color of sectors without explicitly set one
+pub const DEFAULT_CEILING_COLOR: u32 = 0x779977;
+
 /// Sector representation structure
 pub struct Sector {
     /// Sector edge set
@@ -72,6 +133,18 @@ pub struct Sector {
     pub floor: f32,
     /// Ceiling height
     pub ceiling: f32,
+    /// Maximal distance sector is rendered at, parts beyond are filled with fog
+    pub render_distance: Option<f32>,
+    /// Sector gameplay tags (e.g. `damage=10` or `gravity=4.9`)
+    pub tags: Vec<String>,
+    /// Floor color
+    pub floor_color: u32,
+    /// Ceiling color
+    pub ceiling_color: u32,
+    /// Floor texture, used if renderer has flat texturing enabled
+    pub floor_texture: Option<TextureId>,
+    /// Ceiling texture, used if renderer has flat texturing enabled
+    pub ceiling_texture: Option<TextureId>,
 } // struct Sector
 
 impl Sector {
@@ -83,6 +156,12 @@ impl Sector {
             edges: Edge::loop_from_points(points.map(|v| (v, EdgeType::Wall))).collect(),
             floor: 0.0,
             ceiling: 1.0,
+            render_distance: None,
+            tags: Vec::new(),
+            floor_color: DEFAULT_FLOOR_COLOR,
+            ceiling_color: DEFAULT_CEILING_COLOR,
+            floor_texture: None,
+            ceiling_texture: None,
         }
     } // fn wall_loop
 
@@ -94,6 +173,97 @@ impl Sector {
 
         sign_collector != 0 && sign_collector != 3//((sign_collector & 0x1) ^ (sign_collector >> 1)) != 0
     } // pub fn is_in
+
+    /// Sector convexity checking function, `contains` works properly only for convex sectors.
+    /// Collinear neighbour edges are allowed, zero-length ones aren't.
+    /// * Returns true if sector edges form convex polygon
+    pub fn is_convex(&self) -> bool {
+        if self.edges.len() < 3 {
+            return false;
+        }
+
+        let mut turn_sign = 0.0f32;
+        let mut total_turn = 0.0f32;
+
+        for (edge, next_edge) in self.edges.iter().zip(self.edges.iter().cycle().skip(1)) {
+            if edge.direction.x == 0.0 && edge.direction.y == 0.0 {
+                return false;
+            }
+
+            let cross = edge.direction % next_edge.direction;
+            let dot = edge.direction ^ next_edge.direction;
+
+            if cross == 0.0 {
+                // Edge turning back
+                if dot < 0.0 {
+                    return false;
+                }
+                continue;
+            }
+
+            if turn_sign * cross < 0.0 {
+                return false;
+            }
+            turn_sign = cross;
+            total_turn += cross.atan2(dot);
+        }
+
+        // Self-intersecting polygons (e.g. pentagram) turn in one direction too, but more than once
+        total_turn.abs() < std::f32::consts::PI * 3.0
+    } // fn is_convex
+
+    /// Signed sector area calculation function (shoelace formula)
+    /// * Returns sector area, positive if sector points go counter-clockwise (in basis with Y axis pointing up), negative otherwise
+    pub fn area(&self) -> f32 {
+        self.edges
+            .iter()
+            .map(|edge| edge.p0 % edge.p1)
+            .sum::<f32>() * 0.5
+    } // fn area
+
+    /// Sector perimeter calculation function
+    /// * Returns sum of sector edge lengths
+    pub fn perimeter(&self) -> f32 {
+        self.edges.iter().map(Edge::length).sum()
+    } // fn perimeter
+
+    /// Sector centroid calculation function
+    /// * Returns area-weighted sector centroid (mean of points for degenerate sectors)
+    pub fn centroid(&self) -> Vec2f {
+        let area = self.area();
+
+        if area == 0.0 {
+            if self.edges.is_empty() {
+                return Vec2f::new(0.0, 0.0);
+            }
+
+            return self.edges
+                .iter()
+                .fold(Vec2f::new(0.0, 0.0), |sum, edge| sum + edge.p0) * (1.0 / self.edges.len() as f32);
+        }
+
+        self.edges
+            .iter()
+            .fold(Vec2f::new(0.0, 0.0), |sum, edge| sum + (edge.p0 + edge.p1) * (edge.p0 % edge.p1)) * (1.0 / (6.0 * area))
+    } // fn centroid
+
+    /// Sector portal connection set getting function
+    /// * `sector_id` - identifier of this sector
+    /// * Returns set of pairs of this sector with its portal destinations and destination of first portal edge that repeats previous one, if any
+    fn portal_pairs(&self, sector_id: SectorId) -> (HashSet<UnorderedPair<SectorId>>, Option<SectorId>) {
+        let mut pairs = HashSet::new();
+        let mut duplicate = None;
+
+        for edge in &self.edges {
+            if let EdgeType::Portal { dst_sector_id } = edge.ty {
+                if !pairs.insert(UnorderedPair::new(sector_id, dst_sector_id)) && duplicate.is_none() {
+                    duplicate = Some(dst_sector_id);
+                }
+            }
+        }
+
+        (pairs, duplicate)
+    } // fn portal_pairs
 }
 
 impl std::fmt::Display for Sector {
@@ -109,6 +279,46 @@ impl std::fmt::Display for Sector {
 } // fn Sector
 
 
+/// Ray and map wall intersection representation structure
+#[derive(Copy, Clone, Debug)]
+pub struct RayHit {
+    /// Hit point
+    pub point: Vec2f,
+    /// Distance from ray origin to hit point
+    pub distance: f32,
+    /// Identifier of sector hit wall belongs to
+    pub sector_id: SectorId,
+    /// Hit wall edge
+    pub edge: Edge,
+} // struct RayHit
+
+/// Maximal count of sectors ray may pass through during `Map::cast_ray`
+pub const CAST_RAY_MAX_SECTORS: usize = 256;
+
+/// Maximal count of walls movement may slide along during single `Map::move_and_slide` call
+const MOVE_AND_SLIDE_ITERATIONS: usize = 4;
+
+/// Minimal distance `Map::move_and_slide` keeps to walls, so resulting location is never located exactly on sector border
+const MOVE_AND_SLIDE_MIN_RADIUS: f32 = 1e-4;
+
+/// Gameplay effects sector applies to player representation structure
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SectorEffects {
+    /// Free fall acceleration
+    pub gravity: f32,
+    /// Damage player takes every second being in sector
+    pub damage_per_second: f32,
+} // struct SectorEffects
+
+impl Default for SectorEffects {
+    fn default() -> Self {
+        Self {
+            gravity: 9.81,
+            damage_per_second: 0.0,
+        }
+    }
+} // impl Default for SectorEffects
+
 /// Map camera state representaiton structure
 pub struct MapCameraState {
     /// Camera location
@@ -119,10 +329,91 @@ pub struct MapCameraState {
     pub angle: f32,
 } // struct MapCameraState
 
+/// Uniform grid of sector identifiers, used to accelerate point queries
+struct SectorGrid {
+    /// Grid origin
+    origin: Vec2f,
+    /// Inverse cell size
+    inv_cell_size: Vec2f,
+    /// Grid size in cells
+    width: usize,
+    /// Grid size in cells
+    height: usize,
+    /// Per-cell lists of sectors, which bounds overlap cell, sorted by sector index
+    cells: Vec<Vec<SectorId>>,
+} // struct SectorGrid
+
+impl SectorGrid {
+    /// Grid building function
+    /// * `sector_bounds` - bounding rectangles of map sectors
+    /// * Returns grid with approximately one cell per sector
+    fn new(sector_bounds: &[Rect<f32>]) -> Self {
+        let mut min = Vec2f::new(f32::MAX, f32::MAX);
+        let mut max = Vec2f::new(f32::MIN, f32::MIN);
+
+        for bounds in sector_bounds {
+            min = Vec2f::new(min.x.min(bounds.x.start), min.y.min(bounds.y.start));
+            max = Vec2f::new(max.x.max(bounds.x.end), max.y.max(bounds.y.end));
+        }
+
+        if sector_bounds.is_empty() {
+            min = Vec2f::new(0.0, 0.0);
+            max = Vec2f::new(0.0, 0.0);
+        }
+
+        let side = ((sector_bounds.len() as f32).sqrt().ceil() as usize).clamp(1, 256);
+        let inv_cell_size = |extent: f32| if extent > 0.0 { side as f32 / extent } else { 0.0 };
+
+        let mut grid = Self {
+            origin: min,
+            inv_cell_size: Vec2f::new(inv_cell_size(max.x - min.x), inv_cell_size(max.y - min.y)),
+            width: side,
+            height: side,
+            cells: (0..side * side).map(|_| Vec::new()).collect(),
+        };
+
+        for (index, bounds) in sector_bounds.iter().enumerate() {
+            let (x0, y0) = grid.get_cell(bounds.start());
+            let (x1, y1) = grid.get_cell(bounds.end());
+
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    grid.cells[y * grid.width + x].push(SectorId::new(index as u32));
+                }
+            }
+        }
+
+        grid
+    } // fn new
+
+    /// Cell by point getting function
+    /// * `point` - point to get cell of
+    /// * Returns cell coordinates, points outside of grid are clamped to border cells
+    fn get_cell(&self, point: Vec2f) -> (usize, usize) {
+        (
+            (((point.x - self.origin.x) * self.inv_cell_size.x) as isize).clamp(0, self.width as isize - 1) as usize,
+            (((point.y - self.origin.y) * self.inv_cell_size.y) as isize).clamp(0, self.height as isize - 1) as usize,
+        )
+    } // fn get_cell
+
+    /// Point candidate sectors getting function
+    /// * `point` - point to get candidates for
+    /// * Returns identifiers of sectors that may contain point, sorted by index
+    fn get_candidates(&self, point: Vec2f) -> &[SectorId] {
+        let (x, y) = self.get_cell(point);
+        &self.cells[y * self.width + x]
+    } // fn get_candidates
+} // impl SectorGrid
+
 /// Map representation structure
 pub struct Map {
     sectors: Vec<Sector>,
 
+    /// Sector bounding rectangles
+    sector_bounds: Vec<Rect<f32>>,
+    /// Sector point query acceleration grid
+    sector_grid: SectorGrid,
+
     /// Map camera parameters
     pub camera_location: Vec2f,
     pub camera_height: f32,
@@ -137,7 +428,7 @@ pub struct CameraInfo {
 } // sturct CameraInfo
 
 /// Sector unique identifier represetnation structure
-#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct SectorId(u32);
 
 impl SectorId {
@@ -156,15 +447,52 @@ impl SectorId {
 } // impl SectorId
 
 impl Map {
+    /// Map from sectors create function
+    /// * `sectors` - map sectors
+    /// * `camera` - map camera parameters
+    /// * Returns map with sector acceleration structures built
+    fn new(sectors: Vec<Sector>, camera: CameraInfo) -> Self {
+        let sector_bounds = sectors
+            .iter()
+            .map(|sector| {
+                let mut min = Vec2f::new(f32::MAX, f32::MAX);
+                let mut max = Vec2f::new(f32::MIN, f32::MIN);
+
+                for edge in &sector.edges {
+                    min = Vec2f::new(min.x.min(edge.p0.x), min.y.min(edge.p0.y));
+                    max = Vec2f::new(max.x.max(edge.p0.x), max.y.max(edge.p0.y));
+                }
+
+                Rect::new(min.x..max.x, min.y..max.y)
+            })
+            .collect::<Vec<Rect<f32>>>();
+
+        Self {
+            sector_grid: SectorGrid::new(&sector_bounds),
+            sector_bounds,
+            sectors,
+            camera_location: camera.location,
+            camera_height: camera.height,
+            camera_rotation: camera.rotation,
+        }
+    } // fn new
+
     /// Map sector by point finding function
     /// * `location` - point that must be contained in resulting sector
     /// * Returns option of identifier of sector that contains `location` point
     pub fn find_sector(&self, location: Vec2f) -> Option<SectorId> {
-        self.sectors
+        self.sector_grid
+            .get_candidates(location)
             .iter()
-            .enumerate()
-            .find(|(_, sector)| sector.contains(location))
-            .map(|(index, _)| SectorId::new(index as u32))
+            .copied()
+            .find(|id| {
+                let index = id.as_u32() as usize;
+                let bounds = &self.sector_bounds[index];
+
+                bounds.x.start <= location.x && location.x <= bounds.x.end &&
+                bounds.y.start <= location.y && location.y <= bounds.y.end &&
+                self.sectors[index].contains(location)
+            })
     } // fn find_sector
 
     /// Map sector with adjacent one finding function
@@ -172,29 +500,36 @@ impl Map {
     /// * `adjacent_for` - point that resulting sector may be adjacent with
     /// * Returns option of identifier of sector that contains `location` point
     pub fn find_adjacent_sector(&self, location: Vec2f, adjacent_for: SectorId) -> Option<SectorId> {
-        let sector = match self.get_sector(adjacent_for) {
-            Some(sector) => sector,
-            None => return None,
-        };
+        self.find_adjacent_sector_with_edge(location, adjacent_for).map(|(id, _)| id)
+    } // fn find_adjacent_sector
+
+    /// Map sector with adjacent one and crossed edge finding function
+    /// * `location` - point that must be contained in resulting sector
+    /// * `adjacent_for` - point that resulting sector may be adjacent with
+    /// * Returns option of identifier of sector that contains `location` point and index of `adjacent_for` sector portal edge crossed to reach it (None if `location` is still in `adjacent_for` sector)
+    pub fn find_adjacent_sector_with_edge(&self, location: Vec2f, adjacent_for: SectorId) -> Option<(SectorId, Option<usize>)> {
+        let sector = self.get_sector(adjacent_for)?;
 
         if sector.contains(location) {
-            return Some(adjacent_for);
+            return Some((adjacent_for, None));
         }
 
         // Try find in adjoint edges or just find if not
         sector.edges
             .iter()
-            .filter_map(|edge| if let EdgeType::Portal{dst_sector_id} = edge.ty {
-                Some(dst_sector_id)
+            .enumerate()
+            .filter(|(_, edge)| !edge.portal_attributes.blocking)
+            .filter_map(|(edge_index, edge)| if let EdgeType::Portal{dst_sector_id} = edge.ty {
+                Some((edge_index, dst_sector_id))
             } else {
                 None
             })
-            .filter_map(|id| self.sectors
+            .filter_map(|(edge_index, id)| self.sectors
                 .get(id.as_u32() as usize)
-                .map(|sector| (id, sector)))
-            .find(|(_, sector)| sector.contains(location))
-            .map(|(id, _)| id)
-    } // fn find_adjacent_sector
+                .map(|sector| (edge_index, id, sector)))
+            .find(|(_, _, sector)| sector.contains(location))
+            .map(|(edge_index, id, _)| (id, Some(edge_index)))
+    } // fn find_adjacent_sector_with_edge
 
     /// Trying to find adjacent sector and if not, find any suiting.
     /// * `location` - location of point resulting sector required to contain
@@ -205,16 +540,465 @@ impl Map {
             .or_else(|| self.find_sector(location))
     } // pub fn find_sector_from_old
 
+    /// Edge passability checking function
+    /// * `sector` - sector edge belongs to
+    /// * `edge` - edge to check
+    /// * Returns destination sector identifier if edge is non-blocking portal to sector which floor/ceiling range overlaps `sector` one
+    fn get_passable_portal_destination(&self, sector: &Sector, edge: &Edge) -> Option<SectorId> {
+        let dst_sector_id = match edge.ty {
+            EdgeType::Portal { dst_sector_id } if !edge.portal_attributes.blocking => dst_sector_id,
+            _ => return None,
+        };
+
+        self.get_sector(dst_sector_id)
+            .filter(|dst_sector| dst_sector.floor.max(sector.floor) < dst_sector.ceiling.min(sector.ceiling))
+            .map(|_| dst_sector_id)
+    } // fn get_passable_portal_destination
+
+    /// Movement with collision response function. Motion blocked by walls is projected along them, so body slides instead of stopping.
+    /// Walls and impassable portals (blocking ones and ones to sectors without floor/ceiling overlap) of `sector` and its passable neighbours are considered.
+    /// * `from` - body location
+    /// * `desired` - desired body displacement
+    /// * `sector` - identifier of sector containing `from`
+    /// * `radius` - minimal distance body keeps to walls
+    /// * Returns resolved location and identifier of sector containing it (`from` and `sector` if location can't be resolved)
+    pub fn move_and_slide(&self, from: Vec2f, desired: Vec2f, sector: SectorId, radius: f32) -> (Vec2f, SectorId) {
+        let start_sector = match self.get_sector(sector) {
+            Some(start_sector) => start_sector,
+            None => return (from, sector),
+        };
+
+        // Inward (pointing into sector) normal of sector edge
+        let inward_normal = |sector: &Sector, edge: &Edge| -> Option<Vec2f> {
+            let length = edge.direction.length();
+            if length == 0.0 {
+                return None;
+            }
+
+            Some(Vec2f::new(-edge.direction.y, edge.direction.x) * (sector.area().signum() / length))
+        };
+
+        // Blocking segments: (first point, second point, inward normal)
+        let mut walls = Vec::<(Vec2f, Vec2f, Vec2f)>::new();
+
+        for edge in &start_sector.edges {
+            let dst_sector = match self.get_passable_portal_destination(start_sector, edge).and_then(|id| self.get_sector(id)) {
+                Some(dst_sector) => dst_sector,
+                None => {
+                    walls.extend(inward_normal(start_sector, edge).map(|normal| (edge.p0, edge.p1, normal)));
+                    continue;
+                }
+            };
+
+            for dst_edge in &dst_sector.edges {
+                if self.get_passable_portal_destination(dst_sector, dst_edge).is_none() {
+                    walls.extend(inward_normal(dst_sector, dst_edge).map(|normal| (dst_edge.p0, dst_edge.p1, normal)));
+                }
+            }
+        }
+
+        let radius = radius.max(MOVE_AND_SLIDE_MIN_RADIUS);
+        let mut motion = desired;
+        let mut resolved = false;
+
+        for _ in 0..MOVE_AND_SLIDE_ITERATIONS {
+            let target = from + motion;
+
+            // First found wall motion is blocked by: (wall normal, minimal allowed motion along normal)
+            let blocking = walls.iter().find_map(|&(p0, p1, normal)| {
+                let direction = p1 - p0;
+                let direction_length2 = direction.length2();
+
+                // Motion leaves sector through wall (small tolerance allows to slide exactly along wall)
+                let denominator = motion % direction;
+                if ((from - p0) ^ normal) >= 0.0 && ((target - p0) ^ normal) < -1e-5 && denominator != 0.0 {
+                    let u = ((p0 - from) % motion) / denominator;
+
+                    if (0.0..=1.0).contains(&u) {
+                        return Some((normal, (radius - ((from - p0) ^ normal)).min(0.0)));
+                    }
+                }
+
+                // Motion ends too close to wall
+                let get_parameter = |point: Vec2f| ((point - p0) ^ direction) / direction_length2;
+                let target_parameter = get_parameter(target);
+                let target_closest = p0 + direction * target_parameter.clamp(0.0, 1.0);
+                let target_offset = target - target_closest;
+                let target_distance = target_offset.length();
+                let from_distance = (from - (p0 + direction * get_parameter(from).clamp(0.0, 1.0))).length();
+
+                if target_distance < radius - 1e-5 && target_distance < from_distance {
+                    // Wall ends are pushed away from in radial direction
+                    let normal = if (0.0..=1.0).contains(&target_parameter) || target_distance < 1e-5 {
+                        normal
+                    } else {
+                        target_offset * (1.0 / target_distance)
+                    };
+
+                    return Some((normal, (radius - ((from - target_closest) ^ normal)).min(0.0)));
+                }
+
+                None
+            });
+
+            match blocking {
+                Some((normal, allowed_normal_motion)) => {
+                    // Motion is cut to wall contact, the rest is projected along wall
+                    let normal_motion = motion ^ normal;
+
+                    if allowed_normal_motion <= normal_motion {
+                        break;
+                    }
+                    motion += normal * (allowed_normal_motion - normal_motion);
+                }
+                None => {
+                    resolved = true;
+                    break;
+                }
+            }
+        }
+
+        if !resolved {
+            return (from, sector);
+        }
+
+        let location = from + motion;
+
+        match self.find_sector_from_old(location, sector) {
+            Some(new_sector) => (location, new_sector),
+            None => (from, sector),
+        }
+    } // fn move_and_slide
+
+    /// Ray casting function. Ray passes through portals and stops at first wall.
+    /// * `origin` - ray origin, must be located in `start_sector`
+    /// * `dir` - ray direction, isn't required to be normalized
+    /// * `start_sector` - identifier of sector containing `origin`
+    /// * Returns hit wall or None if direction is zero, ray leaves map through portal to unknown sector or passes through more than `CAST_RAY_MAX_SECTORS` sectors
+    pub fn cast_ray(&self, origin: Vec2f, dir: Vec2f, start_sector: SectorId) -> Option<RayHit> {
+        let dir_length = dir.length();
+        if dir_length.is_nan() || dir_length <= 0.0 {
+            return None;
+        }
+        let dir = dir * (1.0 / dir_length);
+
+        let mut sector_id = start_sector;
+
+        for _ in 0..CAST_RAY_MAX_SECTORS {
+            let sector = self.get_sector(sector_id)?;
+
+            // Sector is convex, so ray line intersects its border twice and ray exits sector at the farthest intersection
+            let mut exit: Option<(f32, &Edge)> = None;
+
+            for edge in &sector.edges {
+                let denominator = dir % edge.direction;
+                if denominator == 0.0 {
+                    continue;
+                }
+
+                let to_p0 = edge.p0 - origin;
+                let t = (to_p0 % edge.direction) / denominator;
+                let u = (to_p0 % dir) / denominator;
+
+                if !(0.0..=1.0).contains(&u) {
+                    continue;
+                }
+
+                if exit.is_none_or(|(exit_t, _)| t > exit_t) {
+                    exit = Some((t, edge));
+                }
+            }
+
+            let (t, edge) = exit?;
+
+            match edge.ty {
+                EdgeType::Portal { dst_sector_id } => sector_id = dst_sector_id,
+                EdgeType::Wall => {
+                    let t = t.max(0.0);
+
+                    return Some(RayHit {
+                        point: origin + dir * t,
+                        distance: t,
+                        sector_id,
+                        edge: *edge,
+                    });
+                }
+            }
+        }
+
+        None
+    } // fn cast_ray
+
     /// Sector by identifier getting function
     /// * `id` - sector identifier
     /// * Returns option of required sector reference.
     pub fn get_sector(&self, id: SectorId) -> Option<&Sector> {
         self.sectors.get(id.as_u32() as usize)
     } // fn get_sector
+
+    /// Sector gameplay effects getting function
+    /// * `id` - sector identifier
+    /// * Returns effects derived from sector `gravity=<float>` and `damage=<float>` tags (defaults for unknown sector or missing tags)
+    pub fn sector_effects(&self, id: SectorId) -> SectorEffects {
+        let mut effects = SectorEffects::default();
+
+        for tag in self.get_sector(id).iter().flat_map(|sector| sector.tags.iter()) {
+            if let Some((key, value)) = tag.split_once('=') {
+                match (key, value.parse::<f32>()) {
+                    ("gravity", Ok(gravity)) => effects.gravity = gravity,
+                    ("damage", Ok(damage)) => effects.damage_per_second = damage,
+                    _ => {}
+                }
+            }
+        }
+
+        effects
+    } // fn sector_effects
+
+    /// Floor height at point getting function
+    /// * `location` - point to get floor height at
+    /// * Returns floor height of sector containing `location` if there is one
+    pub fn floor_height_at(&self, location: Vec2f) -> Option<f32> {
+        self.find_sector(location)
+            .and_then(|id| self.get_sector(id))
+            .map(|sector| sector.floor)
+    } // fn floor_height_at
+
+    /// Sector floor and ceiling heights setting function
+    /// * `id` - identifier of sector to edit
+    /// * `floor` - new floor height
+    /// * `ceiling` - new ceiling height, must be above `floor`
+    /// * Returns Ok if heights are set, error otherwise (sector is unchanged in this case)
+    pub fn set_sector_heights(&mut self, id: SectorId, floor: f32, ceiling: f32) -> Result<(), SectorEditError> {
+        let sector = self.sectors
+            .get_mut(id.as_u32() as usize)
+            .ok_or(SectorEditError::UnknownSector { sector_id: id })?;
+
+        if floor.is_nan() || ceiling.is_nan() || floor >= ceiling {
+            return Err(SectorEditError::InvalidBounds { sector_id: id });
+        }
+
+        // Heights aren't cached anywhere, renderer and collision code read them from sector directly
+        sector.floor = floor;
+        sector.ceiling = ceiling;
+
+        Ok(())
+    } // fn set_sector_heights
+
+    /// Edge texture setting function
+    /// * `sector_id` - identifier of sector edge belongs to
+    /// * `edge_index` - index of edge in sector
+    /// * `texture` - texture to set, None to fill wall with color
+    /// * Returns Ok if edge exists, error otherwise
+    pub fn set_edge_texture(&mut self, sector_id: SectorId, edge_index: usize, texture: Option<TextureId>) -> Result<(), SectorEditError> {
+        let sector = self.sectors
+            .get_mut(sector_id.as_u32() as usize)
+            .ok_or(SectorEditError::UnknownSector { sector_id })?;
+
+        sector.edges
+            .get_mut(edge_index)
+            .ok_or(SectorEditError::UnknownEdge { sector_id, edge_index })?
+            .texture = texture;
+
+        Ok(())
+    } // fn set_edge_texture
+
+    /// Edge texture vertical alignment setting function
+    /// * `sector_id` - identifier of sector edge belongs to
+    /// * `edge_index` - index of edge in sector
+    /// * `texture_align` - texture alignment to set
+    /// * Returns Ok if edge exists, error otherwise
+    pub fn set_edge_texture_align(&mut self, sector_id: SectorId, edge_index: usize, texture_align: TextureAlign) -> Result<(), SectorEditError> {
+        let sector = self.sectors
+            .get_mut(sector_id.as_u32() as usize)
+            .ok_or(SectorEditError::UnknownSector { sector_id })?;
+
+        sector.edges
+            .get_mut(edge_index)
+            .ok_or(SectorEditError::UnknownEdge { sector_id, edge_index })?
+            .texture_align = texture_align;
+
+        Ok(())
+    } // fn set_edge_texture_align
+
+    /// Sector floor and ceiling textures setting function
+    /// * `id` - identifier of sector to edit
+    /// * `floor_texture` - floor texture, None to fill floor with color
+    /// * `ceiling_texture` - ceiling texture, None to fill ceiling with color
+    /// * Returns Ok if sector exists, error otherwise
+    pub fn set_sector_textures(&mut self, id: SectorId, floor_texture: Option<TextureId>, ceiling_texture: Option<TextureId>) -> Result<(), SectorEditError> {
+        let sector = self.sectors
+            .get_mut(id.as_u32() as usize)
+            .ok_or(SectorEditError::UnknownSector { sector_id: id })?;
+
+        sector.floor_texture = floor_texture;
+        sector.ceiling_texture = ceiling_texture;
+
+        Ok(())
+    } // fn set_sector_textures
 } // impl Map
 
+/// Sector editing error
+#[derive(Debug, Clone, PartialEq)]
+pub enum SectorEditError {
+    /// Sector doesn't exist
+    UnknownSector {
+        sector_id: SectorId,
+    },
+    /// Sector floor isn't below ceiling (or any of them is NaN)
+    InvalidBounds {
+        sector_id: SectorId,
+    },
+    /// Sector has no edge with such index
+    UnknownEdge {
+        sector_id: SectorId,
+        edge_index: usize,
+    },
+} // enum SectorEditError
+
+/// Map building error
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapBuildingError {
+    /// Sector has less than 3 edges
+    NotEnoughPoints {
+        sector_id: SectorId,
+    },
+    /// Sector floor isn't below ceiling (or any of them is NaN)
+    InvalidBounds {
+        sector_id: SectorId,
+    },
+    /// Sector edges don't form convex polygon (see `Sector::is_convex`)
+    NonConvexSector {
+        sector_id: SectorId,
+    },
+    /// Portal references sector that doesn't exist
+    UnknownSectorReferenced {
+        sector_id: SectorId,
+        dst_sector_id: SectorId,
+    },
+} // enum MapBuildingError
+
+/// Incremental map builder
+pub struct MapBuilder {
+    sectors: Vec<Sector>,
+    camera: CameraInfo,
+} // struct MapBuilder
+
+impl MapBuilder {
+    /// New map builder create function
+    /// * Returns builder of map without sectors
+    pub fn new() -> Self {
+        Self {
+            sectors: Vec::new(),
+            camera: CameraInfo {
+                location: Vec2f::new(0.0, 0.0),
+                height: 0.3,
+                rotation: 0.0,
+            },
+        }
+    } // fn new
+
+    /// Sector adding function
+    /// * `points` - sector polygon points, each with type of edge starting at it
+    /// * `floor` - sector floor height
+    /// * `ceiling` - sector ceiling height
+    /// * Returns identifier of added sector
+    pub fn add_sector(&mut self, points: &[(Vec2f, EdgeType)], floor: f32, ceiling: f32) -> SectorId {
+        let id = SectorId::new(self.sectors.len() as u32);
+
+        self.sectors.push(Sector {
+            edges: Edge::loop_from_points(points.iter().copied()).collect(),
+            floor,
+            ceiling,
+            render_distance: None,
+            tags: Vec::new(),
+            floor_color: DEFAULT_FLOOR_COLOR,
+            ceiling_color: DEFAULT_CEILING_COLOR,
+            floor_texture: None,
+            ceiling_texture: None,
+        });
+
+        id
+    } // fn add_sector
+
+    /// Sector tag adding function
+    /// * `sector_id` - identifier of sector to add tag to
+    /// * `tag` - tag to add
+    pub fn add_sector_tag(&mut self, sector_id: SectorId, tag: &str) {
+        if let Some(sector) = self.sectors.get_mut(sector_id.as_u32() as usize) {
+            sector.tags.push(tag.to_string());
+        }
+    } // fn add_sector_tag
+
+    /// Sector floor and ceiling colors setting function
+    /// * `sector_id` - identifier of sector to set colors of
+    /// * `floor_color` - floor color
+    /// * `ceiling_color` - ceiling color
+    pub fn set_sector_colors(&mut self, sector_id: SectorId, floor_color: u32, ceiling_color: u32) {
+        if let Some(sector) = self.sectors.get_mut(sector_id.as_u32() as usize) {
+            sector.floor_color = floor_color;
+            sector.ceiling_color = ceiling_color;
+        }
+    } // fn set_sector_colors
+
+    /// Sector render distance setting function
+    /// * `sector_id` - identifier of sector to set render distance of
+    /// * `render_distance` - maximal distance sector is rendered at, None for unlimited
+    pub fn set_sector_render_distance(&mut self, sector_id: SectorId, render_distance: Option<f32>) {
+        if let Some(sector) = self.sectors.get_mut(sector_id.as_u32() as usize) {
+            sector.render_distance = render_distance;
+        }
+    } // fn set_sector_render_distance
+
+    /// Camera parameters setting function
+    /// * `location` - camera location
+    /// * `height` - camera height
+    /// * `rotation` - camera rotation angle
+    pub fn set_camera(&mut self, location: Vec2f, height: f32, rotation: f32) {
+        self.camera = CameraInfo { location, height, rotation };
+    } // fn set_camera
+
+    /// Map building function
+    /// * Returns built map or error if some of sectors are invalid
+    pub fn build(self) -> Result<Map, MapBuildingError> {
+        for (index, sector) in self.sectors.iter().enumerate() {
+            let sector_id = SectorId::new(index as u32);
+
+            if sector.edges.len() < 3 {
+                return Err(MapBuildingError::NotEnoughPoints { sector_id });
+            }
+
+            if sector.floor.is_nan() || sector.ceiling.is_nan() || sector.floor >= sector.ceiling {
+                return Err(MapBuildingError::InvalidBounds { sector_id });
+            }
+
+            // Same invariant as WMT loader enforces, point queries and rendering require convex sectors
+            if !sector.is_convex() {
+                return Err(MapBuildingError::NonConvexSector { sector_id });
+            }
+
+            for edge in &sector.edges {
+                if let EdgeType::Portal { dst_sector_id } = edge.ty {
+                    if dst_sector_id.as_u32() as usize >= self.sectors.len() {
+                        return Err(MapBuildingError::UnknownSectorReferenced { sector_id, dst_sector_id });
+                    }
+                }
+            }
+        }
+
+        Ok(Map::new(self.sectors, self.camera))
+    } // fn build
+} // impl MapBuilder
+
+impl Default for MapBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// WMT map loading error kind
 #[derive(Debug, Clone)]
-pub enum Wmt2LoadingError {
+pub enum Wmt2LoadingErrorKind {
     /// Unknown directive
     UnknownDirective(String),
 
@@ -236,15 +1020,119 @@ pub enum Wmt2LoadingError {
     /// Sector with value not
     UnknownSectorReferenced(String),
 
+    /// Portal chunk references edge that doesn't exist or isn't portal
+    InvalidPortalEdge(String),
+
+    /// Unknown or malformed portal attribute
+    InvalidPortalAttribute(String),
+
+    /// Malformed hexadecimal color
+    InvalidColor(String),
+
+    /// Sector isn't convex or has zero-length edges
+    NonConvexSector(String),
+
+    /// Sector has several portal edges to the same sector
+    DuplicatePortal {
+        from: String,
+        to: String,
+    },
+
+    /// Portal destination sector has no portal back (reported by `Map::load_from_wmt_validated` only)
+    NonReciprocalPortal {
+        from: String,
+        to: String,
+    },
+
     /// Some other error
     Other(String),
-} //
+} // enum Wmt2LoadingErrorKind
+
+impl std::fmt::Display for Wmt2LoadingErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownDirective(directive) => write!(f, "unknown directive \"#{}\"", directive),
+            Self::FloatParsingError(error) => write!(f, "invalid number: {}", error),
+            Self::NotEnoughCameraParameters => f.write_str("not enough camera parameters, location, height and rotation expected"),
+            Self::NoSectorBoundaries => f.write_str("no ':' after sector name"),
+            Self::NoSectorEdgesStart => f.write_str("no '[' before sector edge list"),
+            Self::NotEnoughPointCoordinates => f.write_str("not enough point coordinates, \"x/y\" pair expected"),
+            Self::UnknownSectorReferenced(name) => write!(f, "unknown sector \"{}\" referenced", name),
+            Self::InvalidPortalEdge(edge) => write!(f, "invalid portal edge \"{}\"", edge),
+            Self::InvalidPortalAttribute(attribute) => write!(f, "invalid portal attribute \"{}\"", attribute),
+            Self::InvalidColor(color) => write!(f, "invalid color \"{}\"", color),
+            Self::NonConvexSector(name) => write!(f, "sector \"{}\" isn't convex", name),
+            Self::DuplicatePortal { from, to } => write!(f, "sector \"{}\" has several portals to sector \"{}\"", from, to),
+            Self::NonReciprocalPortal { from, to } => write!(f, "portal from sector \"{}\" to sector \"{}\" has no portal back", from, to),
+            Self::Other(message) => f.write_str(message),
+        }
+    } // fn fmt
+} // impl std::fmt::Display for Wmt2LoadingErrorKind
+
+/// WMT map loading error
+#[derive(Debug, Clone)]
+pub struct Wmt2LoadingError {
+    /// Number of line (1-based) error occured at
+    pub line: usize,
+    /// Error kind
+    pub kind: Wmt2LoadingErrorKind,
+} // struct Wmt2LoadingError
+
+impl std::fmt::Display for Wmt2LoadingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    } // fn fmt
+} // impl std::fmt::Display for Wmt2LoadingError
+
+impl std::error::Error for Wmt2LoadingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            Wmt2LoadingErrorKind::FloatParsingError(error) => Some(error),
+            _ => None,
+        }
+    } // fn source
+} // impl std::error::Error for Wmt2LoadingError
+
+/// Default count of decimal digits map coordinates are saved with
+pub const DEFAULT_WMT_PRECISION: usize = 4;
+
+/// WMT hexadecimal color parsing function
+/// * `color` - color hexadecimal digits
+/// * `digit_count` - required count of digits (6 for RRGGBB colors, 8 for AARRGGBB ones)
+/// * Returns parsed color, None if there's other count of digits or they aren't hexadecimal
+fn parse_hex_color(color: &str, digit_count: usize) -> Option<u32> {
+    if color.len() != digit_count || !color.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    u32::from_str_radix(color, 16).ok()
+} // fn parse_hex_color
 
 impl Map {
+    /// Map from WMT format loading function
+    /// * `source` - WMT map source
+    /// * Returns loaded map or loading error
     pub fn load_from_wmt(source: &str) -> Result<Map, Wmt2LoadingError> {
+        Self::load_from_wmt_impl(source, false)
+    } // fn load_from_wmt
+
+    /// Map from WMT format loading function, that also requires all portals to be two-way
+    /// * `source` - WMT map source
+    /// * Returns loaded map or loading error
+    pub fn load_from_wmt_validated(source: &str) -> Result<Map, Wmt2LoadingError> {
+        Self::load_from_wmt_impl(source, true)
+    } // fn load_from_wmt_validated
+
+    /// Map from WMT format loading function
+    /// * `source` - WMT map source
+    /// * `validate` - check that every portal destination sector has portal back
+    /// * Returns loaded map or loading error
+    fn load_from_wmt_impl(source: &str, validate: bool) -> Result<Map, Wmt2LoadingError> {
         enum ChunkType {
             Sector,
             Camera,
+            Tags,
+            Portal,
             None,
         }
         let mut mode = ChunkType::None;
@@ -257,17 +1145,25 @@ impl Map {
         struct RawSectorPoint {
             base_point: Vec2f,
             dst_sector_name: Option<String>,
+            color: Option<u32>,
         }
 
         struct RawSector {
+            line: usize,
             floor: f32,
             ceiling: f32,
+            floor_color: u32,
+            ceiling_color: u32,
             points: Vec<RawSectorPoint>,
         }
 
         let mut raw_sectors = BTreeMap::new();
+        let mut raw_tags = BTreeMap::<String, (usize, Vec<String>)>::new();
+        let mut raw_portals = Vec::<(usize, String, usize, PortalAttributes)>::new();
+
+        for (line_index, mut line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
 
-        for mut line in source.lines() {
             // Cut comments
             if let Some(i) = line.find("//") {
                 line = line.split_at(i).0
@@ -278,101 +1174,1042 @@ impl Map {
                 continue;
             }
 
-            if line.starts_with("#") {
-                if line.starts_with("#sector") {
-                    mode = ChunkType::Sector;
-                } else if line.starts_with("#camera") {
-                    mode = ChunkType::Camera;
-                } else {
-                    return Err(Wmt2LoadingError::UnknownDirective(line.get(1..).unwrap().into()))
+            // Parse line, all errors are attributed to it
+            (|| -> Result<(), Wmt2LoadingErrorKind> {
+                if line.starts_with("#") {
+                    if line.starts_with("#sector") {
+                        mode = ChunkType::Sector;
+                    } else if line.starts_with("#camera") {
+                        mode = ChunkType::Camera;
+                    } else if line.starts_with("#tags") {
+                        mode = ChunkType::Tags;
+                    } else if line.starts_with("#portal") {
+                        mode = ChunkType::Portal;
+                    } else {
+                        return Err(Wmt2LoadingErrorKind::UnknownDirective(line.get(1..).unwrap().into()))
+                    }
+                    return Ok(());
                 }
-                continue;
-            }
 
-            match mode {
-                ChunkType::Camera => {
-                    // Parsing camera information in single fucking line
-                    [camera.location.x, camera.location.y, camera.height, camera.rotation] = line
-                        .chars()
-                        .filter(|v| !v.is_whitespace())
-                        .collect::<String>()
-                        .split(',')
-                        .map(|v| v.parse::<f32>())
-                        .collect::<Result<Vec<f32>, _>>()
-                        .map_err(|e| Wmt2LoadingError::FloatParsingError(e))?
-                        .try_into()
-                        .map_err(|_| Wmt2LoadingError::NotEnoughCameraParameters)?;
-                }
-                ChunkType::Sector => {
-                    fn parse_pair(pair: &str) -> Result<(f32, f32), Wmt2LoadingError> {
-                        let mut s = pair.split('/').map(|v| v.parse::<f32>().map_err(|e| Wmt2LoadingError::FloatParsingError(e)));
-                        let (x, y) = s.next().zip(s.next()).ok_or(Wmt2LoadingError::NotEnoughPointCoordinates)?;
-                        Ok((x?, y?))
+                match mode {
+                    ChunkType::Camera => {
+                        // Parsing camera information in single fucking line
+                        [camera.location.x, camera.location.y, camera.height, camera.rotation] = line
+                            .chars()
+                            .filter(|v| !v.is_whitespace())
+                            .collect::<String>()
+                            .split(',')
+                            .map(|v| v.parse::<f32>())
+                            .collect::<Result<Vec<f32>, _>>()
+                            .map_err(|e| Wmt2LoadingErrorKind::FloatParsingError(e))?
+                            .try_into()
+                            .map_err(|_| Wmt2LoadingErrorKind::NotEnoughCameraParameters)?;
                     }
+                    ChunkType::Sector => {
+                        fn parse_pair(pair: &str) -> Result<(f32, f32), Wmt2LoadingErrorKind> {
+                            let mut s = pair.split('/').map(|v| v.parse::<f32>().map_err(|e| Wmt2LoadingErrorKind::FloatParsingError(e)));
+                            let (x, y) = s.next().zip(s.next()).ok_or(Wmt2LoadingErrorKind::NotEnoughPointCoordinates)?;
+                            Ok((x?, y?))
+                        }
 
-                    let (sector_name, rest) = line.as_str().split_at(line.find(':').ok_or(Wmt2LoadingError::NoSectorBoundaries)?);
-                    let (sector_bounds, rest) = rest[1..].split_at(rest.find('[').ok_or(Wmt2LoadingError::NoSectorEdgesStart)?);
-                    let (floor, ceiling) = parse_pair(&sector_bounds.trim_end_matches('['))?;
+                        let (sector_name, rest) = line.as_str().split_at(line.find(':').ok_or(Wmt2LoadingErrorKind::NoSectorBoundaries)?);
+                        let (sector_bounds, rest) = rest[1..].split_at(rest.find('[').ok_or(Wmt2LoadingErrorKind::NoSectorEdgesStart)?);
+                        // <floor>/<ceiling>[#<RRGGBB floor color>/<RRGGBB ceiling color>]
+                        let sector_bounds = sector_bounds.trim_end_matches('[');
+                        let (sector_bounds, sector_colors) = match sector_bounds.split_once('#') {
+                            Some((sector_bounds, sector_colors)) => (sector_bounds, Some(sector_colors)),
+                            None => (sector_bounds, None),
+                        };
+                        let (floor, ceiling) = parse_pair(sector_bounds)?;
+                        let (floor_color, ceiling_color) = match sector_colors {
+                            Some(sector_colors) => {
+                                let parse_color = |color: &str| parse_hex_color(color, 6)
+                                    .ok_or_else(|| Wmt2LoadingErrorKind::InvalidColor(color.into()));
+                                let (floor_color, ceiling_color) = sector_colors
+                                    .split_once('/')
+                                    .ok_or_else(|| Wmt2LoadingErrorKind::InvalidColor(sector_colors.into()))?;
 
-                    let mut points = Vec::<RawSectorPoint>::new();
+                                (parse_color(floor_color)?, parse_color(ceiling_color)?)
+                            }
+                            None => (DEFAULT_FLOOR_COLOR, DEFAULT_CEILING_COLOR),
+                        };
+
+                        let mut points = Vec::<RawSectorPoint>::new();
+
+                        for pt in rest.trim_end_matches(']').split(',') {
+                            // <x>/<y>[:<portal destination>][#<RRGGBB wall color>]
+                            let (pt, color) = match pt.split_once('#') {
+                                Some((pt, color)) => (pt, Some(
+                                    parse_hex_color(color, 6).ok_or_else(|| Wmt2LoadingErrorKind::InvalidColor(color.into()))?
+                                )),
+                                None => (pt, None),
+                            };
+
+                            let (point_str, dst_sector_name) = pt
+                                .find(':')
+                                .map(|i| {
+                                    let (s, t) = pt.split_at(i);
+                                    (s, Some(t[1..].to_string()))
+                                })
+                                .unwrap_or((pt, None));
 
-                    for pt in rest.trim_end_matches(']').split(',') {
-                        let (point_str, dst_sector_name) = pt
-                            .find(':')
-                            .map(|i| {
-                                let (s, t) = pt.split_at(i);
-                                (s, Some(t[1..].to_string()))
-                            })
-                            .unwrap_or((pt, None));
+                            points.push(RawSectorPoint {
+                                base_point: Vec2f::from_tuple(parse_pair(point_str)?),
+                                dst_sector_name,
+                                color,
+                            });
+                        }
 
-                        points.push(RawSectorPoint {
-                            base_point: Vec2f::from_tuple(parse_pair(point_str)?),
-                            dst_sector_name,
+                        raw_sectors.insert(sector_name.to_owned(), RawSector {
+                            line: line_number,
+                            floor,
+                            ceiling,
+                            floor_color,
+                            ceiling_color,
+                            points,
                         });
                     }
+                    ChunkType::Tags => {
+                        let (sector_name, tags) = line.split_once(':').ok_or(Wmt2LoadingErrorKind::NoSectorBoundaries)?;
 
-                    raw_sectors.insert(sector_name.to_owned(), RawSector {
-                        floor,
-                        ceiling,
-                        points,
-                    });
+                        raw_tags
+                            .entry(sector_name.to_owned())
+                            .or_insert_with(|| (line_number, Vec::new()))
+                            .1
+                            .extend(tags.split(',').filter(|tag| !tag.is_empty()).map(|tag| tag.to_owned()));
+                    }
+                    ChunkType::Portal => {
+                        // <sector name>:<edge index>:<attribute>{,<attribute>}
+                        let mut parts = line.splitn(3, ':');
+                        let (sector_name, edge_index, attributes) = match (parts.next(), parts.next(), parts.next()) {
+                            (Some(sector_name), Some(edge_index), Some(attributes)) => (sector_name, edge_index, attributes),
+                            _ => return Err(Wmt2LoadingErrorKind::InvalidPortalEdge(line.clone())),
+                        };
+                        let edge_index = edge_index.parse::<usize>().map_err(|_| Wmt2LoadingErrorKind::InvalidPortalEdge(line.clone()))?;
+
+                        let mut portal_attributes = PortalAttributes::default();
+
+                        for attribute in attributes.split(',').filter(|attribute| !attribute.is_empty()) {
+                            match attribute.split_once('=') {
+                                Some(("tint", color)) => {
+                                    portal_attributes.tint = Some(parse_hex_color(color, 6).ok_or_else(|| Wmt2LoadingErrorKind::InvalidPortalAttribute(attribute.into()))?);
+                                }
+                                Some(("overlay", color)) => {
+                                    portal_attributes.overlay = Some(parse_hex_color(color, 8).ok_or_else(|| Wmt2LoadingErrorKind::InvalidPortalAttribute(attribute.into()))?);
+                                }
+                                None if attribute == "blocking" => {
+                                    portal_attributes.blocking = true;
+                                }
+                                _ => return Err(Wmt2LoadingErrorKind::InvalidPortalAttribute(attribute.into())),
+                            }
+                        }
+
+                        raw_portals.push((line_number, sector_name.to_owned(), edge_index, portal_attributes));
+                    }
+                    _ => {}
                 }
-                _ => {}
-            }
+
+                Ok(())
+            })().map_err(|kind| Wmt2LoadingError { line: line_number, kind })?;
         }
 
         let name_to_index = raw_sectors.keys().enumerate().map(|(a, b)| (b.clone(), SectorId::new(a as u32))).collect::<BTreeMap<String, SectorId>>();
 
-        Ok(Map {
-            camera_height: camera.height,
-            camera_location: camera.location,
-            camera_rotation: camera.rotation,
-            sectors: raw_sectors
-                .values()
-                .map(|sector| Ok(Sector {
+        if let Some((name, (line, _))) = raw_tags.iter().find(|(name, _)| !name_to_index.contains_key(name.as_str())) {
+            return Err(Wmt2LoadingError { line: *line, kind: Wmt2LoadingErrorKind::UnknownSectorReferenced(name.clone()) });
+        }
+
+        let mut sectors = raw_sectors
+                .iter()
+                .map(|(name, sector)| Ok(Sector {
                     floor: sector.floor,
                     ceiling: sector.ceiling,
+                    render_distance: None,
+                    tags: raw_tags.get(name).map(|(_, tags)| tags.clone()).unwrap_or_default(),
+                    floor_color: sector.floor_color,
+                    ceiling_color: sector.ceiling_color,
+                    floor_texture: None,
+                    ceiling_texture: None,
                     edges: Edge::loop_from_points(sector.points.iter().map(|v| (v.base_point, EdgeType::Wall)))
                         .zip(sector.points.iter())
                         .map(|(mut edge, point)| {
+                            edge.color = point.color;
                             if let Some(dst_name) = point.dst_sector_name.as_ref() {
                                 edge.ty = EdgeType::Portal {
-                                    dst_sector_id: name_to_index.get(dst_name.as_str()).copied().ok_or(Wmt2LoadingError::UnknownSectorReferenced(dst_name.into()))?
+                                    dst_sector_id: name_to_index.get(dst_name.as_str()).copied().ok_or(Wmt2LoadingError {
+                                        line: sector.line,
+                                        kind: Wmt2LoadingErrorKind::UnknownSectorReferenced(dst_name.into()),
+                                    })?
                                 };
                             }
                             Ok(edge)
                         })
                         .collect::<Result<Vec<Edge>, Wmt2LoadingError>>()?,
+                }).and_then(|built: Sector| if built.is_convex() {
+                    Ok(built)
+                } else {
+                    Err(Wmt2LoadingError { line: sector.line, kind: Wmt2LoadingErrorKind::NonConvexSector(name.clone()) })
                 }))
-            .collect::<Result<Vec<Sector>, Wmt2LoadingError>>()?
-        })
-    } // fn load_from_wmt
+            .collect::<Result<Vec<Sector>, Wmt2LoadingError>>()?;
+
+        // Merge explicit portal attributes into parsed geometry
+        for (line, sector_name, edge_index, portal_attributes) in raw_portals {
+            let sector_id = name_to_index.get(sector_name.as_str()).copied().ok_or(Wmt2LoadingError {
+                line,
+                kind: Wmt2LoadingErrorKind::UnknownSectorReferenced(sector_name.clone()),
+            })?;
+
+            match sectors[sector_id.as_u32() as usize].edges.get_mut(edge_index) {
+                Some(edge) if matches!(edge.ty, EdgeType::Portal { .. }) => edge.portal_attributes = portal_attributes,
+                _ => return Err(Wmt2LoadingError {
+                    line,
+                    kind: Wmt2LoadingErrorKind::InvalidPortalEdge(format!("{}:{}", sector_name, edge_index)),
+                }),
+            }
+        }
+
+        // Convex sectors can share single edge only, so several portals between the same sectors are invalid
+        for ((name, raw_sector), (index, sector)) in raw_sectors.iter().zip(sectors.iter().enumerate()) {
+            if let (_, Some(dst_sector_id)) = sector.portal_pairs(SectorId::new(index as u32)) {
+                return Err(Wmt2LoadingError {
+                    line: raw_sector.line,
+                    kind: Wmt2LoadingErrorKind::DuplicatePortal {
+                        from: name.clone(),
+                        to: raw_sectors.keys().nth(dst_sector_id.as_u32() as usize).cloned().unwrap_or_default(),
+                    },
+                });
+            }
+        }
+
+        if validate {
+            for ((name, raw_sector), (index, sector)) in raw_sectors.iter().zip(sectors.iter().enumerate()) {
+                let sector_id = SectorId::new(index as u32);
+
+                for edge in &sector.edges {
+                    let dst_sector_id = match edge.ty {
+                        EdgeType::Portal { dst_sector_id } => dst_sector_id,
+                        EdgeType::Wall => continue,
+                    };
+
+                    let has_portal_back = sectors[dst_sector_id.as_u32() as usize].edges
+                        .iter()
+                        .any(|dst_edge| dst_edge.ty == EdgeType::Portal { dst_sector_id: sector_id });
+
+                    if !has_portal_back {
+                        return Err(Wmt2LoadingError {
+                            line: raw_sector.line,
+                            kind: Wmt2LoadingErrorKind::NonReciprocalPortal {
+                                from: name.clone(),
+                                to: raw_sectors.keys().nth(dst_sector_id.as_u32() as usize).cloned().unwrap_or_default(),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(Map::new(sectors, camera))
+    } // fn load_from_wmt_impl
+
+    /// Map into WMT text format serialization function. Edge and sector textures, texture alignment and sector render distances have no WMT representation, so they aren't round-tripped.
+    /// * Returns map source with `DEFAULT_WMT_PRECISION` digits after decimal point, sectors are named by their indices zero-padded to the same width (`sector00`..`sector11` for 12 sectors), as loader orders sectors by name as strings
+    pub fn save_to_wmt(&self) -> String {
+        self.save_to_wmt_with_precision(DEFAULT_WMT_PRECISION)
+    } // fn save_to_wmt
+
+    /// Map to WMT format with fixed coordinate precision serialization function. Textures, texture alignment and render distances aren't written (see `save_to_wmt`).
+    /// * `decimals` - count of decimal digits to write coordinates with
+    /// * Returns WMT map source
+    pub fn save_to_wmt_with_precision(&self, decimals: usize) -> String {
+        // Same values produce same strings, so shared vertices are snapped identically
+        let format_float = |value: f32| -> String {
+            let mut result = format!("{:.*}", decimals, value);
+
+            if result.contains('.') {
+                result.truncate(result.trim_end_matches('0').trim_end_matches('.').len());
+            }
+            if result == "-0" {
+                result.remove(0);
+            }
+
+            result
+        };
+
+        // Names are zero-padded to keep sector indices same after loading
+        let name_width = self.sectors.len().saturating_sub(1).to_string().len();
+        let sector_name = |id: SectorId| format!("sector{:0width$}", id.as_u32(), width = name_width);
+
+        let mut result = String::from("#sectors\n");
+
+        for (id, sector) in self.iter_indexed_sectors() {
+            let points = sector.edges
+                .iter()
+                .map(|edge| {
+                    let mut point = match edge.ty {
+                        EdgeType::Wall => format!("{}/{}", format_float(edge.p0.x), format_float(edge.p0.y)),
+                        EdgeType::Portal { dst_sector_id } => format!("{}/{}:{}", format_float(edge.p0.x), format_float(edge.p0.y), sector_name(dst_sector_id)),
+                    };
+                    if let Some(color) = edge.color {
+                        point.push_str(&format!("#{:06X}", color));
+                    }
+                    point
+                })
+                .collect::<Vec<String>>()
+                .join(",");
+
+            let colors = if sector.floor_color != DEFAULT_FLOOR_COLOR || sector.ceiling_color != DEFAULT_CEILING_COLOR {
+                format!("#{:06X}/{:06X}", sector.floor_color, sector.ceiling_color)
+            } else {
+                String::new()
+            };
+
+            result.push_str(&format!("{}:{}/{}{}[{}]\n", sector_name(id), format_float(sector.floor), format_float(sector.ceiling), colors, points));
+        }
+
+        if self.sectors.iter().any(|sector| !sector.tags.is_empty()) {
+            result.push_str("\n#tags\n");
+
+            for (id, sector) in self.iter_indexed_sectors().filter(|(_, sector)| !sector.tags.is_empty()) {
+                result.push_str(&format!("{}:{}\n", sector_name(id), sector.tags.join(",")));
+            }
+        }
+
+        let mut portal_chunk = String::new();
+
+        for (id, sector) in self.iter_indexed_sectors() {
+            for (edge_index, edge) in sector.edges.iter().enumerate() {
+                if !matches!(edge.ty, EdgeType::Portal { .. }) || edge.portal_attributes == PortalAttributes::default() {
+                    continue;
+                }
+
+                let mut attributes = Vec::new();
+                if let Some(tint) = edge.portal_attributes.tint {
+                    attributes.push(format!("tint={:06X}", tint));
+                }
+                if edge.portal_attributes.blocking {
+                    attributes.push("blocking".to_string());
+                }
+                if let Some(overlay) = edge.portal_attributes.overlay {
+                    attributes.push(format!("overlay={:08X}", overlay));
+                }
+
+                portal_chunk.push_str(&format!("{}:{}:{}\n", sector_name(id), edge_index, attributes.join(",")));
+            }
+        }
+
+        if !portal_chunk.is_empty() {
+            result.push_str("\n#portal\n");
+            result.push_str(&portal_chunk);
+        }
+
+        result.push_str(&format!(
+            "\n#camera\n{}, {}, {}, {}\n",
+            format_float(self.camera_location.x),
+            format_float(self.camera_location.y),
+            format_float(self.camera_height),
+            format_float(self.camera_rotation),
+        ));
+
+        result
+    } // fn save_to_wmt_with_precision
 
     /// Iterator through indexed sectors getting function
     /// * Returns DoublEndedIterator with SectorId and &Sector items.E
     pub fn iter_indexed_sectors<'a>(&'a self) -> impl DoubleEndedIterator<Item = (SectorId, &'a Sector)> {
         self.sectors.iter().enumerate().map(|(index, sector)| (SectorId::new(index as u32), sector))
     } // fn iter_indexed_sectors
+
+    /// Portal iterator getting function. Portals aren't deduplicated, so reciprocal portal pair produces two items.
+    /// * Returns iterator over (source sector, destination sector, portal edge) triples
+    pub fn iter_portals(&self) -> impl Iterator<Item = (SectorId, SectorId, &Edge)> {
+        self.iter_indexed_sectors()
+            .flat_map(|(sector_id, sector)| sector.edges
+                .iter()
+                .filter_map(move |edge| match edge.ty {
+                    EdgeType::Portal { dst_sector_id } => Some((sector_id, dst_sector_id, edge)),
+                    EdgeType::Wall => None,
+                })
+            )
+    } // fn iter_portals
+
+    /// Portal connection set getting function. Reciprocal portals and duplicated portal edges collapse into single pair.
+    /// * Returns set of sector pairs connected by at least one portal
+    pub fn portal_pairs(&self) -> HashSet<UnorderedPair<SectorId>> {
+        self.iter_indexed_sectors()
+            .flat_map(|(sector_id, sector)| sector.portal_pairs(sector_id).0)
+            .collect()
+    } // fn portal_pairs
+
+    /// Map bounding box getting function
+    /// * Returns rectangle tightly bounding all sector points, None if map has no sectors
+    pub fn bounding_box(&self) -> Option<Rect<f32>> {
+        self.sector_bounds
+            .iter()
+            .cloned()
+            .reduce(|l, r| Rect::new(
+                l.x.start.min(r.x.start)..l.x.end.max(r.x.end),
+                l.y.start.min(r.y.start)..l.y.end.max(r.y.end),
+            ))
+    } // fn bounding_box
 } // impl Map
 
+/// Maps shared by tests of modules that work with map
+#[cfg(test)]
+pub mod test_maps {
+    use super::*;
+
+    /// Single square room map building function
+    /// * `ceiling` - room ceiling height, floor is located at 0
+    /// * `tags` - room sector tags
+    /// * Returns map with 2x2 square sector
+    pub fn room_map(ceiling: f32, tags: &[&str]) -> Map {
+        let mut builder = MapBuilder::new();
+        let sector_id = builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 2.0), EdgeType::Wall),
+            (Vec2f::new(0.0, 2.0), EdgeType::Wall),
+        ], 0.0, ceiling);
+        for tag in tags {
+            builder.add_sector_tag(sector_id, tag);
+        }
+        builder.build().unwrap()
+    } // fn room_map
+} // mod test_maps
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::xorshift_rand::Xorshift32;
+
+    /// Map topology (edge types and point count of every sector) comparison function
+    /// * `lhs`, `rhs` - maps to compare
+    /// * `tolerance` - maximal point coordinate difference
+    fn assert_same_topology(lhs: &Map, rhs: &Map, tolerance: f32) {
+        assert_eq!(lhs.sectors.len(), rhs.sectors.len());
+
+        for (lhs_sector, rhs_sector) in lhs.sectors.iter().zip(rhs.sectors.iter()) {
+            assert_eq!(lhs_sector.edges.len(), rhs_sector.edges.len());
+
+            for (lhs_edge, rhs_edge) in lhs_sector.edges.iter().zip(rhs_sector.edges.iter()) {
+                assert!(lhs_edge.ty == rhs_edge.ty);
+                assert!((lhs_edge.p0 - rhs_edge.p0).length() <= tolerance);
+                assert!((lhs_edge.p1 - rhs_edge.p1).length() <= tolerance);
+            }
+        }
+    } // fn assert_same_topology
+
+    #[test]
+    fn wmt_round_trip_at_3_decimals_preserves_topology() {
+        let third = 1.0 / 3.0;
+        let (a, b) = (SectorId::new(0), SectorId::new(1));
+
+        // Shared vertices have non-representable coordinates, so they're snapped
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(third, 0.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(third, 1.0 + third), EdgeType::Wall),
+            (Vec2f::new(0.0, 1.0 + third), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(third, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0000001, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0000001, 1.0 + third), EdgeType::Wall),
+            (Vec2f::new(third, 1.0 + third), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.25, 0.75);
+        let map = builder.build().unwrap();
+
+        let source = map.save_to_wmt_with_precision(3);
+        assert!(source.contains("0.333/") && !source.contains("0.3333"));
+        assert!(source.contains("2/") && !source.contains("2.0"));
+
+        let loaded = Map::load_from_wmt_validated(&source).unwrap();
+        assert_same_topology(&map, &loaded, 1e-3);
+
+        // Shared portal vertices are snapped identically
+        assert!(loaded.sectors[0].edges[1].p0 == loaded.sectors[1].edges[3].p1);
+        assert!(loaded.sectors[0].edges[1].p1 == loaded.sectors[1].edges[3].p0);
+
+        // Saving loaded map is stable
+        assert_eq!(loaded.save_to_wmt_with_precision(3), source);
+    } // fn wmt_round_trip_at_3_decimals_preserves_topology
+
+    #[test]
+    fn default_map_round_trip() {
+        let map = Map::load_from_wmt(include_str!("../maps/default.wmt")).unwrap();
+        let loaded = Map::load_from_wmt_validated(&map.save_to_wmt()).unwrap();
+
+        assert_same_topology(&map, &loaded, 1e-4);
+    } // fn default_map_round_trip
+
+    /// Two unit square sectors connected by portal WMT source building function
+    /// * `portal_chunk` - `#portal` chunk lines
+    /// * Returns WMT source
+    fn two_squares_wmt(portal_chunk: &str) -> String {
+        format!("#sectors\na:0/1[0/0,1/0:b,1/1,0/1]\nb:0/1[1/0,2/0,2/1,1/1:a]\n\n#portal\n{}\n#camera\n0.5, 0.5, 0.5, 0\n", portal_chunk)
+    } // fn two_squares_wmt
+
+    #[test]
+    fn portal_chunk_sets_attributes() {
+        let map = Map::load_from_wmt(&two_squares_wmt("a: 1: tint=FF8080, blocking\nb:3:overlay=8000FFFF\n")).unwrap();
+
+        let a_portal = &map.sectors[0].edges[1].portal_attributes;
+        assert_eq!(a_portal, &PortalAttributes { tint: Some(0xFF8080), blocking: true, overlay: None });
+
+        let b_portal = &map.sectors[1].edges[3].portal_attributes;
+        assert_eq!(b_portal, &PortalAttributes { tint: None, blocking: false, overlay: Some(0x8000FFFF) });
+
+        // Attributes are saved
+        let loaded = Map::load_from_wmt(&map.save_to_wmt()).unwrap();
+        assert_eq!(&loaded.sectors[0].edges[1].portal_attributes, a_portal);
+        assert_eq!(&loaded.sectors[1].edges[3].portal_attributes, b_portal);
+
+        // Attributes can't be set for walls and unknown sectors
+        let error = Map::load_from_wmt(&two_squares_wmt("a:0:tint=FF0000\n")).err().unwrap();
+        assert!(matches!(error.kind, Wmt2LoadingErrorKind::InvalidPortalEdge(_)) && error.line == 6, "{:?}", error);
+        let error = Map::load_from_wmt(&two_squares_wmt("c:0:tint=FF0000\n")).err().unwrap();
+        assert!(matches!(error.kind, Wmt2LoadingErrorKind::UnknownSectorReferenced(_)), "{:?}", error);
+
+        let error = Map::load_from_wmt(&two_squares_wmt("a:1:glass\n")).err().unwrap();
+        assert!(matches!(error.kind, Wmt2LoadingErrorKind::InvalidPortalAttribute(ref attribute) if attribute == "glass"), "{:?}", error);
+
+        // Tint is RRGGBB and overlay is AARRGGBB color, other digit counts are rejected
+        for attribute in ["tint=red", "tint=F", "tint=+FFFFF", "tint=80FF8080", "overlay=FF0000", "overlay=1FF00FF00"] {
+            let error = Map::load_from_wmt(&two_squares_wmt(&format!("a:1:{}\n", attribute))).err().unwrap();
+            assert!(matches!(error.kind, Wmt2LoadingErrorKind::InvalidPortalAttribute(ref invalid) if invalid == attribute), "{:?}", error);
+        }
+    } // fn portal_chunk_sets_attributes
+
+    #[test]
+    fn loading_errors_are_displayed() {
+        use std::error::Error;
+
+        // Loader is usable with `?` in functions returning boxed errors
+        let load = |source: &str| -> Result<Map, std::boxed::Box<dyn Error>> {
+            Ok(Map::load_from_wmt(source)?)
+        };
+
+        let error = load("// comment\n#sprites\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 2: unknown directive \"#sprites\"");
+        assert!(error.source().is_none());
+
+        let error = Map::load_from_wmt("#sectors\na:0/one[0/0,1/0,1/1]\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 2: invalid number: invalid float literal");
+        assert!(error.source().and_then(|source| source.downcast_ref::<std::num::ParseFloatError>()).is_some());
+
+        let error = Map::load_from_wmt("#sectors\na:0/1[0/0:b,1/0,1/1]\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 2: unknown sector \"b\" referenced");
+        assert!(error.source().is_none());
+    } // fn loading_errors_are_displayed
+
+    #[test]
+    fn malformed_sector_line_is_reported() {
+        let source = |malformed_sector: &str| format!(
+            "// Map with comments\n\n#sectors\na:0/1[0/0,1/0,1/1] // first sector\n\n{}\n\n#camera\n0.5, 0.2, 0.5, 0\n",
+            malformed_sector
+        );
+
+        let error = Map::load_from_wmt(&source("b:0/1 0/0,1/0,1/1]")).err().unwrap();
+        assert!(matches!(error.kind, Wmt2LoadingErrorKind::NoSectorEdgesStart), "{:?}", error);
+        assert_eq!(error.line, 6);
+
+        let error = Map::load_from_wmt(&source("b:0/1[0/0,1,1/1]")).err().unwrap();
+        assert!(matches!(error.kind, Wmt2LoadingErrorKind::NotEnoughPointCoordinates), "{:?}", error);
+        assert_eq!(error.line, 6);
+        assert!(error.to_string().starts_with("line 6: "));
+
+        // Well-formed map is loaded
+        assert!(Map::load_from_wmt(&source("b:0/1[1/0,2/0,1/1]")).is_ok());
+    } // fn malformed_sector_line_is_reported
+
+    /// Unit square sector points building function
+    /// * `x` - square left side x coordinate
+    /// * `right` - type of square right edge
+    /// * `left` - type of square left edge
+    /// * Returns square points with edge types
+    fn square_points(x: f32, right: EdgeType, left: EdgeType) -> [(Vec2f, EdgeType); 4] {
+        [
+            (Vec2f::new(x, 0.0), EdgeType::Wall),
+            (Vec2f::new(x + 1.0, 0.0), right),
+            (Vec2f::new(x + 1.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(x, 1.0), left),
+        ]
+    } // fn square_points
+
+    #[test]
+    fn builder_builds_two_sector_map() {
+        let mut builder = MapBuilder::new();
+        let a = builder.add_sector(&square_points(0.0, EdgeType::Portal { dst_sector_id: SectorId::new(1) }, EdgeType::Wall), 0.0, 1.0);
+        let b = builder.add_sector(&square_points(1.0, EdgeType::Wall, EdgeType::Portal { dst_sector_id: SectorId::new(0) }), 0.2, 0.8);
+        builder.set_camera(Vec2f::new(0.5, 0.5), 0.4, 1.0);
+        let map = builder.build().unwrap();
+
+        assert_eq!((a, b), (SectorId::new(0), SectorId::new(1)));
+        assert_eq!(map.find_sector(Vec2f::new(0.5, 0.5)), Some(a));
+        assert_eq!(map.find_sector(Vec2f::new(1.5, 0.5)), Some(b));
+        assert_eq!(map.get_sector(b).map(|sector| (sector.floor, sector.ceiling)), Some((0.2, 0.8)));
+        assert_eq!(map.camera_location, Vec2f::new(0.5, 0.5));
+        assert_eq!(map.iter_portals().map(|(from, to, _)| (from, to)).collect::<Vec<_>>(), vec![(a, b), (b, a)]);
+    } // fn builder_builds_two_sector_map
+
+    #[test]
+    fn builder_rejects_invalid_sectors() {
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&square_points(0.0, EdgeType::Wall, EdgeType::Wall)[..2], 0.0, 1.0);
+        assert_eq!(builder.build().err(), Some(MapBuildingError::NotEnoughPoints { sector_id: SectorId::new(0) }));
+
+        for (floor, ceiling) in [(1.0, 0.0), (1.0, 1.0), (f32::NAN, 1.0)] {
+            let mut builder = MapBuilder::new();
+            builder.add_sector(&square_points(0.0, EdgeType::Wall, EdgeType::Wall), floor, ceiling);
+            assert_eq!(builder.build().err(), Some(MapBuildingError::InvalidBounds { sector_id: SectorId::new(0) }));
+        }
+
+        // Arrow-shaped sector has reflex angle at its inner point
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&square_points(0.0, EdgeType::Wall, EdgeType::Wall), 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(0.0, 2.0), EdgeType::Wall),
+            (Vec2f::new(1.0, 1.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        assert_eq!(builder.build().err(), Some(MapBuildingError::NonConvexSector { sector_id: SectorId::new(1) }));
+
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&square_points(0.0, EdgeType::Portal { dst_sector_id: SectorId::new(7) }, EdgeType::Wall), 0.0, 1.0);
+        assert_eq!(builder.build().err(), Some(MapBuildingError::UnknownSectorReferenced {
+            sector_id: SectorId::new(0),
+            dst_sector_id: SectorId::new(7),
+        }));
+    } // fn builder_rejects_invalid_sectors
+
+    #[test]
+    fn saved_sector_names_keep_sector_order() {
+        // More than 10 sectors, so sector names must be ordered by index in loader, not as plain strings
+        let mut builder = MapBuilder::new();
+        for index in 0..12 {
+            let x = index as f32;
+            let left = if index > 0 { EdgeType::Portal { dst_sector_id: SectorId::new(index - 1) } } else { EdgeType::Wall };
+            let right = if index < 11 { EdgeType::Portal { dst_sector_id: SectorId::new(index + 1) } } else { EdgeType::Wall };
+            builder.add_sector(&square_points(x, right, left), x * 0.125, 1.0 + x);
+        }
+        builder.set_camera(Vec2f::new(0.5, 0.5), 0.75, 1.25);
+        let map = builder.build().unwrap();
+
+        let source = map.save_to_wmt();
+        assert!(source.starts_with("#sectors\nsector00:"), "{}", source);
+        assert!(source.contains("\nsector11:"));
+        assert!(source.contains("\n#camera\n0.5, 0.5, 0.75, 1.25\n"));
+
+        let loaded = Map::load_from_wmt(&source).unwrap();
+        assert_same_topology(&map, &loaded, 1e-4);
+        for (sector, loaded_sector) in map.sectors.iter().zip(loaded.sectors.iter()) {
+            assert_eq!((sector.floor, sector.ceiling), (loaded_sector.floor, loaded_sector.ceiling));
+        }
+        assert!(loaded.camera_location == map.camera_location);
+        assert_eq!((loaded.camera_height, loaded.camera_rotation), (0.75, 1.25));
+    } // fn saved_sector_names_keep_sector_order
+
+    #[test]
+    fn sector_height_edit_updates_queries() {
+        let (a, b) = (SectorId::new(0), SectorId::new(1));
+
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&square_points(0.0, EdgeType::Portal { dst_sector_id: b }, EdgeType::Wall), 0.0, 1.0);
+        builder.add_sector(&square_points(1.0, EdgeType::Wall, EdgeType::Portal { dst_sector_id: a }), 0.0, 1.0);
+        let mut map = builder.build().unwrap();
+
+        map.set_sector_heights(b, 0.25, 2.0).unwrap();
+        assert_eq!(map.floor_height_at(Vec2f::new(1.5, 0.5)), Some(0.25));
+        assert_eq!(map.floor_height_at(Vec2f::new(0.5, 0.5)), Some(0.0));
+        assert_eq!(map.floor_height_at(Vec2f::new(2.5, 0.5)), None);
+        assert_eq!(map.get_sector(b).map(|sector| sector.ceiling), Some(2.0));
+
+        // Invalid edits are rejected and don't change sector
+        assert_eq!(map.set_sector_heights(b, 3.0, 2.0), Err(SectorEditError::InvalidBounds { sector_id: b }));
+        assert_eq!(map.set_sector_heights(b, f32::NAN, 2.0), Err(SectorEditError::InvalidBounds { sector_id: b }));
+        assert_eq!(map.set_sector_heights(SectorId::new(2), 0.0, 1.0), Err(SectorEditError::UnknownSector { sector_id: SectorId::new(2) }));
+        assert_eq!(map.floor_height_at(Vec2f::new(1.5, 0.5)), Some(0.25));
+
+        // Spatial index stays valid
+        for (point, sector_id) in [(Vec2f::new(0.25, 0.75), Some(a)), (Vec2f::new(1.75, 0.25), Some(b)), (Vec2f::new(-0.5, 0.5), None)] {
+            assert_eq!(map.find_sector(point), sector_id);
+        }
+    } // fn sector_height_edit_updates_queries
+
+    #[test]
+    fn non_convex_sectors_are_rejected() {
+        let load = |sector: &str| Map::load_from_wmt(&format!("#sectors\nok:0/1[5/0,6/0,6/1,5/1]\n{}\n#camera\n5.5, 0.5, 0.5, 0\n", sector));
+        let assert_non_convex = |sector: &str| {
+            let error = load(sector).err().unwrap();
+            assert!(matches!(error.kind, Wmt2LoadingErrorKind::NonConvexSector(ref name) if name == "bad"), "{:?}", error);
+            assert_eq!(error.line, 3);
+        };
+
+        // Concave quad (dart), polygon with zero-length edge and self-intersecting pentagram
+        assert_non_convex("bad:0/1[0/0,2/1,0/2,1/1]");
+        assert_non_convex("bad:0/1[0/0,1/0,1/0,1/1,0/1]");
+        assert_non_convex("bad:0/1[0/0,2/0,0.4/1.2,1/-0.7,1.6/1.2]");
+
+        // Collinear neighbour edges are allowed
+        assert!(load("good:0/1[0/0,1/0,2/0,2/1,0/1]").is_ok());
+    } // fn non_convex_sectors_are_rejected
+
+    #[test]
+    fn one_way_portal_is_rejected_by_validated_loader() {
+        let source = "#sectors\na:0/1[0/0,1/0:b,1/1,0/1]\nb:0/1[1/0,2/0,2/1,1/1]\n\n#camera\n0.5, 0.5, 0.5, 0\n";
+
+        // One-way portals are allowed by default
+        let map = Map::load_from_wmt(source).unwrap();
+        assert_eq!(map.iter_portals().count(), 1);
+
+        let error = Map::load_from_wmt_validated(source).err().unwrap();
+        assert!(matches!(error.kind, Wmt2LoadingErrorKind::NonReciprocalPortal { ref from, ref to } if from == "a" && to == "b"), "{:?}", error);
+        assert_eq!(error.line, 2);
+
+        // Portal back makes map valid
+        assert!(Map::load_from_wmt_validated(&source.replace("1/1]", "1/1:a]")).is_ok());
+    } // fn one_way_portal_is_rejected_by_validated_loader
+
+    /// Synthetic map building function
+    /// * `columns`, `rows` - map size in sectors
+    /// * `seed` - vertex jitter random seed
+    /// * Returns map of `columns * rows` convex quad sectors with shared randomly jittered vertices
+    fn jittered_grid_map(columns: usize, rows: usize, seed: u32) -> Map {
+        let mut rand = Xorshift32::new(seed);
+        let mut vertices = Vec::with_capacity((columns + 1) * (rows + 1));
+        for y in 0..=rows {
+            for x in 0..=columns {
+                let mut jitter = || (rand.next() % 1024) as f32 / 1024.0 * 0.4 - 0.2;
+                vertices.push(Vec2f::new(x as f32 + jitter(), y as f32 + jitter()));
+            }
+        }
+
+        let vertex = |x: usize, y: usize| (vertices[y * (columns + 1) + x], EdgeType::Wall);
+        let mut builder = MapBuilder::new();
+        for y in 0..rows {
+            for x in 0..columns {
+                builder.add_sector(&[vertex(x, y), vertex(x + 1, y), vertex(x + 1, y + 1), vertex(x, y + 1)], 0.0, 1.0);
+            }
+        }
+        builder.build().unwrap()
+    } // fn jittered_grid_map
+
+    /// Linear scan sector finding function, reference for `Map::find_sector`
+    /// * `map` - map to find sector in
+    /// * `location` - point that must be contained in resulting sector
+    /// * Returns option of identifier of first sector that contains `location` point
+    fn find_sector_linear(map: &Map, location: Vec2f) -> Option<SectorId> {
+        map.sectors
+            .iter()
+            .position(|sector| sector.contains(location))
+            .map(|index| SectorId::new(index as u32))
+    } // fn find_sector_linear
+
+    /// Random points generation function
+    /// * `count` - point count
+    /// * `seed` - random seed
+    /// * `extent` - points are generated in [-1, extent + 1] range
+    /// * Returns points
+    fn random_points(count: usize, seed: u32, extent: Vec2f) -> Vec<Vec2f> {
+        let mut rand = Xorshift32::new(seed);
+        let mut coordinate = |extent: f32| (rand.next() % 65536) as f32 / 65536.0 * (extent + 2.0) - 1.0;
+
+        (0..count).map(|_| Vec2f::new(coordinate(extent.x), coordinate(extent.y))).collect()
+    } // fn random_points
+
+    #[test]
+    fn grid_find_sector_matches_linear_scan() {
+        let map = jittered_grid_map(40, 25, 0x1234567);
+        assert_eq!(map.sectors.len(), 1000);
+
+        let mut found = 0;
+        for point in random_points(10000, 0x89ABCDE, Vec2f::new(40.0, 25.0)) {
+            let sector_id = map.find_sector(point);
+            assert_eq!(sector_id, find_sector_linear(&map, point), "point {}", point);
+            found += sector_id.is_some() as usize;
+        }
+
+        // Both found and missing points are checked
+        assert!(found > 8000 && found < 10000, "{} points found", found);
+
+        // Shared vertices are contained in several sectors, first one is returned
+        for point in map.sectors[0..50].iter().map(|sector| sector.edges[2].p0) {
+            assert_eq!(map.find_sector(point), find_sector_linear(&map, point));
+        }
+    } // fn grid_find_sector_matches_linear_scan
+
+    #[test]
+    fn sector_area_and_centroid() {
+        let assert_near = |actual: Vec2f, expected: Vec2f| assert!((actual - expected).length() < 1e-5, "{} != {}", actual, expected);
+
+        let square = [Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 0.0), Vec2f::new(1.0, 1.0), Vec2f::new(0.0, 1.0)];
+        let sector = Sector::wall_loop(square.iter().copied());
+        assert_eq!(sector.area(), 1.0);
+        assert_eq!(sector.perimeter(), 4.0);
+        assert_near(sector.centroid(), Vec2f::new(0.5, 0.5));
+
+        // Clockwise sector has negative area, but same centroid
+        let sector = Sector::wall_loop(square.iter().rev().copied());
+        assert_eq!(sector.area(), -1.0);
+        assert_near(sector.centroid(), Vec2f::new(0.5, 0.5));
+
+        // Right triangle with legs 3 and 6, centroid is mean of points
+        let triangle = [Vec2f::new(1.0, 1.0), Vec2f::new(4.0, 1.0), Vec2f::new(1.0, 7.0)];
+        let sector = Sector::wall_loop(triangle.iter().copied());
+        assert_eq!(sector.area(), 9.0);
+        assert_near(sector.centroid(), Vec2f::new(2.0, 3.0));
+
+        // Centroid is area-weighted, not mean of points
+        let trapezoid = [Vec2f::new(0.0, 0.0), Vec2f::new(4.0, 0.0), Vec2f::new(3.0, 1.0), Vec2f::new(1.0, 1.0)];
+        let sector = Sector::wall_loop(trapezoid.iter().copied());
+        assert_eq!(sector.area(), 3.0);
+        assert_near(sector.centroid(), Vec2f::new(2.0, 4.0 / 9.0));
+
+        // Degenerate sector centroid is mean of points
+        let segment = [Vec2f::new(0.0, 0.0), Vec2f::new(2.0, 2.0)];
+        let sector = Sector::wall_loop(segment.iter().copied());
+        assert_eq!(sector.area(), 0.0);
+        assert_near(sector.centroid(), Vec2f::new(1.0, 1.0));
+    } // fn sector_area_and_centroid
+
+    /// Benchmark, run with `cargo test --release find_sector_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn find_sector_benchmark() {
+        let map = jittered_grid_map(40, 25, 0x1234567);
+        let points = random_points(200000, 0x89ABCDE, Vec2f::new(40.0, 25.0));
+
+        let measure = |find: &dyn Fn(Vec2f) -> Option<SectorId>| {
+            let start = std::time::Instant::now();
+            let found = points.iter().filter(|point| find(**point).is_some()).count();
+            (start.elapsed(), found)
+        };
+
+        let (linear_time, linear_found) = measure(&|point| find_sector_linear(&map, point));
+        let (grid_time, grid_found) = measure(&|point| map.find_sector(point));
+
+        println!(
+            "find_sector on {} sectors, {} points: linear scan {:?}, grid {:?} ({:.1}x)",
+            map.sectors.len(),
+            points.len(),
+            linear_time,
+            grid_time,
+            linear_time.as_secs_f64() / grid_time.as_secs_f64(),
+        );
+        assert_eq!(linear_found, grid_found);
+        assert!(grid_time < linear_time);
+    } // fn find_sector_benchmark
+
+    #[test]
+    fn bounding_box_bounds_all_points() {
+        assert!(MapBuilder::new().build().unwrap().bounding_box().is_none());
+
+        // Square and trapezoid sharing a portal edge
+        let (a, b) = (SectorId::new(0), SectorId::new(1));
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(-1.0, -2.0), EdgeType::Wall),
+            (Vec2f::new(1.0, -2.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(1.0, 0.5), EdgeType::Wall),
+            (Vec2f::new(-1.0, 0.5), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(1.0, -2.0), EdgeType::Wall),
+            (Vec2f::new(3.5, -1.0), EdgeType::Wall),
+            (Vec2f::new(3.5, 0.0), EdgeType::Wall),
+            (Vec2f::new(1.0, 0.5), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.0, 1.0);
+        let map = builder.build().unwrap();
+
+        let bounding_box = map.bounding_box().unwrap();
+        assert_eq!(bounding_box, Rect::new(-1.0..3.5, -2.0..0.5));
+
+        for edge in map.sectors.iter().flat_map(|sector| sector.edges.iter()) {
+            for point in [edge.p0, edge.p1] {
+                assert!(bounding_box.x.contains(&point.x) || point.x == bounding_box.x.end);
+                assert!(bounding_box.y.contains(&point.y) || point.y == bounding_box.y.end);
+            }
+        }
+    } // fn bounding_box_bounds_all_points
+
+    #[test]
+    fn default_map_portal_count_matches_file() {
+        let source = include_str!("../maps/default.wmt");
+        let map = Map::load_from_wmt(source).unwrap();
+
+        // Portal points are ones with destination sector name after the colon
+        let file_portal_count: usize = source
+            .lines()
+            .skip_while(|line| line.trim() != "#sectors")
+            .skip(1)
+            .take_while(|line| !line.trim().is_empty())
+            .map(|line| line.split_once('[').unwrap().1.matches(':').count())
+            .sum();
+        assert_eq!(file_portal_count, 36);
+        assert_eq!(map.iter_portals().count(), file_portal_count);
+
+        // Reciprocal portals are yielded separately
+        for (src_sector_id, dst_sector_id, edge) in map.iter_portals() {
+            assert_eq!(edge.ty, EdgeType::Portal { dst_sector_id });
+            assert!(map.iter_portals().any(|(src, dst, _)| src == dst_sector_id && dst == src_sector_id));
+        }
+    } // fn default_map_portal_count_matches_file
+
+    #[test]
+    fn ray_passes_through_portal_to_far_wall() {
+        let map = Map::load_from_wmt(&two_squares_wmt("")).unwrap();
+        let origin = Vec2f::new(0.25, 0.5);
+
+        // Ray crosses `a`-`b` portal at x = 1 and hits `b` far wall at x = 2
+        let hit = map.cast_ray(origin, Vec2f::new(2.0, 0.0), SectorId::new(0)).unwrap();
+        assert_eq!(hit.sector_id, SectorId::new(1));
+        assert_eq!(hit.edge.ty, EdgeType::Wall);
+        assert_eq!((hit.edge.p0, hit.edge.p1), (Vec2f::new(2.0, 0.0), Vec2f::new(2.0, 1.0)));
+        assert!((hit.distance - 1.75).abs() < 1e-5, "distance is {}", hit.distance);
+        assert!((hit.point - Vec2f::new(2.0, 0.5)).length() < 1e-5);
+
+        let hit = map.cast_ray(origin, Vec2f::new(1.0, 0.25), SectorId::new(0)).unwrap();
+        assert_eq!(hit.sector_id, SectorId::new(1));
+        assert!((hit.distance - 1.75 * (1.0f32 + 0.0625).sqrt()).abs() < 1e-5, "distance is {}", hit.distance);
+        assert!((hit.point - Vec2f::new(2.0, 0.9375)).length() < 1e-5);
+
+        // Wall of start sector stops the ray
+        let hit = map.cast_ray(origin, Vec2f::new(-1.0, 0.0), SectorId::new(0)).unwrap();
+        assert_eq!(hit.sector_id, SectorId::new(0));
+        assert!((hit.distance - 0.25).abs() < 1e-5, "distance is {}", hit.distance);
+
+        assert!(map.cast_ray(origin, Vec2f::new(0.0, 0.0), SectorId::new(0)).is_none());
+    } // fn ray_passes_through_portal_to_far_wall
+
+    #[test]
+    fn movement_slides_along_walls() {
+        let close = |lhs: Vec2f, rhs: Vec2f| (lhs - rhs).length() < 1e-3;
+        let radius = 0.1;
+
+        let mut builder = MapBuilder::new();
+        let room = builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 4.0), EdgeType::Wall),
+            (Vec2f::new(0.0, 4.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        let map = builder.build().unwrap();
+
+        // Diagonal motion into wall keeps its parallel part
+        let (location, sector_id) = map.move_and_slide(Vec2f::new(2.0, 3.5), Vec2f::new(1.0, 1.0), room, radius);
+        assert!(close(location, Vec2f::new(3.0, 4.0 - radius)), "slid to {:?}", location);
+        assert_eq!(sector_id, room);
+
+        // Motion into corner stops at both walls
+        let (location, _) = map.move_and_slide(Vec2f::new(3.5, 3.5), Vec2f::new(1.0, 1.0), room, radius);
+        assert!(close(location, Vec2f::new(4.0 - radius, 4.0 - radius)), "stopped at {:?}", location);
+
+        // Motion through portal isn't changed and moves body to neighbour sector
+        let map = Map::load_from_wmt(&two_squares_wmt("")).unwrap();
+        let (location, sector_id) = map.move_and_slide(Vec2f::new(0.5, 0.5), Vec2f::new(1.0, 0.0), SectorId::new(0), radius);
+        assert!(close(location, Vec2f::new(1.5, 0.5)), "moved to {:?}", location);
+        assert_eq!(sector_id, SectorId::new(1));
+
+        // Blocking portal acts as wall
+        let map = Map::load_from_wmt(&two_squares_wmt("a:1:blocking")).unwrap();
+        let (location, sector_id) = map.move_and_slide(Vec2f::new(0.5, 0.5), Vec2f::new(1.0, 0.0), SectorId::new(0), radius);
+        assert!(close(location, Vec2f::new(1.0 - radius, 0.5)), "moved to {:?}", location);
+        assert_eq!(sector_id, SectorId::new(0));
+    } // fn movement_slides_along_walls
+
+    #[test]
+    fn unit_square_edge_lengths_and_perimeter() {
+        let square = [Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 0.0), Vec2f::new(1.0, 1.0), Vec2f::new(0.0, 1.0)];
+        let sector = Sector::wall_loop(square.iter().copied());
+
+        assert_eq!(sector.edges.len(), 4);
+        for edge in &sector.edges {
+            assert_eq!(edge.length(), 1.0);
+        }
+        assert_eq!(sector.perimeter(), 4.0);
+    } // fn unit_square_edge_lengths_and_perimeter
+
+    #[test]
+    fn edge_colors_are_parsed() {
+        let source = "#sectors\na:0/1[0/0#AACCAA,1/0:b#102030,1/1,0/1]\nb:0/1[1/0,2/0,2/1,1/1:a]\n\n#camera\n0.5, 0.5, 0.5, 0\n";
+        let map = Map::load_from_wmt(source).unwrap();
+
+        let edges = &map.sectors[0].edges;
+        assert_eq!(edges[0].color, Some(0xAACCAA));
+        assert_eq!(edges[0].ty, EdgeType::Wall);
+        assert_eq!(edges[1].color, Some(0x102030));
+        assert_eq!(edges[1].ty, EdgeType::Portal { dst_sector_id: SectorId::new(1) });
+        assert_eq!(edges[2].color, None);
+        assert!(map.sectors[1].edges.iter().all(|edge| edge.color.is_none()));
+
+        // Colors are saved
+        let loaded = Map::load_from_wmt(&map.save_to_wmt()).unwrap();
+        let colors = |map: &Map| map.sectors.iter().flat_map(|sector| sector.edges.iter().map(|edge| edge.color)).collect::<Vec<_>>();
+        assert_eq!(colors(&loaded), colors(&map));
+
+        for invalid in ["AACCA", "AACCAAF", "GGCCAA", ""] {
+            let error = Map::load_from_wmt(&source.replace("AACCAA", invalid)).err().unwrap();
+            assert!(matches!(error.kind, Wmt2LoadingErrorKind::InvalidColor(ref color) if color == invalid), "{:?}", error);
+        }
+    } // fn edge_colors_are_parsed
+
+    #[test]
+    fn sector_colors_are_loaded() {
+        let source = "#sectors\na:0/1#112233/445566[0/0,1/0:b,1/1,0/1]\nb:0/1[1/0,2/0,2/1,1/1:a]\n\n#camera\n0.5, 0.5, 0.5, 0\n";
+        let map = Map::load_from_wmt(source).unwrap();
+
+        assert_eq!((map.sectors[0].floor, map.sectors[0].ceiling), (0.0, 1.0));
+        assert_eq!((map.sectors[0].floor_color, map.sectors[0].ceiling_color), (0x112233, 0x445566));
+        assert_eq!((map.sectors[1].floor_color, map.sectors[1].ceiling_color), (DEFAULT_FLOOR_COLOR, DEFAULT_CEILING_COLOR));
+
+        // Colors are saved
+        let loaded = Map::load_from_wmt(&map.save_to_wmt()).unwrap();
+        for (loaded_sector, sector) in loaded.sectors.iter().zip(map.sectors.iter()) {
+            assert_eq!((loaded_sector.floor_color, loaded_sector.ceiling_color), (sector.floor_color, sector.ceiling_color));
+        }
+
+        for invalid in ["112233", "112233/", "112233/44556"] {
+            let error = Map::load_from_wmt(&source.replace("112233/445566", invalid)).err().unwrap();
+            assert!(matches!(error.kind, Wmt2LoadingErrorKind::InvalidColor(_)), "{:?}", error);
+        }
+    } // fn sector_colors_are_loaded
+
+    #[test]
+    fn duplicate_portals_collapse_into_single_pair() {
+        let (a, b) = (SectorId::new(0), SectorId::new(1));
+
+        // Reciprocal portals collapse into single pair
+        let map = Map::load_from_wmt(&two_squares_wmt("")).unwrap();
+        assert_eq!(map.iter_portals().count(), 2);
+        assert_eq!(map.portal_pairs(), HashSet::from([UnorderedPair::new(a, b)]));
+
+        // Several portal edges between the same sectors collapse too
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(1.0, 0.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(1.0, 1.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(0.0, 1.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(1.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 1.0), EdgeType::Wall),
+            (Vec2f::new(1.0, 1.0), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.0, 1.0);
+        let map = builder.build().unwrap();
+        assert_eq!(map.iter_portals().count(), 3);
+        assert_eq!(map.portal_pairs(), HashSet::from([UnorderedPair::new(b, a)]));
+
+        // Loaders report them
+        let source = two_squares_wmt("").replace("1/1,0/1]", "1/1:b,0/1]");
+        for error in [Map::load_from_wmt(&source).err().unwrap(), Map::load_from_wmt_validated(&source).err().unwrap()] {
+            assert!(matches!(error.kind, Wmt2LoadingErrorKind::DuplicatePortal { ref from, ref to } if from == "a" && to == "b"), "{:?}", error);
+            assert_eq!(error.line, 2);
+        }
+    } // fn duplicate_portals_collapse_into_single_pair
+} // mod tests
+
 // file map.rs
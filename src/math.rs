@@ -1,27 +1,73 @@
-use std::ops::{Add, AddAssign, BitXor, Div, DivAssign, Mul, MulAssign, Neg, Range, Rem, RemAssign, Sub, SubAssign};
+use core::ops::{Add, AddAssign, BitXor, Div, DivAssign, Mul, MulAssign, Neg, Range, Rem, RemAssign, Sub, SubAssign};
 
 pub mod numeric_traits {
     pub trait Sqrt {
         fn sqrt(self) -> Self;
     }
 
+    #[cfg(feature = "std")]
     impl Sqrt for f32 {
         fn sqrt(self) -> Self {
             self.sqrt()
         }
     }
 
+    #[cfg(feature = "std")]
     impl Sqrt for f64 {
         fn sqrt(self) -> Self {
             self.sqrt()
         }
     }
+
+    pub trait Trig {
+        fn sin(self) -> Self;
+        fn cos(self) -> Self;
+        fn tan(self) -> Self;
+    }
+
+    #[cfg(feature = "std")]
+    impl Trig for f32 {
+        fn sin(self) -> Self {
+            self.sin()
+        }
+
+        fn cos(self) -> Self {
+            self.cos()
+        }
+
+        fn tan(self) -> Self {
+            self.tan()
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl Trig for f64 {
+        fn sin(self) -> Self {
+            self.sin()
+        }
+
+        fn cos(self) -> Self {
+            self.cos()
+        }
+
+        fn tan(self) -> Self {
+            self.tan()
+        }
+    }
 }
 
 macro_rules! consume_ident {
     ($type: ty, $i: ident) => { $type };
 }
 
+macro_rules! count_idents {
+    () => { 0usize };
+
+    ($first: ident $(, $rest: ident)*) => {
+        1usize + count_idents!($($rest),*)
+    };
+}
+
 macro_rules! impl_vecn_base {
     ($struct_name: ident, $template_type: ident, $value_type: ty, $($x: ident),*) => {
         #[derive(Debug, Default, PartialEq)]
@@ -139,6 +185,50 @@ macro_rules! impl_vecn {
     ($struct_name: ident, $($x: ident),*) => {
         impl_vecn_base!($struct_name, T, T, $($x),*);
 
+        impl<T> $struct_name<T> {
+            /// Vector from array of components construction function
+            /// * `a` - array of vector components
+            /// * Returns vector with components taken from array in order
+            pub fn from_array(a: [T; count_idents!($($x),*)]) -> Self {
+                Self::from(a)
+            } // fn from_array
+
+            /// Vector into array of components conversion function
+            /// * Returns array of vector components in order
+            pub fn into_array(self) -> [T; count_idents!($($x),*)] {
+                self.into()
+            } // fn into_array
+        }
+
+        impl<T> From<[T; count_idents!($($x),*)]> for $struct_name<T> {
+            fn from(a: [T; count_idents!($($x),*)]) -> Self {
+                let [$($x),*] = a;
+
+                Self { $($x),* }
+            }
+        }
+
+        impl<T> From<$struct_name<T>> for [T; count_idents!($($x),*)] {
+            fn from(v: $struct_name<T>) -> Self {
+                [$( v.$x ),*]
+            }
+        }
+
+        impl<T: core::fmt::Display> core::fmt::Display for $struct_name<T> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                let components: &[&dyn core::fmt::Display] = &[$( &self.$x ),*];
+
+                f.write_str("(")?;
+                for (index, component) in components.iter().enumerate() {
+                    if index != 0 {
+                        f.write_str(", ")?;
+                    }
+                    component.fmt(f)?;
+                }
+                f.write_str(")")
+            }
+        }
+
         impl<T: Add<T, Output = T> + Mul<T, Output = T>> BitXor for $struct_name<T> {
             type Output = T;
 
@@ -212,6 +302,51 @@ macro_rules! impl_rectn {
                 $point_name::<T>::new($( self.$x.end.clone() ),*)
             }
         }
+
+        impl<T: Clone + PartialOrd> $struct_name<T> {
+            /// Rectangle intersection calculation function
+            /// * `other` - rectangle to intersect with
+            /// * Returns intersection or None if rectangles do not overlap
+            pub fn intersection(&self, other: &Self) -> Option<Self> {
+                $(
+                    let $x = {
+                        let start = if self.$x.start > other.$x.start { self.$x.start.clone() } else { other.$x.start.clone() };
+                        let end = if self.$x.end < other.$x.end { self.$x.end.clone() } else { other.$x.end.clone() };
+
+                        if start >= end {
+                            return None;
+                        }
+
+                        start..end
+                    };
+                )*
+
+                Some(Self { $( $x ),* })
+            }
+
+            /// Point containment check function
+            /// * `p` - point to check
+            /// * Returns true if point lies inside of the [start, end) rectangle
+            pub fn contains_point(&self, p: &$point_name<T>) -> bool {
+                $( self.$x.contains(&p.$x) )&&*
+            }
+
+            /// Point into rectangle bounds clamping function. Bounds are inclusive unlike `contains_point` ones,
+            /// so point clamped to `end` isn't contained in rectangle (e.g. pixel coordinates must be clamped to `end - 1` instead).
+            /// * `p` - point to clamp
+            /// * Returns point clamped into [start, end] bounds
+            pub fn clamp_point(&self, p: &$point_name<T>) -> $point_name<T> {
+                $point_name::<T>::new($(
+                    if p.$x < self.$x.start {
+                        self.$x.start.clone()
+                    } else if p.$x > self.$x.end {
+                        self.$x.end.clone()
+                    } else {
+                        p.$x.clone()
+                    }
+                ),*)
+            }
+        }
     }
 }
 
@@ -260,3 +395,83 @@ impl<T: Clone + Mul<T, Output = T> + Sub<T, Output = T>> Rem for Vec2<T> {
         self.x * rhs.y - self.y * rhs.x
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn rect_intersection_overlapping() {
+        let lhs = Rect::new(0..10, 0..10);
+        let rhs = Rect::new(5..15, -5..5);
+
+        assert_eq!(lhs.intersection(&rhs), Some(Rect::new(5..10, 0..5)));
+        assert_eq!(rhs.intersection(&lhs), Some(Rect::new(5..10, 0..5)));
+    } // fn rect_intersection_overlapping
+
+    #[test]
+    fn rect_intersection_disjoint() {
+        let lhs = Rect::new(0..10, 0..10);
+
+        assert_eq!(lhs.intersection(&Rect::new(20..30, 0..10)), None);
+        assert_eq!(lhs.intersection(&Rect::new(0..10, 20..30)), None);
+
+        // Rectangles are half-open, so touching ones don't overlap
+        assert_eq!(lhs.intersection(&Rect::new(10..20, 0..10)), None);
+    } // fn rect_intersection_disjoint
+
+    #[test]
+    fn rect_intersection_nested() {
+        let outer = Rect::new(0..10, 0..10);
+        let inner = Rect::new(2..4, 3..8);
+
+        assert_eq!(outer.intersection(&inner), Some(inner.clone()));
+        assert_eq!(inner.intersection(&outer), Some(inner));
+    } // fn rect_intersection_nested
+
+    #[test]
+    fn rect_point_containment_and_clamping() {
+        let rect = Rect::new(0..10, 5..8);
+
+        assert!(rect.contains_point(&Vec2::new(0, 5)));
+        assert!(rect.contains_point(&Vec2::new(9, 7)));
+        assert!(!rect.contains_point(&Vec2::new(10, 7)));
+        assert!(!rect.contains_point(&Vec2::new(3, 4)));
+
+        assert_eq!(rect.clamp_point(&Vec2::new(-3, 20)), Vec2::new(0, 8));
+        assert_eq!(rect.clamp_point(&Vec2::new(4, 6)), Vec2::new(4, 6));
+
+        // Clamping bounds are inclusive, containment ones are half-open
+        let clamped = rect.clamp_point(&Vec2::new(15, 20));
+        assert_eq!(clamped, rect.end());
+        assert!(!rect.contains_point(&clamped));
+        assert!(rect.contains_point(&rect.clamp_point(&Vec2::new(-3, -1))));
+    } // fn rect_point_containment_and_clamping
+
+    #[test]
+    fn vector_display() {
+        assert_eq!(format!("{}", Vec2::new(1, -2)), "(1, -2)");
+        assert_eq!(format!("{}", Vec3::new(0.5f32, 1.0, 2.25)), "(0.5, 1, 2.25)");
+        assert_eq!(format!("{}", Vec4::new(1, 2, 3, 4)), "(1, 2, 3, 4)");
+
+        // Formatting parameters are applied to every component
+        assert_eq!(format!("{:.1}", Vec2::new(1.0f32, 2.0)), "(1.0, 2.0)");
+    } // fn vector_display
+
+    #[test]
+    fn vector_array_round_trip() {
+        let v2 = Vec2::from([1, 2]);
+        assert_eq!(v2, Vec2::new(1, 2));
+        assert_eq!(<[i32; 2]>::from(v2), [1, 2]);
+
+        let v3 = Vec3::from_array([1.0f32, 2.0, 3.0]);
+        assert_eq!(v3, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(v3.into_array(), [1.0, 2.0, 3.0]);
+
+        let v4: [u8; 4] = Vec4::from([4u8, 3, 2, 1]).into();
+        assert_eq!(v4, [4, 3, 2, 1]);
+    } // fn vector_array_round_trip
+} // mod tests
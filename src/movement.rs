@@ -0,0 +1,295 @@
+/// WEIRD Project
+/// `File` movement.rs
+/// `Description` Player movement and collision implementation module
+/// `Author` TioT2
+/// `Last changed` 17.10.2026
+
+use crate::camera::Camera;
+use crate::map::{Map, SectorId};
+use crate::math::Vec2f;
+
+/// Movement parameters representation structure
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MovementConfig {
+    /// Maximal forward speed (units per second)
+    pub forward_speed: f32,
+    /// Maximal backward speed (units per second)
+    pub backward_speed: f32,
+    /// Maximal strafe speed (units per second)
+    pub strafe_speed: f32,
+    /// Vertical speed (units per second)
+    pub vertical_speed: f32,
+    /// Turn speed (radians per second)
+    pub turn_speed: f32,
+    /// Acceleration towards desired velocity (units per second squared)
+    pub acceleration: f32,
+    /// Deceleration applied if no movement input is given (units per second squared)
+    pub friction: f32,
+    /// Minimal distance to walls
+    pub radius: f32,
+} // struct MovementConfig
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            forward_speed: 3.0,
+            backward_speed: 3.0,
+            strafe_speed: 3.0,
+            vertical_speed: 3.0,
+            turn_speed: 2.0,
+            acceleration: 24.0,
+            friction: 16.0,
+            radius: 0.1,
+        }
+    }
+} // impl Default for MovementConfig
+
+/// Movement input representation structure, all components are in [-1, 1] range
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MovementIntent {
+    /// Forward (positive) or backward (negative) movement
+    pub forward: f32,
+    /// Left (positive) or right (negative) strafe
+    pub left: f32,
+    /// Counter-clockwise (positive) or clockwise (negative) turn
+    pub turn: f32,
+    /// Up (positive) or down (negative) movement
+    pub up: f32,
+} // struct MovementIntent
+
+/// Player movement state representation structure
+pub struct Movement {
+    /// Movement parameters
+    pub config: MovementConfig,
+    /// Current horizontal velocity
+    velocity: Vec2f,
+} // struct Movement
+
+impl Movement {
+    /// New movement state create function
+    /// * `config` - movement parameters
+    /// * Returns movement state without velocity
+    pub fn new(config: MovementConfig) -> Self {
+        Self {
+            config,
+            velocity: Vec2f { x: 0.0, y: 0.0 },
+        }
+    } // fn new
+
+    /// Current horizontal velocity getting function
+    /// * Returns velocity
+    pub fn get_velocity(&self) -> Vec2f {
+        self.velocity
+    } // fn get_velocity
+
+    /// Velocity updating function. Velocity is moved towards desired one by acceleration (or by friction if there's no input).
+    /// * `camera` - camera to take movement directions from
+    /// * `intent` - movement input
+    /// * `dt` - time delta
+    pub fn update_velocity(&mut self, camera: &Camera, intent: &MovementIntent, dt: f32) {
+        let forward_speed = if intent.forward >= 0.0 { self.config.forward_speed } else { self.config.backward_speed };
+
+        let target = camera.direction * (intent.forward * forward_speed) - camera.right * (intent.left * self.config.strafe_speed);
+
+        let rate = if intent.forward == 0.0 && intent.left == 0.0 {
+            self.config.friction
+        } else {
+            self.config.acceleration
+        } * dt;
+
+        let delta = target - self.velocity;
+        let delta_length = delta.length();
+
+        self.velocity = if delta_length <= rate {
+            target
+        } else {
+            self.velocity + delta * (rate / delta_length)
+        };
+    } // fn update_velocity
+
+    /// Movement applying function
+    /// * `camera` - camera to move
+    /// * `sector_id` - camera sector identifier, updated if camera crosses portal
+    /// * `map` - map to check collisions in
+    /// * `intent` - movement input
+    /// * `dt` - time delta
+    /// * `free_fly` - ignore collisions and sector bounds
+    /// * Returns index of `sector_id` sector edge crossed during movement, if any
+    pub fn apply_movement(&mut self, camera: &mut Camera, sector_id: &mut SectorId, map: &Map, intent: &MovementIntent, dt: f32, free_fly: bool) -> Option<usize> {
+        self.update_velocity(camera, intent, dt);
+
+        if self.velocity.x == 0.0 && self.velocity.y == 0.0 && intent.turn == 0.0 && intent.up == 0.0 {
+            return None;
+        }
+
+        let new_rotation = camera.rotation + intent.turn * self.config.turn_speed * dt;
+        let new_height = camera.height + intent.up * self.config.vertical_speed * dt;
+        let new_location = camera.location + self.velocity * dt;
+
+        if free_fly {
+            camera.set_location(new_location, new_height, new_rotation);
+
+            // Keep last valid sector if camera is outside of map geometry
+            *sector_id = map.find_sector_from_old(camera.location, *sector_id).unwrap_or(*sector_id);
+            return None;
+        }
+
+        // Walls don't stop movement, but clip it, so camera slides along them
+        let (slid_location, new_sector_id) = map.move_and_slide(camera.location, new_location - camera.location, *sector_id, self.config.radius);
+
+        if let Some(new_sector) = map.get_sector(new_sector_id) {
+            if *sector_id == new_sector_id || (camera.height >= new_sector.floor && camera.height <= new_sector.ceiling) {
+                let crossed_edge = map
+                    .find_adjacent_sector_with_edge(slid_location, *sector_id)
+                    .and_then(|(_, crossed_edge)| crossed_edge);
+
+                // Velocity component into walls is lost
+                if dt > 0.0 {
+                    self.velocity = (slid_location - camera.location) * (1.0 / dt);
+                }
+
+                camera.set_location(
+                    slid_location,
+                    new_height.clamp(new_sector.floor, new_sector.ceiling),
+                    new_rotation,
+                );
+                *sector_id = new_sector_id;
+                return crossed_edge;
+            }
+        }
+
+        // Movement is blocked, so only turn and vertical movement are applied
+        self.velocity = Vec2f { x: 0.0, y: 0.0 };
+        if let Some(sector) = map.get_sector(*sector_id) {
+            camera.set_location(camera.location, new_height.clamp(sector.floor, sector.ceiling), new_rotation);
+        }
+
+        None
+    } // fn apply_movement
+} // impl Movement
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{test_maps, EdgeType, MapBuilder};
+
+    /// Movement simulation function
+    /// * `map` - map to move in
+    /// * `free_fly` - free-fly mode flag
+    /// * Returns camera and its sector after 2 seconds of moving forward and up from room center
+    fn simulate(map: &Map, free_fly: bool) -> (Camera, SectorId) {
+        let mut movement = Movement::new(MovementConfig::default());
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(1.0, 1.0), 0.5, 0.0);
+        let mut sector_id = SectorId::new(0);
+
+        let intent = MovementIntent { forward: 1.0, up: 1.0, ..Default::default() };
+        for _ in 0..120 {
+            movement.apply_movement(&mut camera, &mut sector_id, map, &intent, 1.0 / 60.0, free_fly);
+        }
+
+        (camera, sector_id)
+    } // fn simulate
+
+    #[test]
+    fn free_fly_ignores_walls() {
+        let map = test_maps::room_map(1.0, &[]);
+
+        let (camera, sector_id) = simulate(&map, false);
+        assert!(camera.location.x <= 2.0 - MovementConfig::default().radius + 1e-4);
+        assert!(camera.height <= 1.0);
+        assert_eq!(sector_id, SectorId::new(0));
+
+        let (camera, sector_id) = simulate(&map, true);
+        assert!(camera.location.x > 4.0, "free-fly camera is stopped at {}", camera.location.x);
+        assert!(camera.height > 1.0);
+
+        // Last valid sector is kept outside of geometry
+        assert_eq!(sector_id, SectorId::new(0));
+    } // fn free_fly_ignores_walls
+
+    #[test]
+    fn crossed_portal_edge_is_reported() {
+        let (a, b) = (SectorId::new(0), SectorId::new(1));
+
+        let mut builder = MapBuilder::new();
+        builder.add_sector(&[
+            (Vec2f::new(0.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 0.0), EdgeType::Portal { dst_sector_id: b }),
+            (Vec2f::new(2.0, 2.0), EdgeType::Wall),
+            (Vec2f::new(0.0, 2.0), EdgeType::Wall),
+        ], 0.0, 1.0);
+        builder.add_sector(&[
+            (Vec2f::new(2.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 0.0), EdgeType::Wall),
+            (Vec2f::new(4.0, 2.0), EdgeType::Wall),
+            (Vec2f::new(2.0, 2.0), EdgeType::Portal { dst_sector_id: a }),
+        ], 0.0, 1.0);
+        let map = builder.build().unwrap();
+
+        let mut movement = Movement::new(MovementConfig::default());
+        let mut camera = Camera::new();
+        camera.set_location(Vec2f::new(1.0, 1.0), 0.5, 0.0);
+        let mut sector_id = a;
+
+        // Moves until any edge is crossed, returns sector edge is crossed from, edge index and new sector
+        let mut move_until_crossing = |forward: f32| -> (SectorId, usize, SectorId) {
+            let intent = MovementIntent { forward, ..Default::default() };
+            for _ in 0..240 {
+                let from_sector_id = sector_id;
+                if let Some(edge_index) = movement.apply_movement(&mut camera, &mut sector_id, &map, &intent, 1.0 / 60.0, false) {
+                    return (from_sector_id, edge_index, sector_id);
+                }
+            }
+            panic!("no edge crossed");
+        };
+
+        let (from_sector_id, edge_index, to_sector_id) = move_until_crossing(1.0);
+        let edge = &map.get_sector(from_sector_id).unwrap().edges[edge_index];
+        assert_eq!((from_sector_id, edge_index, to_sector_id), (a, 1, b));
+        assert!(edge.p0 == Vec2f::new(2.0, 0.0) && edge.p1 == Vec2f::new(2.0, 2.0));
+
+        let (from_sector_id, edge_index, to_sector_id) = move_until_crossing(-1.0);
+        let edge = &map.get_sector(from_sector_id).unwrap().edges[edge_index];
+        assert_eq!((from_sector_id, edge_index, to_sector_id), (b, 3, a));
+        assert!(edge.p0 == Vec2f::new(2.0, 2.0) && edge.p1 == Vec2f::new(2.0, 0.0));
+    } // fn crossed_portal_edge_is_reported
+
+    #[test]
+    fn released_input_decelerates_to_zero() {
+        let config = MovementConfig { forward_speed: 4.0, strafe_speed: 1.5, ..Default::default() };
+        let mut movement = Movement::new(config);
+        let camera = Camera::new();
+        let dt = 1.0 / 60.0;
+
+        // Acceleration isn't instant, velocity reaches maximal forward speed
+        movement.update_velocity(&camera, &MovementIntent { forward: 1.0, ..Default::default() }, dt);
+        assert!((movement.get_velocity().length() - config.acceleration * dt).abs() < 1e-4);
+        for _ in 0..60 {
+            movement.update_velocity(&camera, &MovementIntent { forward: 1.0, ..Default::default() }, dt);
+        }
+        assert!((movement.get_velocity().length() - config.forward_speed).abs() < 1e-4);
+
+        // Velocity decreases by friction every step
+        let mut steps = 0;
+        let mut last_speed = movement.get_velocity().length();
+        while last_speed > 0.0 {
+            movement.update_velocity(&camera, &MovementIntent::default(), dt);
+            let speed = movement.get_velocity().length();
+            assert!(speed < last_speed && speed >= last_speed - config.friction * dt - 1e-4, "speed {} after {}", speed, last_speed);
+            last_speed = speed;
+            steps += 1;
+        }
+        // Last step may be spent on rounding error
+        let expected_steps = (config.forward_speed / (config.friction * dt)).ceil() as usize;
+        assert!(steps == expected_steps || steps == expected_steps + 1, "stopped in {} steps", steps);
+
+        // Strafe speed is limited separately
+        for _ in 0..60 {
+            movement.update_velocity(&camera, &MovementIntent { left: 1.0, ..Default::default() }, dt);
+        }
+        assert!((movement.get_velocity().length() - config.strafe_speed).abs() < 1e-4);
+    } // fn released_input_decelerates_to_zero
+} // mod tests
+
+// file movement.rs
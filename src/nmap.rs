@@ -18,6 +18,24 @@ pub struct Edge {
     pub ty: EdgeType,
 } // struct Edge
 
+impl Edge {
+    /// Edge construction function
+    /// * `p0` - edge start point
+    /// * `p1` - edge end point
+    /// * `ty` - edge type
+    /// * Returns edge from `p0` to `p1`
+    pub fn new(p0: Vec2f, p1: Vec2f, ty: EdgeType) -> Self {
+        let direction = p1 - p0;
+
+        Self {
+            position: p0,
+            direction,
+            direction_cross_position: direction % p0,
+            ty,
+        }
+    } // fn new
+} // impl Edge
+
 /// Edge kind.
 pub enum EdgeType {
     /// Portal to another sector
@@ -41,6 +59,26 @@ pub struct Subsector {
     pub edges: Vec<Edge>,
 } // struct Subsector
 
+impl Subsector {
+    /// Point in subsector (including boundary) checking function
+    /// * `point` - point to check
+    /// * Returns true if point is located inside subsector or on its boundary
+    pub fn contains(&self, point: Vec2f) -> bool {
+        // Subsector is convex, so point is inside if it's located at the same side of all edges (any orientation is accepted)
+        let mut has_left = false;
+        let mut has_right = false;
+
+        for edge in &self.edges {
+            let side = edge.direction % (point - edge.position);
+
+            has_left |= side > DECOMPOSITION_EPSILON;
+            has_right |= side < -DECOMPOSITION_EPSILON;
+        }
+
+        !(has_left && has_right)
+    } // fn contains
+} // impl Subsector
+
 /// Sector. Sector is unit part of the map.
 pub struct Sector {
     /// Set of sector convex subsectors
@@ -51,10 +89,413 @@ pub struct Sector {
     pub ceiling: f32,
 } // struct Sector
 
+impl Sector {
+    /// Subsector containing point finding function
+    /// * `point` - point to find subsector of
+    /// * Returns index of first subsector point is located in (or on boundary of)
+    pub fn find_subsector(&self, point: Vec2f) -> Option<u32> {
+        self.subsectors
+            .iter()
+            .position(|subsector| subsector.contains(point))
+            .map(|index| index as u32)
+    } // fn find_subsector
+} // impl Sector
+
+/// Polygon orientation tolerance, triangles with doubled area below it are treated as degenerate
+const DECOMPOSITION_EPSILON: f32 = 1e-6;
+
+/// Turn direction at polygon vertex calculation function
+/// * `prev`, `point`, `next` - polygon vertex and its neighbours
+/// * Returns doubled signed area of (prev, point, next) triangle, positive if it's a left turn
+fn get_turn(prev: Vec2f, point: Vec2f, next: Vec2f) -> f32 {
+    (point - prev) % (next - point)
+} // fn get_turn
+
+/// Point in triangle (including boundary) checking function, triangle is counter-clockwise
+/// * `point` - point to check
+/// * `a`, `b`, `c` - triangle vertices
+/// * Returns true if point is located inside triangle or on its boundary
+fn triangle_contains(point: Vec2f, a: Vec2f, b: Vec2f, c: Vec2f) -> bool {
+    get_turn(a, b, point) >= -DECOMPOSITION_EPSILON
+        && get_turn(b, c, point) >= -DECOMPOSITION_EPSILON
+        && get_turn(c, a, point) >= -DECOMPOSITION_EPSILON
+} // fn triangle_contains
+
+/// Polygon triangulation function (ear clipping)
+/// * `points` - counter-clockwise simple polygon points
+/// * Returns counter-clockwise triangles as point index triples (collinear points are skipped), None if polygon can't be fully triangulated
+fn triangulate(points: &[Vec2f]) -> Option<Vec<[usize; 3]>> {
+    let mut remaining = (0..points.len()).collect::<Vec<usize>>();
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2));
+
+    while remaining.len() >= 3 {
+        let count = remaining.len();
+        let mut clipped = false;
+
+        for index in 0..count {
+            let (prev, point, next) = (remaining[(index + count - 1) % count], remaining[index], remaining[(index + 1) % count]);
+            let turn = get_turn(points[prev], points[point], points[next]);
+
+            // Collinear vertex, just remove it
+            if turn.abs() <= DECOMPOSITION_EPSILON {
+                remaining.remove(index);
+                clipped = true;
+                break;
+            }
+
+            if turn < 0.0 {
+                continue;
+            }
+
+            // Vertex is ear if there's no other polygon points in its triangle
+            let is_ear = remaining
+                .iter()
+                .filter(|other| ![prev, point, next].contains(other))
+                .all(|other| {
+                    let other_point = points[*other];
+
+                    [points[prev], points[point], points[next]].contains(&other_point)
+                        || !triangle_contains(other_point, points[prev], points[point], points[next])
+                });
+
+            if is_ear {
+                triangles.push([prev, point, next]);
+                remaining.remove(index);
+                clipped = true;
+                break;
+            }
+        }
+
+        // Polygon is self-intersecting, no ears left
+        if !clipped {
+            return None;
+        }
+    }
+
+    Some(triangles)
+} // fn triangulate
+
+/// Convex polygon checking function
+/// * `points` - all polygon points
+/// * `polygon` - indices of counter-clockwise polygon points
+/// * Returns true if polygon has no right turns
+fn is_convex_polygon(points: &[Vec2f], polygon: &[usize]) -> bool {
+    let count = polygon.len();
+
+    (0..count).all(|index| {
+        get_turn(points[polygon[(index + count - 1) % count]], points[polygon[index]], points[polygon[(index + 1) % count]]) >= -DECOMPOSITION_EPSILON
+    })
+} // fn is_convex_polygon
+
+/// Polygons by common edge merging function
+/// * `lhs` - polygon containing `a` -> `b` edge
+/// * `rhs` - polygon containing `b` -> `a` edge
+/// * `a`, `b` - common edge
+/// * Returns merged polygon
+fn merge_polygons(lhs: &[usize], rhs: &[usize], a: usize, b: usize) -> Vec<usize> {
+    let lhs_b = lhs.iter().position(|index| *index == b).unwrap();
+    let rhs_a = rhs.iter().position(|index| *index == a).unwrap();
+
+    // lhs from b to a, then rhs from a to b without common points
+    let mut merged = Vec::with_capacity(lhs.len() + rhs.len() - 2);
+    merged.extend((0..lhs.len()).map(|offset| lhs[(lhs_b + offset) % lhs.len()]));
+    merged.extend((1..rhs.len() - 1).map(|offset| rhs[(rhs_a + offset) % rhs.len()]));
+
+    merged
+} // fn merge_polygons
+
+/// Polygon containing edge finding function
+/// * `polygons` - counter-clockwise polygons to search edge in
+/// * `from`, `to` - edge point indices
+/// * Returns index of polygon containing `from` -> `to` edge
+fn find_edge_owner(polygons: &[Vec<usize>], from: usize, to: usize) -> Option<usize> {
+    polygons.iter().position(|polygon| polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .any(|(p0, p1)| (*p0, *p1) == (from, to))
+    )
+} // fn find_edge_owner
+
+/// Simple (possibly concave) polygon to convex subsectors decomposition function.
+/// Polygon is triangulated and then neighbour pieces are merged while they stay convex (Hertel-Mehlhorn algorithm),
+/// so there are at most four times more subsectors than in optimal decomposition.
+/// * `points` - simple polygon points in any order
+/// * Returns counter-clockwise subsectors, polygon edges are walls and edges between subsectors are subportals (by index in returned vector).
+///   Empty vector is returned for degenerate and self-intersecting polygons, so area is never silently dropped.
+pub fn decompose_into_subsectors(points: &[Vec2f]) -> Vec<Subsector> {
+    // Remove repeating points and make polygon counter-clockwise
+    let mut points = points.to_vec();
+    points.dedup();
+    while points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 3 {
+        return Vec::new();
+    }
+
+    let doubled_area = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(p0, p1)| *p0 % *p1)
+        .sum::<f32>();
+    if doubled_area.abs() <= DECOMPOSITION_EPSILON {
+        return Vec::new();
+    }
+    if doubled_area < 0.0 {
+        points.reverse();
+    }
+
+    let triangles = match triangulate(&points) {
+        Some(triangles) => triangles,
+        None => return Vec::new(),
+    };
+
+    let mut polygons = triangles
+        .into_iter()
+        .map(|triangle| triangle.to_vec())
+        .collect::<Vec<Vec<usize>>>();
+
+    // Merge polygons by diagonals while result is convex
+    'merge: loop {
+        for lhs_index in 0..polygons.len() {
+            let lhs = &polygons[lhs_index];
+
+            for edge_index in 0..lhs.len() {
+                let (a, b) = (lhs[edge_index], lhs[(edge_index + 1) % lhs.len()]);
+
+                let rhs_index = match find_edge_owner(&polygons, b, a) {
+                    Some(index) => index,
+                    None => continue,
+                };
+
+                let merged = merge_polygons(lhs, &polygons[rhs_index], a, b);
+                if !is_convex_polygon(&points, &merged) {
+                    continue;
+                }
+
+                polygons[lhs_index] = merged;
+                polygons.remove(rhs_index);
+                continue 'merge;
+            }
+        }
+
+        break;
+    }
+
+    // Build subsectors, shared edges become subportals
+    polygons
+        .iter()
+        .map(|polygon| Subsector {
+            edges: polygon
+                .iter()
+                .zip(polygon.iter().cycle().skip(1))
+                .map(|(a, b)| Edge::new(points[*a], points[*b], match find_edge_owner(&polygons, *b, *a) {
+                    Some(subsector_id) => EdgeType::Subportal { subsector_id: subsector_id as u32 },
+                    None => EdgeType::Wall {},
+                }))
+                .collect(),
+        })
+        .collect()
+} // fn decompose_into_subsectors
+
 /// Map representation structure
 pub struct Map {
     /// Map sector set
     pub sectors: Vec<Sector>,
 } // struct Map
 
+impl Map {
+    /// From old (convex sector) map construction function
+    /// * `old` - map to convert, every its sector becomes sector with single subsector
+    /// * Returns converted map, sector indices match old sector ids
+    pub fn from_legacy(old: &crate::map::Map) -> Self {
+        Self {
+            sectors: old
+                .iter_indexed_sectors()
+                .map(|(_, sector)| Sector {
+                    subsectors: vec![Subsector {
+                        edges: sector.edges
+                            .iter()
+                            .map(|edge| Edge::new(edge.p0, edge.p1, match edge.ty {
+                                crate::map::EdgeType::Wall => EdgeType::Wall {},
+                                crate::map::EdgeType::Portal { dst_sector_id } => EdgeType::Portal { sector_id: dst_sector_id.as_u32() },
+                            }))
+                            .collect(),
+                    }],
+                    floor: sector.floor,
+                    ceiling: sector.ceiling,
+                })
+                .collect(),
+        }
+    } // fn from_legacy
+
+    /// Subsector containing point finding function
+    /// * `point` - point to find subsector of
+    /// * Returns (sector index, subsector index) pair of first subsector point is located in
+    pub fn find_subsector(&self, point: Vec2f) -> Option<(u32, u32)> {
+        self.sectors
+            .iter()
+            .enumerate()
+            .find_map(|(sector_id, sector)| sector
+                .find_subsector(point)
+                .map(|subsector_id| (sector_id as u32, subsector_id))
+            )
+    } // fn find_subsector
+} // impl Map
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Subsector doubled signed area calculation function
+    /// * `subsector` - subsector to calculate area of
+    /// * Returns doubled area, positive for counter-clockwise subsectors
+    fn get_doubled_area(subsector: &Subsector) -> f32 {
+        subsector.edges
+            .iter()
+            .map(|edge| edge.position % (edge.position + edge.direction))
+            .sum()
+    } // fn get_doubled_area
+
+    /// Subsector convexity checking function
+    /// * `subsector` - subsector to check
+    /// * Returns true if subsector has no right turns
+    fn is_convex(subsector: &Subsector) -> bool {
+        subsector.edges
+            .iter()
+            .zip(subsector.edges.iter().cycle().skip(1))
+            .all(|(edge, next)| edge.direction % next.direction >= -DECOMPOSITION_EPSILON)
+    } // fn is_convex
+
+    /// Subportals of subsector getting function
+    /// * `subsector` - subsector to get subportals of
+    /// * Returns (edge start, edge end, destination subsector) triples
+    fn get_subportals(subsector: &Subsector) -> Vec<(Vec2f, Vec2f, u32)> {
+        subsector.edges
+            .iter()
+            .filter_map(|edge| match edge.ty {
+                EdgeType::Subportal { subsector_id } => Some((edge.position, edge.position + edge.direction, subsector_id)),
+                _ => None,
+            })
+            .collect()
+    } // fn get_subportals
+
+    #[test]
+    fn l_shape_is_decomposed_into_two_convex_subsectors() {
+        let l_shape = [
+            Vec2f::new(0.0, 0.0), Vec2f::new(2.0, 0.0), Vec2f::new(2.0, 1.0),
+            Vec2f::new(1.0, 1.0), Vec2f::new(1.0, 2.0), Vec2f::new(0.0, 2.0),
+        ];
+
+        // Clockwise polygon is decomposed same way
+        let mut clockwise = l_shape;
+        clockwise.reverse();
+
+        for points in [l_shape, clockwise] {
+            let subsectors = decompose_into_subsectors(&points);
+            assert_eq!(subsectors.len(), 2);
+
+            for (index, subsector) in subsectors.iter().enumerate() {
+                assert!(is_convex(subsector), "subsector {} isn't convex", index);
+                assert!(get_doubled_area(subsector) > 0.0);
+            }
+            let total_area = subsectors.iter().map(get_doubled_area).sum::<f32>() / 2.0;
+            assert!((total_area - 3.0).abs() < 1e-5);
+
+            // Single subportal per subsector, subportals are the same edge in opposite directions
+            let (subportals0, subportals1) = (get_subportals(&subsectors[0]), get_subportals(&subsectors[1]));
+            assert_eq!(subportals0.len(), 1);
+            assert_eq!(subportals1.len(), 1);
+            assert_eq!((subportals0[0].0, subportals0[0].1, subportals0[0].2), (subportals1[0].1, subportals1[0].0, 1));
+            assert_eq!(subportals1[0].2, 0);
+
+            // All the polygon edges are walls
+            let wall_count = subsectors.iter().flat_map(|subsector| &subsector.edges).filter(|edge| matches!(edge.ty, EdgeType::Wall {})).count();
+            assert_eq!(wall_count, 6);
+
+            assert_eq!(subsectors.iter().filter(|subsector| subsector.contains(Vec2f::new(1.5, 0.5))).count(), 1);
+            assert_eq!(subsectors.iter().filter(|subsector| subsector.contains(Vec2f::new(0.5, 1.5))).count(), 1);
+            assert!(subsectors.iter().all(|subsector| !subsector.contains(Vec2f::new(1.5, 1.5))));
+        }
+    } // fn l_shape_is_decomposed_into_two_convex_subsectors
+
+    #[test]
+    fn convex_and_degenerate_polygons_are_decomposed() {
+        // Convex polygon with collinear and repeating points stays single subsector
+        let square = [
+            Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 0.0), Vec2f::new(2.0, 0.0), Vec2f::new(2.0, 2.0),
+            Vec2f::new(2.0, 2.0), Vec2f::new(0.0, 2.0), Vec2f::new(0.0, 0.0),
+        ];
+        let subsectors = decompose_into_subsectors(&square);
+        assert_eq!(subsectors.len(), 1);
+        assert!(is_convex(&subsectors[0]));
+        assert!(get_subportals(&subsectors[0]).is_empty());
+        assert!((get_doubled_area(&subsectors[0]) - 8.0).abs() < 1e-5);
+
+        // U shape requires at least three convex pieces
+        let u_shape = [
+            Vec2f::new(0.0, 0.0), Vec2f::new(3.0, 0.0), Vec2f::new(3.0, 2.0), Vec2f::new(2.0, 2.0),
+            Vec2f::new(2.0, 1.0), Vec2f::new(1.0, 1.0), Vec2f::new(1.0, 2.0), Vec2f::new(0.0, 2.0),
+        ];
+        let subsectors = decompose_into_subsectors(&u_shape);
+        assert!((3..=4).contains(&subsectors.len()));
+        assert!(subsectors.iter().all(is_convex));
+        assert!((subsectors.iter().map(get_doubled_area).sum::<f32>() - 10.0).abs() < 1e-5);
+
+        // Every subportal has reverse one in its destination subsector
+        for (index, subsector) in subsectors.iter().enumerate() {
+            for (p0, p1, destination) in get_subportals(subsector) {
+                assert!(get_subportals(&subsectors[destination as usize]).contains(&(p1, p0, index as u32)));
+            }
+        }
+
+        assert!(decompose_into_subsectors(&[Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 1.0)]).is_empty());
+        assert!(decompose_into_subsectors(&[Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 1.0), Vec2f::new(2.0, 2.0)]).is_empty());
+    } // fn convex_and_degenerate_polygons_are_decomposed
+
+    #[test]
+    fn self_intersecting_polygon_isnt_decomposed() {
+        // Pentagram goes around its center twice, so it has nonzero area, but isn't simple
+        let pentagram = (0..5)
+            .map(|index| {
+                let angle = (index * 2) as f32 * std::f32::consts::TAU / 5.0;
+                Vec2f::new(angle.cos(), angle.sin())
+            })
+            .collect::<Vec<_>>();
+
+        assert!(triangulate(&pentagram).is_none());
+        assert!(decompose_into_subsectors(&pentagram).is_empty());
+    } // fn self_intersecting_polygon_isnt_decomposed
+
+    #[test]
+    fn legacy_map_is_converted() {
+        let old = crate::map::Map::load_from_wmt(include_str!("../maps/default.wmt")).unwrap();
+        let map = Map::from_legacy(&old);
+
+        assert_eq!(map.sectors.len(), old.iter_indexed_sectors().count());
+
+        for ((old_id, old_sector), sector) in old.iter_indexed_sectors().zip(&map.sectors) {
+            assert_eq!((sector.floor, sector.ceiling), (old_sector.floor, old_sector.ceiling));
+            assert_eq!(sector.subsectors.len(), 1);
+
+            let edges = &sector.subsectors[0].edges;
+            assert_eq!(edges.len(), old_sector.edges.len());
+
+            for (edge, old_edge) in edges.iter().zip(&old_sector.edges) {
+                assert_eq!(edge.position, old_edge.p0);
+                assert_eq!(edge.position + edge.direction, old_edge.p1);
+
+                match (&edge.ty, old_edge.ty) {
+                    (EdgeType::Wall {}, crate::map::EdgeType::Wall) => {}
+                    (EdgeType::Portal { sector_id }, crate::map::EdgeType::Portal { dst_sector_id }) => assert_eq!(*sector_id, dst_sector_id.as_u32()),
+                    _ => panic!("edge type of sector {} isn't preserved", old_id.as_u32()),
+                }
+            }
+        }
+
+        let camera_sector = old.find_sector(old.camera_location).unwrap();
+        assert_eq!(map.find_subsector(old.camera_location), Some((camera_sector.as_u32(), 0)));
+    } // fn legacy_map_is_converted
+} // mod tests
+
 // file map.rs
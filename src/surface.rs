@@ -4,7 +4,33 @@
 /// `Author` TioT2
 /// `Last changed` 04.05.2024
 
+use std::io::Write;
+
 use crate::Ext2;
+use crate::math::Rect;
+
+/// Two surfaces difference representation structure
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfaceDiff {
+    /// Count of differing pixels
+    pub pixel_count: usize,
+    /// Bounding box of differing pixels
+    pub bounds: Rect<usize>,
+    /// Maximal difference of single pixel channel
+    pub max_channel_delta: u8,
+} // struct SurfaceDiff
+
+/// Color alpha blending function, high (unused) bytes of colors are ignored
+/// * `dst` - 0x00RRGGBB color to blend over
+/// * `src` - 0x00RRGGBB color to blend
+/// * `alpha` - `src` opacity, 0 keeps `dst` and 255 gives `src`
+/// * Returns blended 0x00RRGGBB color
+pub fn blend_color(dst: u32, src: u32, alpha: u8) -> u32 {
+    let alpha = alpha as u32;
+    let channel = |shift: u32| ((((dst >> shift) & 0xFF) * (255 - alpha) + ((src >> shift) & 0xFF) * alpha) / 255) << shift;
+
+    channel(16) | channel(8) | channel(0)
+} // fn blend_color
 
 /// Software rendering surface representation structure
 pub struct Surface<'t> {
@@ -31,7 +57,7 @@ impl<'t> Surface<'t> {
     } // fn new
     /// Unclipped bar display function
     /// * `x0`, `y0` - bar begin point
-    /// * `x1`, `y1` - bar end point
+    /// * `x1`, `y1` - bar end point, must not be less than begin one
     /// * `color` - bar color
     pub unsafe fn draw_bar_unchecked(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, color: u32) {
         let mut yptr = self.data.as_mut_ptr().add(y0 * self.stride + x0);
@@ -53,20 +79,27 @@ impl<'t> Surface<'t> {
 
     /// Checked bar display function
     /// * `x0`, `y0` - first bar point
-    /// * `x1`, `y1` - second bar point
+    /// * `x1`, `y1` - second bar point, may be located before first one
     /// * `color` - bar color
     pub fn draw_bar(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, color: u32) {
-        unsafe {
-            let x0 = std::mem::transmute::<isize, usize>(x0.clamp(0, self.width as isize));
-            let y0 = std::mem::transmute::<isize, usize>(y0.clamp(0, self.height as isize));
-
-            let x1 = std::mem::transmute::<isize, usize>(x1.clamp(0, self.width as isize));
-            let y1 = std::mem::transmute::<isize, usize>(y1.clamp(0, self.height as isize));
-
-            self.draw_bar_unchecked(x0, y0, x1, y1, color);
+        // Unchecked version requires ordered coordinates located in surface bounds
+        if let Some(bar) = self.clip_bar(x0, y0, x1, y1) {
+            unsafe {
+                self.draw_bar_unchecked(bar.x.start, bar.y.start, bar.x.end, bar.y.end, color);
+            }
         }
     } // fn draw_bar
 
+    /// Bar by surface bounds clipping function
+    /// * `x0`, `y0` - first bar point
+    /// * `x1`, `y1` - second bar point, may be located before first one
+    /// * Returns bar part located in surface or None if bar is empty or located outside of surface
+    fn clip_bar(&self, x0: isize, y0: isize, x1: isize, y1: isize) -> Option<Rect<usize>> {
+        Rect::new(x0.min(x1)..x0.max(x1), y0.min(y1)..y0.max(y1))
+            .intersection(&Rect::new(0..self.width as isize, 0..self.height as isize))
+            .map(|bar| Rect::new(bar.x.start as usize..bar.x.end as usize, bar.y.start as usize..bar.y.end as usize))
+    } // fn clip_bar
+
     /// Line clipping in on (0, 0, width, height) rectangle function
     /// * `x0`, `y0` - first line point position
     /// * `x1`, `y1` - second line point position
@@ -226,6 +259,376 @@ impl<'t> Surface<'t> {
         }
     } // fn draw_line_unchecked
 
+    /// Anti-aliased line drawing function (Xiaolin Wu's algorithm), line is blended over surface contents.
+    /// Integer coordinates are pixel centers, end points are included like in `draw_line`.
+    /// * `x0`, `y0` - first line point
+    /// * `x1`, `y1` - second line point
+    /// * `color` - color of line
+    pub fn draw_line_aa(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, color: u32) {
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+
+        // Line is walked along its major axis from left to right
+        let (x0, y0, x1, y1) = if steep { (y0, x0, y1, x1) } else { (x0, y0, x1, y1) };
+        let (x0, y0, x1, y1) = if x0 > x1 { (x1, y1, x0, y0) } else { (x0, y0, x1, y1) };
+        let major_size = if steep { self.height } else { self.width } as isize;
+
+        let mut plot = |x: isize, y: isize, coverage: f32| {
+            let (x, y) = if steep { (y, x) } else { (x, y) };
+
+            if x >= 0 && y >= 0 {
+                self.blend_pixel(x as usize, y as usize, color, (coverage.clamp(0.0, 1.0) * 255.0) as u8);
+            }
+        };
+
+        // Fractional part, positive for negative values too
+        let fpart = |value: f32| value - value.floor();
+
+        let dx = x1 - x0;
+        let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+        // End points, `gap` is part of end pixel covered by line
+        let x_begin = x0.round();
+        let y_begin = y0 + gradient * (x_begin - x0);
+        let gap = (x_begin - x0 + 1.0).min(1.0);
+        plot(x_begin as isize, y_begin.floor() as isize, (1.0 - fpart(y_begin)) * gap);
+        plot(x_begin as isize, y_begin.floor() as isize + 1, fpart(y_begin) * gap);
+
+        let x_end = x1.round();
+        if x_end == x_begin {
+            return;
+        }
+        let y_end = y1 + gradient * (x_end - x1);
+        let gap = (x1 - x_end + 1.0).min(1.0);
+        plot(x_end as isize, y_end.floor() as isize, (1.0 - fpart(y_end)) * gap);
+        plot(x_end as isize, y_end.floor() as isize + 1, fpart(y_end) * gap);
+
+        // Main span (clipped by surface along major axis), each column is split between two nearest rows
+        let span_begin = (x_begin as isize + 1).max(0);
+        let span_end = (x_end as isize).min(major_size);
+
+        let mut y = y_begin + gradient * (span_begin as f32 - x_begin);
+        for x in span_begin..span_end {
+            plot(x, y.floor() as isize, 1.0 - fpart(y));
+            plot(x, y.floor() as isize + 1, fpart(y));
+            y += gradient;
+        }
+    } // fn draw_line_aa
+
+    /// Triangle filling function, pixels are filled if their centers are inside of triangle
+    /// * `p0`, `p1`, `p2` - triangle points
+    /// * `color` - triangle color
+    pub fn fill_triangle(&mut self, p0: (isize, isize), p1: (isize, isize), p2: (isize, isize), color: u32) {
+        let mut points = [p0, p1, p2];
+        points.sort_by_key(|point| point.1);
+        let [p0, p1, p2] = points;
+
+        // X coordinate of (pa, pb) edge at y
+        let edge_x = |pa: (isize, isize), pb: (isize, isize), y: f32| -> f32 {
+            pa.0 as f32 + (pb.0 - pa.0) as f32 * (y - pa.1 as f32) / (pb.1 - pa.1) as f32
+        };
+
+        for y in p0.1.clamp(0, self.height as isize)..p2.1.clamp(0, self.height as isize) {
+            let pixel_y = y as f32 + 0.5;
+
+            // Long edge always spans current row, short one is chosen by middle point
+            let long_x = edge_x(p0, p2, pixel_y);
+            let short_x = if y < p1.1 {
+                edge_x(p0, p1, pixel_y)
+            } else {
+                edge_x(p1, p2, pixel_y)
+            };
+
+            let x_begin = ((long_x.min(short_x) - 0.5).ceil() as isize).clamp(0, self.width as isize) as usize;
+            let x_end = ((long_x.max(short_x) - 0.5).ceil() as isize).clamp(0, self.width as isize) as usize;
+
+            let row = y as usize * self.stride;
+            self.data[row + x_begin..row + x_end].fill(color);
+        }
+    } // fn fill_triangle
+
+    /// Convex polygon filling function, pixels are filled if their centers are inside of polygon
+    /// * `points` - polygon points in any winding order, polygon must be convex
+    /// * `color` - polygon color
+    pub fn fill_convex_polygon(&mut self, points: &[(isize, isize)], color: u32) {
+        let (y_min, y_max) = match points.iter().map(|point| point.1).fold(None, |range: Option<(isize, isize)>, y| Some(match range {
+            Some((y_min, y_max)) => (y_min.min(y), y_max.max(y)),
+            None => (y, y),
+        })) {
+            Some(range) => range,
+            None => return,
+        };
+
+        // Edges with nonzero height, sorted by upper point
+        let mut edges = points.iter()
+            .zip(points.iter().cycle().skip(1))
+            .filter(|(pa, pb)| pa.1 != pb.1)
+            .map(|(&pa, &pb)| if pa.1 < pb.1 { (pa, pb) } else { (pb, pa) })
+            .collect::<Vec<_>>();
+        edges.sort_by_key(|(pa, _)| pa.1);
+
+        for y in y_min.clamp(0, self.height as isize)..y_max.clamp(0, self.height as isize) {
+            let pixel_y = y as f32 + 0.5;
+
+            // Row span is bounded by leftmost and rightmost edge crossings
+            let span = edges.iter()
+                .take_while(|(pa, _)| (pa.1 as f32) <= pixel_y)
+                .filter(|(_, pb)| (pb.1 as f32) > pixel_y)
+                .map(|(pa, pb)| pa.0 as f32 + (pb.0 - pa.0) as f32 * (pixel_y - pa.1 as f32) / (pb.1 - pa.1) as f32)
+                .fold(None, |span: Option<(f32, f32)>, x| Some(match span {
+                    Some((x_min, x_max)) => (x_min.min(x), x_max.max(x)),
+                    None => (x, x),
+                }));
+
+            if let Some((x_min, x_max)) = span {
+                let x_begin = ((x_min - 0.5).ceil() as isize).clamp(0, self.width as isize) as usize;
+                let x_end = ((x_max - 0.5).ceil() as isize).clamp(0, self.width as isize) as usize;
+
+                let row = y as usize * self.stride;
+                self.data[row + x_begin..row + x_end].fill(color);
+            }
+        }
+    } // fn fill_convex_polygon
+
+    /// Circle outline drawing function (midpoint algorithm), out-of-surface pixels are skipped
+    /// * `cx`, `cy` - circle center
+    /// * `radius` - circle radius, nothing is drawn if negative
+    /// * `color` - circle color
+    pub fn draw_circle(&mut self, cx: isize, cy: isize, radius: isize, color: u32) {
+        if radius < 0 {
+            return;
+        }
+
+        let mut put_pixel = |x: isize, y: isize| {
+            if x >= 0 && y >= 0 {
+                self.set_pixel(x as usize, y as usize, color);
+            }
+        };
+
+        let mut x = radius;
+        let mut y = 0;
+        let mut error = 1 - radius;
+
+        while x >= y {
+            // Octant symmetry
+            put_pixel(cx + x, cy + y);
+            put_pixel(cx + y, cy + x);
+            put_pixel(cx - y, cy + x);
+            put_pixel(cx - x, cy + y);
+            put_pixel(cx - x, cy - y);
+            put_pixel(cx - y, cy - x);
+            put_pixel(cx + y, cy - x);
+            put_pixel(cx + x, cy - y);
+
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    } // fn draw_circle
+
+    /// Circle filling function, filled area is bounded by `draw_circle` outline
+    /// * `cx`, `cy` - circle center
+    /// * `radius` - circle radius, nothing is drawn if negative
+    /// * `color` - circle color
+    pub fn fill_circle(&mut self, cx: isize, cy: isize, radius: isize, color: u32) {
+        if radius < 0 {
+            return;
+        }
+
+        let radius2 = (radius * radius + radius) as f32;
+
+        for y in (cy - radius).clamp(0, self.height as isize)..(cy + radius + 1).clamp(0, self.height as isize) {
+            let dy = (y - cy) as f32;
+            let half_width = (radius2 - dy * dy).max(0.0).sqrt() as isize;
+
+            let x_begin = (cx - half_width).clamp(0, self.width as isize) as usize;
+            let x_end = (cx + half_width + 1).clamp(0, self.width as isize) as usize;
+
+            let row = y as usize * self.stride;
+            self.data[row + x_begin..row + x_end].fill(color);
+        }
+    } // fn fill_circle
+
+    /// Checked pixel setting function, out-of-surface pixels are ignored
+    /// * `x`, `y` - pixel coordinates
+    /// * `color` - pixel color
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: u32) {
+        if x < self.width && y < self.height {
+            if let Some(pixel) = self.data.get_mut(y * self.stride + x) {
+                *pixel = color;
+            }
+        }
+    } // fn set_pixel
+
+    /// Checked pixel getting function
+    /// * `x`, `y` - pixel coordinates
+    /// * Returns pixel color or None if pixel is out of surface
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u32> {
+        if x < self.width && y < self.height {
+            self.data.get(y * self.stride + x).copied()
+        } else {
+            None
+        }
+    } // fn get_pixel
+
+    /// Checked pixel blending function, out-of-surface pixels are ignored
+    /// * `x`, `y` - pixel coordinates
+    /// * `color` - color to blend over pixel
+    /// * `alpha` - color opacity
+    pub fn blend_pixel(&mut self, x: usize, y: usize, color: u32, alpha: u8) {
+        if x < self.width && y < self.height {
+            if let Some(pixel) = self.data.get_mut(y * self.stride + x) {
+                *pixel = blend_color(*pixel, color, alpha);
+            }
+        }
+    } // fn blend_pixel
+
+    /// Checked blended bar display function
+    /// * `x0`, `y0` - first bar point
+    /// * `x1`, `y1` - second bar point, may be located before first one
+    /// * `color` - bar color
+    /// * `alpha` - bar opacity
+    pub fn draw_bar_blended(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, color: u32, alpha: u8) {
+        let bar = match self.clip_bar(x0, y0, x1, y1) {
+            Some(bar) => bar,
+            None => return,
+        };
+
+        for y in bar.y {
+            for pixel in &mut self.data[y * self.stride + bar.x.start..y * self.stride + bar.x.end] {
+                *pixel = blend_color(*pixel, color, alpha);
+            }
+        }
+    } // fn draw_bar_blended
+
+    /// Surface copying function, copied part is clipped by this surface bounds
+    /// * `src` - surface to copy pixels of
+    /// * `dst_x`, `dst_y` - location of `src` top left corner on this surface
+    /// * `transparent` - color that isn't copied, all pixels are copied if not set
+    fn blit_impl(&mut self, src: &Surface, dst_x: usize, dst_y: usize, transparent: Option<u32>) {
+        let width = src.width.min(self.width.saturating_sub(dst_x));
+        let height = src.height.min(self.height.saturating_sub(dst_y));
+
+        // Row offsets are computed from `dst_x`, so empty copy must not reach them
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        for y in 0..height {
+            let src_row = &src.data[y * src.stride..y * src.stride + width];
+            let dst_start = (dst_y + y) * self.stride + dst_x;
+            let dst_row = &mut self.data[dst_start..dst_start + width];
+
+            match transparent {
+                None => dst_row.copy_from_slice(src_row),
+                Some(transparent) => {
+                    for (dst, src) in dst_row.iter_mut().zip(src_row) {
+                        if *src != transparent {
+                            *dst = *src;
+                        }
+                    }
+                }
+            }
+        }
+    } // fn blit_impl
+
+    /// Surface copying function, copied part is clipped by this surface bounds
+    /// * `src` - surface to copy pixels of
+    /// * `dst_x`, `dst_y` - location of `src` top left corner on this surface
+    pub fn blit(&mut self, src: &Surface, dst_x: usize, dst_y: usize) {
+        self.blit_impl(src, dst_x, dst_y, None);
+    } // fn blit
+
+    /// Color keyed surface copying function, copied part is clipped by this surface bounds
+    /// * `src` - surface to copy pixels of
+    /// * `dst_x`, `dst_y` - location of `src` top left corner on this surface
+    /// * `transparent` - `src` color that isn't copied
+    pub fn blit_keyed(&mut self, src: &Surface, dst_x: usize, dst_y: usize, transparent: u32) {
+        self.blit_impl(src, dst_x, dst_y, Some(transparent));
+    } // fn blit_keyed
+
+    /// Rectangle filling function
+    /// * `rect` - rectangle to fill, clipped by surface bounds
+    /// * `color` - fill color
+    pub fn fill(&mut self, rect: Rect<usize>, color: u32) {
+        let rect = match rect.intersection(&Rect::new(0..self.width, 0..self.height)) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        for y in rect.y {
+            let row_start = y * self.stride;
+
+            if let Some(row) = self.data.get_mut(row_start + rect.x.start..row_start + rect.x.end) {
+                row.fill(color);
+            }
+        }
+    } // fn fill
+
+    /// Surface clearing function, only `width` pixels of each row are touched
+    /// * `color` - color to fill surface with
+    pub fn clear(&mut self, color: u32) {
+        self.fill(Rect::new(0..self.width, 0..self.height), color);
+    } // fn clear
+
+    /// Surface comparison function. Surfaces of different extents are reported as totally different.
+    /// * `other` - surface to compare with
+    /// * Returns None if surfaces are equal and difference description otherwise
+    pub fn diff(&self, other: &Surface) -> Option<SurfaceDiff> {
+        if self.width != other.width || self.height != other.height {
+            let width = self.width.max(other.width);
+            let height = self.height.max(other.height);
+
+            return Some(SurfaceDiff {
+                pixel_count: width * height,
+                bounds: Rect::new(0..width, 0..height),
+                max_channel_delta: u8::MAX,
+            });
+        }
+
+        let mut result: Option<SurfaceDiff> = None;
+
+        for y in 0..self.height {
+            let self_row = &self.data[y * self.stride..y * self.stride + self.width];
+            let other_row = &other.data[y * other.stride..y * other.stride + other.width];
+
+            for (x, (l, r)) in self_row.iter().zip(other_row.iter()).enumerate() {
+                if l == r {
+                    continue;
+                }
+
+                let channel_delta = l.to_le_bytes()
+                    .iter()
+                    .zip(r.to_le_bytes().iter())
+                    .map(|(lc, rc)| lc.abs_diff(*rc))
+                    .max()
+                    .unwrap_or(0);
+
+                match result.as_mut() {
+                    Some(diff) => {
+                        diff.pixel_count += 1;
+                        diff.bounds.x.start = diff.bounds.x.start.min(x);
+                        diff.bounds.x.end = diff.bounds.x.end.max(x + 1);
+                        diff.bounds.y.end = y + 1;
+                        diff.max_channel_delta = diff.max_channel_delta.max(channel_delta);
+                    }
+                    None => {
+                        result = Some(SurfaceDiff {
+                            pixel_count: 1,
+                            bounds: Rect::new(x..x + 1, y..y + 1),
+                            max_channel_delta: channel_delta,
+                        });
+                    }
+                }
+            }
+        }
+
+        result
+    } // fn diff
+
     /// Surface data getting function
     /// * Returns mutable slice of all surface data
     pub fn get_data_mut<'a>(&'a mut self) -> &'a mut [u32] {
@@ -252,6 +655,474 @@ impl<'t> Surface<'t> {
     pub fn get_stride(&self) -> usize {
         self.stride
     } // fn get_stride
+
+    /// Surface row getting function
+    /// * `y` - row index, must be less than height
+    /// * Returns `width` pixels of row
+    fn get_row(&self, y: usize) -> &[u32] {
+        &self.data[y * self.stride..y * self.stride + self.width]
+    } // fn get_row
+
+    /// Binary PPM (P6) image writing function
+    /// * `w` - writer to write image to
+    /// * Returns Ok if image is written, writer error otherwise
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        w.write_all(format!("P6\n{} {}\n255\n", self.width, self.height).as_bytes())?;
+
+        let mut row_bytes = Vec::with_capacity(self.width * 3);
+
+        for y in 0..self.height {
+            row_bytes.clear();
+            row_bytes.extend(self.get_row(y).iter().flat_map(|pixel| [(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8]));
+            w.write_all(&row_bytes)?;
+        }
+
+        Ok(())
+    } // fn write_ppm
+
+    /// 24-bit BMP image writing function
+    /// * `w` - writer to write image to
+    /// * Returns Ok if image is written, writer error otherwise
+    pub fn write_bmp<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        const HEADER_SIZE: u32 = 14;
+        const INFO_HEADER_SIZE: u32 = 40;
+
+        // Rows are aligned to 4 bytes
+        let row_size = (self.width * 3 + 3) & !3;
+        let image_size = (row_size * self.height) as u32;
+
+        let mut header = Vec::with_capacity((HEADER_SIZE + INFO_HEADER_SIZE) as usize);
+
+        // File header
+        header.extend_from_slice(b"BM");
+        header.extend_from_slice(&(HEADER_SIZE + INFO_HEADER_SIZE + image_size).to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&(HEADER_SIZE + INFO_HEADER_SIZE).to_le_bytes());
+
+        // Info header, positive height means bottom-up row order
+        header.extend_from_slice(&INFO_HEADER_SIZE.to_le_bytes());
+        header.extend_from_slice(&(self.width as i32).to_le_bytes());
+        header.extend_from_slice(&(self.height as i32).to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&24u16.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&image_size.to_le_bytes());
+        header.extend_from_slice(&2835i32.to_le_bytes());
+        header.extend_from_slice(&2835i32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+
+        w.write_all(&header)?;
+
+        let mut row_bytes = Vec::with_capacity(row_size);
+
+        for y in (0..self.height).rev() {
+            row_bytes.clear();
+            row_bytes.extend(self.get_row(y).iter().flat_map(|pixel| [*pixel as u8, (pixel >> 8) as u8, (pixel >> 16) as u8]));
+            row_bytes.resize(row_size, 0);
+            w.write_all(&row_bytes)?;
+        }
+
+        Ok(())
+    } // fn write_bmp
 } // impl Surface
 
+/// Software rendering surface that owns its data representation structure
+pub struct OwnedSurface {
+    data: Vec<u32>,
+    width: usize,
+    height: usize,
+    stride: usize,
+} // struct OwnedSurface
+
+impl OwnedSurface {
+    /// New zero-filled surface construction function
+    /// * `width` - surface width
+    /// * `height` - surface height
+    /// * Returns newly created surface
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            data: vec![0; width * height],
+            width,
+            height,
+            stride: width,
+        }
+    } // fn new
+
+    /// Surface from existing data construction function
+    /// * `data` - surface data
+    /// * `width` - surface width
+    /// * `height` - surface height
+    /// * `stride` - surface row stride
+    /// * Returns newly created surface or None if `data` is too small to hold `height` rows or `stride` is less than `width`
+    pub fn from_vec(data: Vec<u32>, width: usize, height: usize, stride: usize) -> Option<Self> {
+        if stride < width || data.len() < stride * height {
+            return None;
+        }
+
+        Some(Self {
+            data,
+            width,
+            height,
+            stride,
+        })
+    } // fn from_vec
+
+    /// Borrowed surface view getting function
+    /// * Returns surface that renders into this surface data
+    pub fn as_surface(&mut self) -> Surface<'_> {
+        Surface::new(&mut self.data, self.width, self.height, self.stride)
+    } // fn as_surface
+
+    /// Surface data getting function
+    /// * Returns slice of all surface data
+    pub fn get_data(&self) -> &[u32] {
+        &self.data
+    } // fn get_data
+
+    /// Surface extent getting function
+    /// * Returns surface extent
+    pub fn get_extent(&self) -> Ext2<usize> {
+        Ext2 {
+            w: self.width,
+            h: self.height,
+        }
+    } // fn get_extent
+
+    /// Surface data stride getting function
+    /// * Returns length of single surface row in pixels
+    pub fn get_stride(&self) -> usize {
+        self.stride
+    } // fn get_stride
+
+    /// Surface into underlying data conversion function
+    /// * Returns surface data
+    pub fn into_vec(self) -> Vec<u32> {
+        self.data
+    } // fn into_vec
+} // impl OwnedSurface
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owned_surface_draw_and_read_back() {
+        let mut owned = OwnedSurface::new(8, 6);
+        assert_eq!(owned.get_extent(), Ext2 { w: 8, h: 6 });
+
+        {
+            let mut surface = owned.as_surface();
+            surface.draw_bar(2, 1, 5, 3, 0xFF0000);
+            surface.set_pixel(7, 5, 0x00FF00);
+        }
+
+        let pixel = |x: usize, y: usize| owned.get_data()[y * owned.get_stride() + x];
+        assert_eq!(pixel(2, 1), 0xFF0000);
+        assert_eq!(pixel(4, 2), 0xFF0000);
+        assert_eq!(pixel(2, 3), 0x000000);
+        assert_eq!(pixel(7, 5), 0x00FF00);
+        let data = owned.get_data();
+        assert_eq!(data.iter().filter(|pixel| **pixel == 0xFF0000).count(), 3 * 2);
+    } // fn owned_surface_draw_and_read_back
+
+    #[test]
+    fn owned_surface_from_vec_respects_stride() {
+        assert!(OwnedSurface::from_vec(vec![0; 10], 4, 3, 4).is_none());
+        assert!(OwnedSurface::from_vec(vec![0; 16], 4, 3, 3).is_none());
+
+        let mut owned = OwnedSurface::from_vec(vec![0; 18], 4, 3, 6).unwrap();
+        owned.as_surface().draw_bar(0, 0, 4, 3, 0xFFFFFF);
+
+        // Padding after every row is left untouched
+        let data = owned.into_vec();
+        for row in data.chunks(6) {
+            assert_eq!(row, &[0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0, 0]);
+        }
+    } // fn owned_surface_from_vec_respects_stride
+
+    #[test]
+    fn diff_reports_modified_pixels() {
+        let (width, height) = (8, 6);
+        let pattern = |x: usize, y: usize| (x * 0x10 + y * 0x1000) as u32;
+
+        // Surfaces with different strides but same contents
+        let mut left_data = vec![0u32; width * height];
+        let mut right_data = vec![0xDEADBEEFu32; (width + 3) * height];
+        for y in 0..height {
+            for x in 0..width {
+                left_data[y * width + x] = pattern(x, y);
+                right_data[y * (width + 3) + x] = pattern(x, y);
+            }
+        }
+
+        let left = Surface::new(&mut left_data, width, height, width);
+        assert_eq!(left.diff(&Surface::new(&mut right_data, width, height, width + 3)), None);
+
+        // One modified pixel
+        right_data[4 * (width + 3) + 5] ^= 0x00300000;
+        let right = Surface::new(&mut right_data, width, height, width + 3);
+        assert_eq!(left.diff(&right), Some(SurfaceDiff {
+            pixel_count: 1,
+            bounds: Rect::new(5..6, 4..5),
+            max_channel_delta: 0x30,
+        }));
+
+        // Second pixel extends bounds
+        right_data[width + 3 + 2] = pattern(2, 1) + 2;
+        let right = Surface::new(&mut right_data, width, height, width + 3);
+        assert_eq!(left.diff(&right), Some(SurfaceDiff {
+            pixel_count: 2,
+            bounds: Rect::new(2..6, 1..5),
+            max_channel_delta: 0x30,
+        }));
+    } // fn diff_reports_modified_pixels
+
+    #[test]
+    fn clear_and_fill_respect_stride() {
+        let (width, height, stride) = (4, 3, 6);
+        let mut data = vec![0u32; stride * height];
+
+        // Padding columns of every row stay untouched
+        Surface::new(&mut data, width, height, stride).clear(0x112233);
+        for y in 0..height {
+            assert!(data[y * stride..y * stride + width].iter().all(|&pixel| pixel == 0x112233));
+            assert!(data[y * stride + width..(y + 1) * stride].iter().all(|&pixel| pixel == 0));
+        }
+
+        // Rectangle is clipped by surface bounds, not by stride
+        Surface::new(&mut data, width, height, stride).fill(Rect::new(2..10, 1..10), 0xAABBCC);
+        for y in 0..height {
+            for x in 0..stride {
+                let expected = match (x < width, x >= 2 && y >= 1) {
+                    (false, _) => 0,
+                    (true, true) => 0xAABBCC,
+                    (true, false) => 0x112233,
+                };
+                assert_eq!(data[y * stride + x], expected, "pixel ({}, {})", x, y);
+            }
+        }
+
+        // Rectangle outside of surface changes nothing
+        let before = data.clone();
+        Surface::new(&mut data, width, height, stride).fill(Rect::new(4..6, 0..3), 0xFFFFFF);
+        assert!(data == before);
+    } // fn clear_and_fill_respect_stride
+
+    #[test]
+    fn reversed_bar_matches_normalized_one() {
+        let (width, height) = (10, 8);
+
+        let bar_pixels = |x0: isize, y0: isize, x1: isize, y1: isize| {
+            let mut data = vec![0u32; width * height];
+            Surface::new(&mut data, width, height, width).draw_bar(x0, y0, x1, y1, 0xFFFFFF);
+            data
+        };
+
+        for (x0, y0, x1, y1) in [(2, 1, 7, 5), (0, 0, 10, 8), (-3, -2, 4, 20), (3, 3, 3, 6)] {
+            let normalized = bar_pixels(x0, y0, x1, y1);
+            assert!(bar_pixels(x1, y1, x0, y0) == normalized);
+            assert!(bar_pixels(x1, y0, x0, y1) == normalized);
+            assert!(bar_pixels(x0, y1, x1, y0) == normalized);
+        }
+
+        let filled = bar_pixels(7, 5, 2, 1).iter().filter(|&&pixel| pixel == 0xFFFFFF).count();
+        assert_eq!(filled, 5 * 4);
+    } // fn reversed_bar_matches_normalized_one
+
+    #[test]
+    fn pixel_is_read_back() {
+        let (width, height, stride) = (5, 4, 7);
+        let mut data = vec![0u32; stride * height];
+        let mut surface = Surface::new(&mut data, width, height, stride);
+
+        surface.set_pixel(4, 3, 0x123456);
+        surface.set_pixel(0, 1, 0xABCDEF);
+        assert_eq!(surface.get_pixel(4, 3), Some(0x123456));
+        assert_eq!(surface.get_pixel(0, 1), Some(0xABCDEF));
+        assert_eq!(surface.get_pixel(1, 1), Some(0));
+
+        // Pixels out of width are ignored even if they fit into stride
+        surface.set_pixel(5, 0, 0xFFFFFF);
+        surface.set_pixel(0, 4, 0xFFFFFF);
+        assert_eq!(surface.get_pixel(5, 0), None);
+        assert_eq!(surface.get_pixel(0, 4), None);
+        assert_eq!(surface.get_pixel(usize::MAX, usize::MAX), None);
+
+        assert_eq!(data.iter().filter(|&&pixel| pixel != 0).count(), 2);
+        assert_eq!(data[3 * stride + 4], 0x123456);
+    } // fn pixel_is_read_back
+
+    #[test]
+    fn clipped_and_keyed_blit() {
+        let key = 0xFF00FF;
+
+        // 3x2 source with padded stride, its center column is transparent
+        let mut src_data = vec![
+            0x000001, key, 0x000003, 0xDEAD,
+            0x000004, key, 0x000006, 0xDEAD,
+        ];
+        let src = Surface::new(&mut src_data, 3, 2, 4);
+
+        let (width, height, stride) = (4, 3, 5);
+        let mut dst_data = vec![0x777777u32; stride * height];
+
+        // Source is clipped by right and bottom destination borders
+        Surface::new(&mut dst_data, width, height, stride).blit(&src, 2, 2);
+        let mut expected = vec![0x777777u32; stride * height];
+        expected[2 * stride + 2] = 0x000001;
+        expected[2 * stride + 3] = key;
+        assert!(dst_data == expected);
+
+        // Transparent color isn't copied
+        Surface::new(&mut dst_data, width, height, stride).blit_keyed(&src, 0, 0, key);
+        for (x, y, color) in [(0, 0, 0x000001), (2, 0, 0x000003), (0, 1, 0x000004), (2, 1, 0x000006)] {
+            expected[y * stride + x] = color;
+        }
+        assert!(dst_data == expected);
+
+        // Source fully outside of destination changes nothing
+        Surface::new(&mut dst_data, width, height, stride).blit(&src, 4, 0);
+        Surface::new(&mut dst_data, width, height, stride).blit(&src, 0, 3);
+        Surface::new(&mut dst_data, width, height, stride).blit(&src, 100, 0);
+        Surface::new(&mut dst_data, width, height, stride).blit(&src, 0, 100);
+        Surface::new(&mut dst_data, width, height, stride).blit_keyed(&src, usize::MAX, usize::MAX, key);
+        assert!(dst_data == expected);
+    } // fn clipped_and_keyed_blit
+
+    #[test]
+    fn half_white_over_black_is_mid_gray() {
+        let (width, height) = (4, 3);
+        let mut data = vec![0u32; width * height];
+        let mut surface = Surface::new(&mut data, width, height, width);
+
+        // Unused high bytes don't leak into result
+        surface.set_pixel(0, 0, 0xFF000000);
+        surface.blend_pixel(0, 0, 0xFFFFFFFF, 128);
+        surface.blend_pixel(4, 0, 0xFFFFFF, 128);
+        surface.draw_bar_blended(3, 3, 1, 1, 0xFFFFFF, 128);
+
+        let channels = |color: u32| [(color >> 16) & 0xFF, (color >> 8) & 0xFF, color & 0xFF];
+        for (x, y) in [(0, 0), (1, 1), (2, 2)] {
+            let color = surface.get_pixel(x, y).unwrap();
+            assert_eq!(color >> 24, 0);
+            assert_eq!(channels(color), [0x80; 3], "pixel ({}, {}) is {:08X}", x, y, color);
+        }
+        assert_eq!(data.iter().filter(|&&pixel| pixel == 0x808080).count(), 1 + 2 * 2);
+
+        // Blending extremes
+        assert_eq!(blend_color(0x123456, 0xABCDEF, 0), 0x123456);
+        assert_eq!(blend_color(0x123456, 0xABCDEF, 255), 0xABCDEF);
+    } // fn half_white_over_black_is_mid_gray
+
+    #[test]
+    fn circles_are_drawn_and_clipped() {
+        let (width, height) = (20, 16);
+        let mut data = vec![0u32; width * height];
+        let mut surface = Surface::new(&mut data, width, height, width);
+
+        surface.draw_circle(8, 8, 5, 0xFFFFFF);
+        for (x, y) in [(13, 8), (3, 8), (8, 3), (8, 13)] {
+            assert_eq!(surface.get_pixel(x, y), Some(0xFFFFFF), "outline pixel ({}, {})", x, y);
+        }
+        assert_eq!(surface.get_pixel(8, 8), Some(0));
+        assert_eq!(surface.get_pixel(12, 12), Some(0));
+
+        surface.fill_circle(8, 8, 3, 0x00FF00);
+        for (x, y) in [(8, 8), (11, 8), (5, 8), (8, 11), (10, 10)] {
+            assert_eq!(surface.get_pixel(x, y), Some(0x00FF00), "filled pixel ({}, {})", x, y);
+        }
+        for (x, y) in [(12, 8), (11, 11), (5, 5)] {
+            assert_ne!(surface.get_pixel(x, y), Some(0x00FF00), "pixel ({}, {}) is out of radius", x, y);
+        }
+
+        // Circles with off-surface centers are clipped
+        surface.draw_circle(-3, 20, 8, 0xFF0000);
+        surface.fill_circle(25, -4, 9, 0x0000FF);
+        surface.fill_circle(-100, -100, 4, 0x0000FF);
+        assert_eq!(surface.get_pixel(19, 0), Some(0x0000FF));
+        assert!(data.contains(&0xFF0000));
+    } // fn circles_are_drawn_and_clipped
+
+    #[test]
+    fn triangle_is_filled() {
+        let (width, height) = (16, 12);
+        let mut data = vec![0u32; width * height];
+        let mut surface = Surface::new(&mut data, width, height, width);
+
+        surface.fill_convex_polygon(&[(2, 2), (14, 2), (2, 10)], 0xFFFFFF);
+        for (x, y) in [(3, 3), (8, 3), (3, 8), (6, 5)] {
+            assert_eq!(surface.get_pixel(x, y), Some(0xFFFFFF), "interior pixel ({}, {})", x, y);
+        }
+        for (x, y) in [(1, 5), (8, 1), (12, 8), (9, 7), (3, 10)] {
+            assert_eq!(surface.get_pixel(x, y), Some(0), "exterior pixel ({}, {})", x, y);
+        }
+
+        // Winding order doesn't matter and polygon is clipped by surface
+        let filled = data.clone();
+        let mut reversed = vec![0u32; width * height];
+        Surface::new(&mut reversed, width, height, width).fill_convex_polygon(&[(2, 10), (14, 2), (2, 2)], 0xFFFFFF);
+        assert!(reversed == filled);
+
+        let mut clipped = vec![0u32; width * height];
+        Surface::new(&mut clipped, width, height, width).fill_convex_polygon(&[(-10, -10), (30, -10), (30, 30), (-10, 30)], 0xFFFFFF);
+        assert!(clipped.iter().all(|&pixel| pixel == 0xFFFFFF));
+    } // fn triangle_is_filled
+
+    #[test]
+    fn aa_line_blends_diagonal_and_keeps_horizontal_solid() {
+        let (width, height) = (16, 12);
+        let mut data = vec![0u32; width * height];
+        let mut surface = Surface::new(&mut data, width, height, width);
+
+        // Ideal line passes between pixel centers at odd columns, so pixels on both sides are partially covered
+        surface.draw_line_aa(0.0, 0.0, 10.0, 5.0, 0xFFFFFF);
+        for x in [1, 3, 5, 7, 9] {
+            let y = x / 2;
+            for pixel in [surface.get_pixel(x, y).unwrap(), surface.get_pixel(x, y + 1).unwrap()] {
+                assert!(pixel != 0 && pixel != 0xFFFFFF, "pixel {:06X} near ({}, {}) isn't partially blended", pixel, x, y);
+            }
+        }
+        assert_eq!(surface.get_pixel(4, 2), Some(0xFFFFFF));
+        assert_eq!(surface.get_pixel(4, 5), Some(0));
+
+        surface.draw_line_aa(0.0, 8.0, 15.0, 8.0, 0x00FF00);
+        for x in 0..width {
+            assert_eq!(surface.get_pixel(x, 8), Some(0x00FF00), "horizontal line pixel {}", x);
+            assert_eq!(surface.get_pixel(x, 7), Some(0));
+            assert_eq!(surface.get_pixel(x, 9), Some(0));
+        }
+    } // fn aa_line_blends_diagonal_and_keeps_horizontal_solid
+
+    #[test]
+    fn ppm_and_bmp_headers_describe_surface() {
+        let (width, height, stride) = (3, 2, 5);
+        let mut data = vec![0xDEADu32; stride * height];
+        let mut surface = Surface::new(&mut data, width, height, stride);
+        surface.clear(0x102030);
+        surface.set_pixel(0, 0, 0xAABBCC);
+
+        let mut ppm = Vec::new();
+        surface.write_ppm(&mut ppm).unwrap();
+        let header = b"P6\n3 2\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + width * height * 3);
+        assert_eq!(&ppm[header.len()..header.len() + 6], &[0xAA, 0xBB, 0xCC, 0x10, 0x20, 0x30]);
+
+        let mut bmp = Vec::new();
+        surface.write_bmp(&mut bmp).unwrap();
+        let u32_at = |offset: usize| u32::from_le_bytes(bmp[offset..offset + 4].try_into().unwrap());
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(u32_at(2) as usize, bmp.len());
+        assert_eq!((u32_at(18), u32_at(22)), (width as u32, height as u32));
+        assert_eq!(u16::from_le_bytes([bmp[28], bmp[29]]), 24);
+
+        // Rows are padded to 4 bytes and stored bottom-up, so top left pixel starts last row
+        let row_size = 12;
+        assert_eq!(bmp.len(), 54 + row_size * height);
+        assert_eq!(&bmp[54 + row_size..54 + row_size + 3], &[0xCC, 0xBB, 0xAA]);
+        assert_eq!(&bmp[54..57], &[0x30, 0x20, 0x10]);
+    } // fn ppm_and_bmp_headers_describe_surface
+} // mod tests
+
 // file self.rs
@@ -0,0 +1,85 @@
+/// WEIRD Project
+/// `File` texture.rs
+/// `Description` Texture representation and sampling module
+/// `Author` TioT2
+/// `Last changed` 17.10.2026
+
+/// Texture unique identifier representation structure
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub struct TextureId(u32);
+
+impl TextureId {
+    /// Texture id from u32 creation function
+    /// * `index` - index of texture to turn into TextureId
+    /// * Returns new TextureId
+    pub fn new(index: u32) -> Self {
+        Self(index)
+    } // fn new
+
+    /// TextureId into unique underlying u32 turning function
+    /// * Returns TextureId unique underlying u32
+    pub fn as_u32(self) -> u32 {
+        self.0
+    } // fn as_u32
+} // impl TextureId
+
+/// Texture representation structure
+#[derive(Clone, Debug, PartialEq)]
+pub struct Texture {
+    /// Texture width in texels
+    width: usize,
+    /// Texture height in texels
+    height: usize,
+    /// 0x00RRGGBB texels, row by row
+    pixels: Vec<u32>,
+} // struct Texture
+
+impl Texture {
+    /// Texture create function
+    /// * `width` - texture width
+    /// * `height` - texture height
+    /// * `pixels` - texels, row by row
+    /// * Returns texture or None if texture is empty or pixel count doesn't match size
+    pub fn new(width: usize, height: usize, pixels: Vec<u32>) -> Option<Self> {
+        if width == 0 || height == 0 || width.checked_mul(height) != Some(pixels.len()) {
+            return None;
+        }
+
+        Some(Self { width, height, pixels })
+    } // fn new
+
+    /// Texture width getting function
+    /// * Returns texture width in texels
+    pub fn get_width(&self) -> usize {
+        self.width
+    } // fn get_width
+
+    /// Texture height getting function
+    /// * Returns texture height in texels
+    pub fn get_height(&self) -> usize {
+        self.height
+    } // fn get_height
+
+    /// Texel getting function
+    /// * `x`, `y` - texel coordinates
+    /// * Returns texel or None if coordinates are out of texture
+    pub fn get_texel(&self, x: usize, y: usize) -> Option<u32> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    } // fn get_texel
+
+    /// Repeated texture sampling function (nearest texel)
+    /// * `u`, `v` - texture coordinates, [0, 1) range covers whole texture, it's repeated outside of it
+    /// * Returns sampled texel
+    pub fn sample(&self, u: f32, v: f32) -> u32 {
+        let x = ((u.rem_euclid(1.0) * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v.rem_euclid(1.0) * self.height as f32) as usize).min(self.height - 1);
+
+        self.pixels[y * self.width + x]
+    } // fn sample
+} // impl Texture
+
+// file texture.rs
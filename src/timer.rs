@@ -4,9 +4,17 @@
 /// `Author` TioT2
 /// `Last changed` 05.05.2024
 
+/// Default fixed step duration
+pub const DEFAULT_FIXED_DT: f32 = 1.0 / 60.0;
+
+/// Default maximal count of fixed steps per frame
+pub const DEFAULT_MAX_FIXED_STEPS: usize = 8;
+
+/// Default count of frame times timer keeps
+pub const DEFAULT_FRAME_HISTORY_SIZE: usize = 128;
+
 /// Timer representation structure
 pub struct Timer {
-    start_time_point: std::time::Instant,
     time_point: std::time::Instant,
     fps_time_point: std::time::Instant,
     time: f32,
@@ -14,15 +22,33 @@ pub struct Timer {
     fps: f32,
     fps_counter: u32,
     fps_duration: f32,
+    time_scale: f32,
+    paused: bool,
+    fixed_dt: f32,
+    max_fixed_steps: usize,
+    fixed_time_accumulator: f32,
+    /// Real (unscaled) durations of last frames, ring buffer
+    frame_times: Vec<f32>,
+    /// Maximal count of frame times kept
+    frame_history_size: usize,
+    /// Index of frame time to overwrite next if history is full
+    next_frame_time_index: usize,
 } // struct Timer
 
 impl Timer {
     /// Timer constructor.
     /// * Returns newly created timer with count, starting from creation moment
     pub fn new() -> Self {
+        Self::new_with_history(DEFAULT_FRAME_HISTORY_SIZE)
+    } // fn new
+
+    /// Timer with specified frame time history size constructor.
+    /// * `frame_history_size` - count of last frame times to keep, at least one is kept
+    /// * Returns newly created timer with count, starting from creation moment
+    pub fn new_with_history(frame_history_size: usize) -> Self {
+        let frame_history_size = frame_history_size.max(1);
         let now = std::time::Instant::now();
         Self {
-            start_time_point: now.clone(),
             time_point: now.clone(),
             fps_time_point: now.clone(),
             time: 0.0,
@@ -30,27 +56,49 @@ impl Timer {
             fps: 30.0,
             fps_counter: 0,
             fps_duration: 1.0,
+            time_scale: 1.0,
+            paused: false,
+            fixed_dt: DEFAULT_FIXED_DT,
+            max_fixed_steps: DEFAULT_MAX_FIXED_STEPS,
+            fixed_time_accumulator: 0.0,
+            frame_times: Vec::with_capacity(frame_history_size),
+            frame_history_size,
+            next_frame_time_index: 0,
         }
-    } // fn new
+    } // fn new_with_history
 
     /// Timer resetting function.
     /// Sets timer starting point to 0.
     pub fn reset_time(&mut self) {
         let now = std::time::Instant::now();
-        self.start_time_point = now.clone();
         self.time_point = now.clone();
         self.fps_time_point = now.clone();
         self.fps_counter = 0;
 
         self.time = 0.0;
+        self.fixed_time_accumulator = 0.0;
+        self.frame_times.clear();
+        self.next_frame_time_index = 0;
     } // fn reset_time
 
     /// Timer duration update function.
+    /// Time advances by real time passed multiplied by time scale (or doesn't advance at all if timer is paused).
     pub fn response(&mut self) {
-        let now = std::time::Instant::now();
+        self.response_at(std::time::Instant::now());
+    } // fn response
+
+    /// Timer duration to specified moment update function
+    /// * `now` - moment current frame ends at
+    fn response_at(&mut self, now: std::time::Instant) {
+        let frame_time = (now - self.time_point).as_secs_f32();
 
-        self.time = (now - self.start_time_point).as_secs_f32();
-        self.delta_time = (now - self.time_point).as_secs_f32();
+        self.push_frame_time(frame_time);
+        self.delta_time = if self.paused {
+            0.0
+        } else {
+            frame_time * self.time_scale
+        };
+        self.time += self.delta_time;
 
         self.fps_counter += 1;
 
@@ -62,7 +110,137 @@ impl Timer {
         }
 
         self.time_point = now;
-    } // fn response
+    } // fn response_at
+
+    /// Frame time to history adding function
+    /// * `frame_time` - real frame duration, replaces the oldest one if history is full
+    fn push_frame_time(&mut self, frame_time: f32) {
+        if self.frame_times.len() < self.frame_history_size {
+            self.frame_times.push(frame_time);
+        } else {
+            self.frame_times[self.next_frame_time_index] = frame_time;
+        }
+        self.next_frame_time_index = (self.next_frame_time_index + 1) % self.frame_history_size;
+    } // fn push_frame_time
+
+    /// Frame times getting function
+    /// * Returns iterator on real durations of last frames from the oldest to the latest one
+    pub fn get_frame_times(&self) -> impl DoubleEndedIterator<Item = f32> + '_ {
+        // History isn't full yet if next index points after its end, so older part is empty then
+        let (newer, older) = self.frame_times.split_at(self.next_frame_time_index.min(self.frame_times.len()));
+
+        older.iter().chain(newer.iter()).copied()
+    } // fn get_frame_times
+
+    /// Average frame time getting function
+    /// * Returns average duration of frames in history, 0 if there's no frames yet
+    pub fn get_avg_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            0.0
+        } else {
+            self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+        }
+    } // fn get_avg_frame_time
+
+    /// Maximal frame time getting function
+    /// * Returns maximal duration of frames in history, 0 if there's no frames yet
+    pub fn get_max_frame_time(&self) -> f32 {
+        self.frame_times.iter().copied().fold(0.0, f32::max)
+    } // fn get_max_frame_time
+
+    /// Frame rate limiting function, sleeps until frame started at last `response` call lasts 1 / `target_fps` seconds
+    /// * `target_fps` - maximal frame rate, 0 means no limit
+    pub fn sleep_to_target(&self, target_fps: f32) {
+        let sleep_duration = Self::get_sleep_duration(self.time_point.elapsed(), target_fps);
+
+        if !sleep_duration.is_zero() {
+            std::thread::sleep(sleep_duration);
+        }
+    } // fn sleep_to_target
+
+    /// Frame rate limiting sleep duration getting function
+    /// * `elapsed` - time elapsed from frame start
+    /// * `target_fps` - maximal frame rate, 0 means no limit
+    /// * Returns time left until frame lasts 1 / `target_fps` seconds, zero if there's no limit or frame duration isn't representable (e.g. NaN target)
+    fn get_sleep_duration(elapsed: std::time::Duration, target_fps: f32) -> std::time::Duration {
+        if target_fps <= 0.0 {
+            return std::time::Duration::ZERO;
+        }
+
+        std::time::Duration::try_from_secs_f32(1.0 / target_fps)
+            .map_or(std::time::Duration::ZERO, |frame_duration| frame_duration.saturating_sub(elapsed))
+    } // fn get_sleep_duration
+
+    /// Timer pausing function, time and delta time stop advancing, FPS is still counted
+    pub fn pause(&mut self) {
+        self.paused = true;
+    } // fn pause
+
+    /// Timer resuming function
+    pub fn resume(&mut self) {
+        self.paused = false;
+    } // fn resume
+
+    /// Timer pause checking function
+    /// * Returns true if timer is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    } // fn is_paused
+
+    /// Time scale setting function
+    /// * `scale` - real time to timer time multiplier, negative values are treated as 0
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.time_scale = scale.max(0.0);
+    } // fn set_time_scale
+
+    /// Time scale getting function
+    /// * Returns real time to timer time multiplier
+    pub fn get_time_scale(&self) -> f32 {
+        self.time_scale
+    } // fn get_time_scale
+
+    /// Fixed steps accumulating function, should be called once per frame after `response`
+    /// * Returns count of fixed steps (of `get_fixed_dt` duration) passed since previous call
+    pub fn accumulate(&mut self) -> usize {
+        self.accumulate_time(self.delta_time)
+    } // fn accumulate
+
+    /// Fixed steps by time passed accumulating function
+    /// * `delta_time` - time passed since previous accumulation
+    /// * Returns count of fixed steps passed, at most `max_fixed_steps`, time of steps above maximum is dropped
+    fn accumulate_time(&mut self, delta_time: f32) -> usize {
+        self.fixed_time_accumulator += delta_time;
+
+        let steps = (self.fixed_time_accumulator / self.fixed_dt) as usize;
+        self.fixed_time_accumulator -= steps as f32 * self.fixed_dt;
+
+        if steps > self.max_fixed_steps {
+            self.fixed_time_accumulator = 0.0;
+            self.max_fixed_steps
+        } else {
+            steps
+        }
+    } // fn accumulate_time
+
+    /// Fixed step duration setting function
+    /// * `fixed_dt` - fixed step duration in seconds, ignored if not positive
+    pub fn set_fixed_dt(&mut self, fixed_dt: f32) {
+        if fixed_dt > 0.0 {
+            self.fixed_dt = fixed_dt;
+        }
+    } // fn set_fixed_dt
+
+    /// Fixed step duration getting function
+    /// * Returns fixed step duration in seconds
+    pub fn get_fixed_dt(&self) -> f32 {
+        self.fixed_dt
+    } // fn get_fixed_dt
+
+    /// Maximal count of fixed steps per frame setting function
+    /// * `max_fixed_steps` - maximal count of steps `accumulate` returns
+    pub fn set_max_fixed_steps(&mut self, max_fixed_steps: usize) {
+        self.max_fixed_steps = max_fixed_steps;
+    } // fn set_max_fixed_steps
 
     /// Time getting function
     /// * Returns current time
@@ -83,4 +261,134 @@ impl Timer {
     } // fn get_Efps
 } // impl Timer
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Timer frames with given durations simulating function
+    /// * `timer` - timer to update
+    /// * `frame_times` - frame durations in seconds
+    fn run_frames(timer: &mut Timer, frame_times: &[f32]) {
+        for frame_time in frame_times {
+            let now = timer.time_point + std::time::Duration::from_secs_f32(*frame_time);
+            timer.response_at(now);
+        }
+    } // fn run_frames
+
+    #[test]
+    fn scaled_time_advances_slower() {
+        let mut timer = Timer::new();
+        timer.set_time_scale(0.5);
+        run_frames(&mut timer, &[0.125; 4]);
+
+        assert!((timer.get_time() - 0.25).abs() < 1e-5);
+        assert!((timer.get_delta_time() - 0.0625).abs() < 1e-5);
+
+        // Paused timer doesn't advance, but counts frames
+        timer.pause();
+        run_frames(&mut timer, &[0.25; 4]);
+        assert!(timer.is_paused());
+        assert_eq!(timer.get_delta_time(), 0.0);
+        assert!((timer.get_time() - 0.25).abs() < 1e-5);
+        // 6 real frames passed during the first second
+        assert!((timer.get_fps() - 6.0).abs() < 1e-3);
+
+        timer.resume();
+        timer.set_time_scale(-1.0);
+        run_frames(&mut timer, &[0.125]);
+        assert_eq!(timer.get_time_scale(), 0.0);
+        assert!((timer.get_time() - 0.25).abs() < 1e-5);
+    } // fn scaled_time_advances_slower
+
+    #[test]
+    fn fixed_steps_match_elapsed_time() {
+        let mut timer = Timer::new();
+        timer.set_fixed_dt(0.01);
+
+        // Uneven frames, remainder of steps is carried over to next frames
+        let frame_times = [0.005, 0.031, 0.012, 0.054, 0.001, 0.017, 0.0, 0.02];
+        let mut steps = 0;
+        for frame_time in frame_times {
+            run_frames(&mut timer, &[frame_time]);
+            steps += timer.accumulate();
+        }
+
+        let elapsed = frame_times.iter().sum::<f32>();
+        assert_eq!(steps, (elapsed / timer.get_fixed_dt()) as usize);
+        assert_eq!(steps, 14);
+
+        // Steps above maximum are dropped
+        timer.set_max_fixed_steps(4);
+        run_frames(&mut timer, &[1.0]);
+        assert_eq!(timer.accumulate(), 4);
+        run_frames(&mut timer, &[0.015]);
+        assert_eq!(timer.accumulate(), 1);
+
+        timer.set_fixed_dt(0.0);
+        assert_eq!(timer.get_fixed_dt(), 0.01);
+    } // fn fixed_steps_match_elapsed_time
+
+    #[test]
+    fn frame_time_history_keeps_last_frames() {
+        let mut timer = Timer::new_with_history(3);
+        assert_eq!(timer.get_avg_frame_time(), 0.0);
+        assert_eq!(timer.get_max_frame_time(), 0.0);
+
+        // Frame times aren't scaled and go from the oldest to the latest one before history is full
+        timer.set_time_scale(2.0);
+        run_frames(&mut timer, &[0.125, 0.5]);
+        let frame_times = timer.get_frame_times().collect::<Vec<_>>();
+        assert_eq!(frame_times.len(), 2);
+        for (actual, expected) in frame_times.iter().zip([0.125, 0.5]) {
+            assert!((actual - expected).abs() < 1e-5);
+        }
+
+        // The oldest one is overwritten, but order is kept after ring buffer wraps
+        run_frames(&mut timer, &[0.25, 0.0625, 0.375]);
+        let frame_times = timer.get_frame_times().collect::<Vec<_>>();
+        assert_eq!(frame_times.len(), 3);
+        for (actual, expected) in frame_times.iter().zip([0.25, 0.0625, 0.375]) {
+            assert!((actual - expected).abs() < 1e-5);
+        }
+        assert!((timer.get_avg_frame_time() - 0.6875 / 3.0).abs() < 1e-5);
+        assert!((timer.get_max_frame_time() - 0.375).abs() < 1e-5);
+
+        timer.reset_time();
+        assert_eq!(timer.get_frame_times().count(), 0);
+        assert_eq!(Timer::new_with_history(0).frame_history_size, 1);
+    } // fn frame_time_history_keeps_last_frames
+
+    #[test]
+    fn frame_rate_is_limited_by_target() {
+        let mut timer = Timer::new();
+        timer.response();
+
+        // Sleeping only adds time, so lower bound is timing-tolerant
+        for _ in 0..3 {
+            let frame_start = std::time::Instant::now();
+            timer.sleep_to_target(60.0);
+            timer.response();
+            assert!(frame_start.elapsed() >= std::time::Duration::from_millis(15));
+            assert!(timer.get_delta_time() >= 0.015);
+        }
+    } // fn frame_rate_is_limited_by_target
+
+    #[test]
+    fn sleep_duration_fills_frame_to_target() {
+        use std::time::Duration;
+
+        assert_eq!(Timer::get_sleep_duration(Duration::from_millis(100), 4.0), Duration::from_millis(150));
+        assert_eq!(Timer::get_sleep_duration(Duration::from_millis(300), 4.0), Duration::ZERO);
+
+        // Zero target doesn't limit frame rate
+        assert_eq!(Timer::get_sleep_duration(Duration::ZERO, 0.0), Duration::ZERO);
+        assert_eq!(Timer::get_sleep_duration(Duration::ZERO, -1.0), Duration::ZERO);
+
+        // Frame durations out of `Duration` range aren't slept
+        assert_eq!(Timer::get_sleep_duration(Duration::ZERO, f32::NAN), Duration::ZERO);
+        assert_eq!(Timer::get_sleep_duration(Duration::ZERO, 1e-30), Duration::ZERO);
+        assert_eq!(Timer::get_sleep_duration(Duration::ZERO, f32::INFINITY), Duration::ZERO);
+    } // fn sleep_duration_fills_frame_to_target
+} // mod tests
+
 // file timer.rs
@@ -17,6 +17,21 @@ impl crate::math::numeric_traits::Sqrt for Fixed {
     }
 }
 
+impl crate::math::numeric_traits::Trig for Fixed {
+    fn sin(self) -> Self {
+        Angle::from_radians_fixed(self).sin()
+    }
+
+    fn cos(self) -> Self {
+        Angle::from_radians_fixed(self).cos()
+    }
+
+    fn tan(self) -> Self {
+        let angle = Angle::from_radians_fixed(self);
+        angle.sin() / angle.cos()
+    }
+}
+
 impl Default for Fixed {
     fn default() -> Self {
         Fixed::zero()
@@ -41,7 +56,56 @@ impl Into<f32> for Fixed {
     }
 }
 
-impl std::ops::Add<Fixed> for Fixed {
+impl core::fmt::Display for Fixed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&self.into_f32(), f)
+    }
+}
+
+/// Fixed number parsing error
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseFixedError {
+    /// String to parse is empty
+    Empty,
+    /// String to parse isn't a decimal number
+    Invalid,
+} // enum ParseFixedError
+
+impl core::fmt::Display for ParseFixedError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Empty => f.write_str("cannot parse fixed from empty string"),
+            Self::Invalid => f.write_str("invalid fixed literal"),
+        }
+    }
+}
+
+impl core::error::Error for ParseFixedError {}
+
+impl core::str::FromStr for Fixed {
+    type Err = ParseFixedError;
+
+    /// Parses decimal number, values out of 16.16 range are clamped to `MIN`/`MAX`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseFixedError::Empty);
+        }
+
+        let value = s.parse::<f32>().map_err(|_| ParseFixedError::Invalid)?;
+
+        if value.is_nan() {
+            Err(ParseFixedError::Invalid)
+        } else if value >= MAX.into_f32() {
+            Ok(MAX)
+        } else if value <= MIN.into_f32() {
+            Ok(MIN)
+        } else {
+            Ok(Fixed::from_f32(value))
+        }
+    }
+}
+
+impl core::ops::Add<Fixed> for Fixed {
     type Output = Fixed;
     fn add(self, rhs: Fixed) -> Self::Output {
         Self::Output {
@@ -50,13 +114,13 @@ impl std::ops::Add<Fixed> for Fixed {
     }
 }
 
-impl std::ops::AddAssign<Fixed> for Fixed {
+impl core::ops::AddAssign<Fixed> for Fixed {
     fn add_assign(&mut self, rhs: Fixed) {
         *self = *self + rhs;
     }
 }
 
-impl std::ops::Sub<Fixed> for Fixed {
+impl core::ops::Sub<Fixed> for Fixed {
     type Output = Fixed;
     fn sub(self, rhs: Fixed) -> Self::Output {
         Self::Output {
@@ -66,13 +130,13 @@ impl std::ops::Sub<Fixed> for Fixed {
 }
 
 
-impl std::ops::SubAssign<Fixed> for Fixed {
+impl core::ops::SubAssign<Fixed> for Fixed {
     fn sub_assign(&mut self, rhs: Fixed) {
         *self = *self - rhs;
     }
 }
 
-impl std::ops::Mul<Fixed> for Fixed {
+impl core::ops::Mul<Fixed> for Fixed {
     type Output = Fixed;
     fn mul(self, rhs: Fixed) -> Self::Output {
         Self::Output {
@@ -90,13 +154,13 @@ impl Fixed {
     }
 }
 
-impl std::ops::MulAssign<Fixed> for Fixed {
+impl core::ops::MulAssign<Fixed> for Fixed {
     fn mul_assign(&mut self, rhs: Fixed) {
         *self = *self * rhs;
     }
 }
 
-impl std::ops::Div<Fixed> for Fixed {
+impl core::ops::Div<Fixed> for Fixed {
     type Output = Fixed;
     fn div(self, rhs: Fixed) -> Self::Output {
         Self::Output {
@@ -113,13 +177,13 @@ impl Fixed {
     }
 }
 
-impl std::ops::DivAssign<Fixed> for Fixed {
+impl core::ops::DivAssign<Fixed> for Fixed {
     fn div_assign(&mut self, rhs: Fixed) {
         *self = *self / rhs;
     }
 }
 
-impl std::ops::Neg for Fixed {
+impl core::ops::Neg for Fixed {
     type Output = Fixed;
     fn neg(self) -> Self::Output {
         Self::Output {
@@ -128,14 +192,14 @@ impl std::ops::Neg for Fixed {
     }
 }
 
-impl std::cmp::PartialOrd for Fixed {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl core::cmp::PartialOrd for Fixed {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         self.value.partial_cmp(&other.value)
     }
 }
 
-impl std::cmp::Ord for Fixed {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+impl core::cmp::Ord for Fixed {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.value.cmp(&other.value)
     }
 }
@@ -165,28 +229,28 @@ impl Fixed {
     /// * Returns fixed point number
     pub const fn from_bits(bits: u32) -> Self {
         Self {
-            value: unsafe { std::mem::transmute::<u32, i32>(bits) },
+            value: unsafe { core::mem::transmute::<u32, i32>(bits) },
         }
     } // pub fn from_bits
 
     /// Number into bits conversion function
     /// * Returns number bits
     pub const fn to_bits(self) -> u32 {
-        unsafe { std::mem::transmute::<i32, u32>(self.value) }
+        unsafe { core::mem::transmute::<i32, u32>(self.value) }
     }// fn to_bits
 
     /// Fixed-point number from f32 constructor
     /// * `value` - floating-point number to construct Fixed from
     /// * Returns fixed that represents `value` number
     pub const fn from_f32(value: f32) -> Self {
-        let exp = (unsafe { std::mem::transmute::<f32, u32>(value) } >> 23) & 0xFF;
+        let exp = (unsafe { core::mem::transmute::<f32, u32>(value) } >> 23) & 0xFF;
 
         Fixed {
-            value: unsafe { std::mem::transmute::<u32, i32>(unwrap_or(if exp < 134 {
-                ((std::mem::transmute::<f32, u32>(value) & 0x7FFFFF) | 0x800000).checked_shr(134 - exp)
+            value: unsafe { core::mem::transmute::<u32, i32>(unwrap_or(if exp < 134 {
+                ((core::mem::transmute::<f32, u32>(value) & 0x7FFFFF) | 0x800000).checked_shr(134 - exp)
             } else {
-                ((std::mem::transmute::<f32, u32>(value) & 0x7FFFFF) | 0x800000).checked_shl(exp - 134)
-            }, 0) ^ std::mem::transmute::<i32, u32>(std::mem::transmute::<f32, i32>(value) >> 31)) }
+                ((core::mem::transmute::<f32, u32>(value) & 0x7FFFFF) | 0x800000).checked_shl(exp - 134)
+            }, 0) ^ core::mem::transmute::<i32, u32>(core::mem::transmute::<f32, i32>(value) >> 31)) }
         }
     } // fn from_f32
 
@@ -194,10 +258,10 @@ impl Fixed {
     /// * Returns number that represents this f32 as floating-point
     pub const fn into_f32(self) -> f32 {
         unsafe {
-            let unsigned = std::mem::transmute::<i32, u32>(self.value ^ (self.value >> 31));
+            let unsigned = core::mem::transmute::<i32, u32>(self.value ^ (self.value >> 31));
             let lz = unsigned.leading_zeros();
 
-            std::mem::transmute::<u32, f32>((std::mem::transmute::<i32, u32>(self.value) & 0x80000000) | ((142 - lz) << 23) | (unwrap_or(unsigned.checked_shl(lz + 1), 0) >> 9))
+            core::mem::transmute::<u32, f32>((core::mem::transmute::<i32, u32>(self.value) & 0x80000000) | ((142 - lz) << 23) | (unwrap_or(unsigned.checked_shl(lz + 1), 0) >> 9))
         }
     } // fn into_f32
 
@@ -237,6 +301,74 @@ impl Fixed {
         }
     } // fn abs
 
+    /// Minimum calculation function
+    /// * `other` - number to compare with
+    /// * Returns minimal of two numbers
+    pub const fn min(self, other: Fixed) -> Fixed {
+        if self.value < other.value { self } else { other }
+    } // fn min
+
+    /// Maximum calculation function
+    /// * `other` - number to compare with
+    /// * Returns maximal of two numbers
+    pub const fn max(self, other: Fixed) -> Fixed {
+        if self.value > other.value { self } else { other }
+    } // fn max
+
+    /// Linear interpolation function
+    /// * `to` - number to interpolate to
+    /// * `t` - interpolation factor, 0 corresponds to `self` and 1 to `to`
+    /// * Returns interpolated number
+    pub const fn lerp(self, to: Fixed, t: Fixed) -> Fixed {
+        Fixed {
+            value: self.value.wrapping_add(Fixed { value: to.value.wrapping_sub(self.value) }.mul_const(t).value),
+        }
+    } // fn lerp
+
+    /// Clamping function. Panics if `lo > hi`, same as `f32::clamp`.
+    /// * `lo` - lower bound
+    /// * `hi` - upper bound
+    /// * Returns number clamped into [lo, hi] range
+    pub const fn clamp(self, lo: Fixed, hi: Fixed) -> Fixed {
+        assert!(lo.value <= hi.value, "Fixed::clamp requires lo <= hi");
+        self.max(lo).min(hi)
+    } // fn clamp
+
+    /// Saturating addition function
+    /// * `rhs` - number to add
+    /// * Returns sum clamped into [MIN, MAX] range instead of wrapped one
+    pub const fn saturating_add(self, rhs: Fixed) -> Fixed {
+        Fixed {
+            value: self.value.saturating_add(rhs.value),
+        }
+    } // fn saturating_add
+
+    /// Saturating subtraction function
+    /// * `rhs` - number to subtract
+    /// * Returns difference clamped into [MIN, MAX] range instead of wrapped one
+    pub const fn saturating_sub(self, rhs: Fixed) -> Fixed {
+        Fixed {
+            value: self.value.saturating_sub(rhs.value),
+        }
+    } // fn saturating_sub
+
+    /// Saturating multiplication function
+    /// * `rhs` - number to multiply by
+    /// * Returns product clamped into [MIN, MAX] range instead of wrapped one
+    pub const fn saturating_mul(self, rhs: Fixed) -> Fixed {
+        let product = (self.value as i64 * rhs.value as i64) >> 16;
+
+        Fixed {
+            value: if product > i32::MAX as i64 {
+                i32::MAX
+            } else if product < i32::MIN as i64 {
+                i32::MIN
+            } else {
+                product as i32
+            },
+        }
+    } // fn saturating_mul
+
     /// Arccosine calculation function
     /// * Returns acos
     pub const fn acos(self) -> Angle {
@@ -247,6 +379,13 @@ impl Fixed {
         };
     } // fn acos
 
+    /// Sine table lookup function
+    /// * `index` - angle bits (full turn is 65536)
+    /// * Returns sine value from quarter-turn table
+    pub const fn sin_lookup(index: u16) -> Fixed {
+        sin_raw(index)
+    } // fn sin_lookup
+
     /// Signum calculation function
     /// * Returns self signum
     pub const fn signum(self) -> Self {
@@ -261,7 +400,7 @@ impl Fixed {
         // Assert on sqrt
         // debug_assert!(self.value >= 0);
 
-        let mut r: u32 = unsafe { std::mem::transmute::<i32, u32>(self.value) };
+        let mut r: u32 = unsafe { core::mem::transmute::<i32, u32>(self.value) };
         let mut q: u32 = 0;
         let mut b: u32 = 0x40000000u32;
         let mut t: u32;
@@ -325,7 +464,7 @@ impl Fixed {
 /// Minimal fixed value possible
 pub const MIN: Fixed = Fixed::from_bits(0x80000000);
 /// Maximal fixed value possible
-pub const MAX: Fixed = Fixed::from_bits(0xFFFFFFFF);
+pub const MAX: Fixed = Fixed::from_bits(0x7FFFFFFF);
 /// Minimal fixed value possible
 pub const EPSILON: Fixed = Fixed::from_bits(1);
 
@@ -334,12 +473,13 @@ pub mod consts {
     use super::Fixed;
 
     /// PI Number
-    pub const PI: Fixed = Fixed::from_f32(std::f32::consts::PI);
+    pub const PI: Fixed = Fixed::from_f32(core::f32::consts::PI);
     /// E Number
-    pub const E: Fixed = Fixed::from_f32(std::f32::consts::E);
+    pub const E: Fixed = Fixed::from_f32(core::f32::consts::E);
 } // mod consts
 
 /// Sine lookup table in [0..pi/4] range represented by fixed-point number
+#[allow(clippy::approx_constant)] // generated table values are expected to match constants
 const SIN_QUART: [Fixed; 16384] = {
     let sinq: [f32; 16384] = include!("sin_quart.txt");
     let mut dst = [Fixed::zero(); 16384];
@@ -366,6 +506,21 @@ const ACOS: [Angle; 65537] = {
     dst
 };
 
+/// Sine lookup function
+/// * `index` - angle bits (full turn is 65536)
+/// * Returns sine value from quarter-turn table with quadrant folding applied
+pub const fn sin_raw(index: u16) -> Fixed {
+    let sign_mask = (index as i16 >> 15) as i32;
+    let mut quart = index & 0x7FFF;
+    if quart > 0x3FFF {
+        quart = 0x7FFF - quart;
+    }
+
+    Fixed {
+        value: SIN_QUART[quart as usize].value ^ sign_mask
+    }
+} // fn sin_raw
+
 /// Angle representaiton structure
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
 pub struct Angle {
@@ -385,16 +540,18 @@ impl Angle {
     /// From degrees contained in float32 number angle construction function
     /// * `degrees` - degrees in floating point
     /// * Returns angle
+    #[cfg(feature = "std")]
     pub fn from_degrees_f32(degrees: f32) -> Self {
-        Self::from_radians_f32(degrees * std::f32::consts::PI / 180.0)
+        Self::from_radians_f32(degrees * core::f32::consts::PI / 180.0)
     } // fn from_degrees_f32
 
     /// From radians contained in float32 number angle construction function
     /// * `radians` - radians in floating point
     /// * Returns angle
+    #[cfg(feature = "std")]
     pub fn from_radians_f32(radians: f32) -> Self {
         Self {
-            value: (((radians / (std::f32::consts::PI * 2.0)).fract() + 1.0).fract() * 65536.0) as u16
+            value: (((radians / (core::f32::consts::PI * 2.0)).fract() + 1.0).fract() * 65536.0) as u16
         }
     } // fn from_radians_f32
 
@@ -430,58 +587,91 @@ impl Angle {
     /// * `radians` - radians in floating point
     /// * Returns angle
     pub fn into_radians_f32(self) -> f32 {
-        self.value as f32 * std::f32::consts::PI / 32768.0
+        self.value as f32 * core::f32::consts::PI / 32768.0
     } // fn from_radians_f32
 
     /// Angle sine calculation function
     /// * Returns the angle sine
-    pub const fn sin(mut self) -> Fixed {
-        let fixed_xor_mask = unsafe { std::mem::transmute::<u32, i32>((self.value as u32) << 16) >> 31 };
-        self.value &= 0x7FFF;
-        if self.value > 0x3FFF {
-            self.value = 0x7FFFu16.wrapping_sub(self.value);
+    pub const fn sin(self) -> Fixed {
+        sin_raw(self.value)
+    } // fn sin
+
+    /// Linear interpolation by shortest arc function
+    /// * `to` - angle to interpolate to
+    /// * `t` - interpolation factor, 0 corresponds to `self` and 1 to `to`
+    /// * Returns interpolated angle
+    pub const fn lerp_shortest(self, to: Angle, t: Fixed) -> Angle {
+        // Signed delta is always the shortest one
+        let delta = to.value.wrapping_sub(self.value) as i16 as i64;
+
+        Self {
+            value: self.value.wrapping_add(((delta * t.value as i64) >> 16) as u16),
+        }
+    } // fn lerp_shortest
+
+    /// Two-argument arctangent calculation function (angle of (x, y) vector)
+    /// * `y` - vector y component
+    /// * `x` - vector x component
+    /// * Returns vector angle, zero angle for zero vector
+    pub const fn atan2(y: Fixed, x: Fixed) -> Angle {
+        let ax = x.value.unsigned_abs() as u64;
+        let ay = y.value.unsigned_abs() as u64;
+
+        if ax == 0 && ay == 0 {
+            return Angle::zero();
         }
 
-        Fixed {
-            value: SIN_QUART[self.value as usize].value ^ fixed_xor_mask
+        let length = (ax * ax + ay * ay).isqrt();
+
+        // Arccosine is badly conditioned near 0 and 1, so table is accessed by smaller component only
+        let quarter = if ay <= ax {
+            16384 - ACOS[((ay << 16) / length) as usize].value
+        } else {
+            ACOS[((ax << 16) / length) as usize].value
+        };
+
+        let half = if x.value < 0 { 32768 - quarter } else { quarter };
+
+        Angle {
+            value: if y.value < 0 { half.wrapping_neg() } else { half },
         }
-    } // fn sin
+    } // fn atan2
 
     /// Angle cosine calculation function
     /// * Returns the angle cosine
-    pub const fn cos(mut self) -> Fixed {
-        self.value = self.value.wrapping_neg().wrapping_add(0x3FFF);
-        self.sin()
+    pub const fn cos(self) -> Fixed {
+        // Table values are taken in centers of angle steps, so cosine is sine of exactly 0x3FFF - angle
+        sin_raw(0x3FFFu16.wrapping_sub(self.value))
     } // fn cos
 } // impl Angle
 
-impl std::ops::Add<Angle> for Angle {
+impl core::ops::Add<Angle> for Angle {
     type Output = Angle;
     fn add(self, rhs: Self) -> Self::Output {
         Self { value: self.value.wrapping_add(rhs.value) }
     }
 }
 
-impl std::ops::AddAssign<Angle> for Angle {
+impl core::ops::AddAssign<Angle> for Angle {
     fn add_assign(&mut self, rhs: Angle) {
         self.value = self.value.wrapping_add(rhs.value);
     }
 }
 
-impl std::ops::Sub<Angle> for Angle {
+impl core::ops::Sub<Angle> for Angle {
     type Output = Angle;
     fn sub(self, rhs: Self) -> Self::Output {
         Self { value: self.value.wrapping_sub(rhs.value) }
     }
 }
 
-impl std::ops::SubAssign<Angle> for Angle {
+impl core::ops::SubAssign<Angle> for Angle {
     fn sub_assign(&mut self, rhs: Angle) {
         self.value = self.value.wrapping_sub(rhs.value);
     }
 }
 
-impl std::ops::Mul<Fixed> for Angle {
+impl core::ops::Mul<Fixed> for Angle {
     type Output = Angle;
     fn mul(self, rhs: Fixed) -> Self::Output {
         Self {
@@ -490,4 +680,185 @@ impl std::ops::Mul<Fixed> for Angle {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn trig_matches_f32() {
+        use crate::math::numeric_traits::Trig;
+
+        const EPSILON: f32 = 0.001;
+
+        for i in -2000..=2000 {
+            let radians = i as f32 * 0.005;
+            let fixed = Fixed::from_f32(radians);
+
+            let sin: f32 = Trig::sin(fixed).into();
+            let cos: f32 = Trig::cos(fixed).into();
+
+            assert!((sin - radians.sin()).abs() < EPSILON, "sin({}) = {}, expected {}", radians, sin, radians.sin());
+            assert!((cos - radians.cos()).abs() < EPSILON, "cos({}) = {}, expected {}", radians, cos, radians.cos());
+
+            // Tangent is badly conditioned near PI/2, so it's checked in (-1.2, 1.2) range only
+            if radians.abs() < 1.2 {
+                let tan: f32 = Trig::tan(fixed).into();
+                assert!((tan - radians.tan()).abs() < EPSILON * 4.0, "tan({}) = {}, expected {}", radians, tan, radians.tan());
+            }
+        }
+    } // fn trig_matches_f32
+
+    #[test]
+    fn display_and_from_str() {
+        use core::str::FromStr;
+        use std::format;
+
+        assert_eq!(format!("{}", Fixed::from_f32(1.5)), "1.5");
+        assert_eq!(format!("{:.3}", Fixed::from_f32(-0.5)), "-0.500");
+        assert_eq!(format!("{}", Fixed::from_i16(3)), "3");
+
+        assert_eq!(Fixed::from_str("-0.5"), Ok(Fixed::from_f32(-0.5)));
+        assert_eq!(Fixed::from_str("12.25"), Ok(Fixed::from_f32(12.25)));
+        assert_eq!(Fixed::from_str(""), Err(ParseFixedError::Empty));
+        assert_eq!(Fixed::from_str("abc"), Err(ParseFixedError::Invalid));
+        assert_eq!(Fixed::from_str("NaN"), Err(ParseFixedError::Invalid));
+
+        // Out of range values are clamped
+        assert_eq!(Fixed::from_str("1e9"), Ok(MAX));
+        assert_eq!(Fixed::from_str("-1e9"), Ok(MIN));
+        assert_eq!(Fixed::from_str("inf"), Ok(MAX));
+
+        // Displayed numbers are parsed back to the same value
+        for value in [0.0, 0.125, -0.5, 3.75, -1024.0625, 32767.5] {
+            let fixed = Fixed::from_f32(value);
+            assert_eq!(Fixed::from_str(&format!("{}", fixed)), Ok(fixed), "{} isn't parsed back", value);
+        }
+    } // fn display_and_from_str
+
+    #[test]
+    fn clamp_min_max_match_f32() {
+        // Functions are usable in const context
+        const CLAMPED: Fixed = consts::PI.clamp(Fixed::from_i16(0), Fixed::from_i16(3));
+        assert_eq!(CLAMPED, Fixed::from_i16(3));
+
+        let values = [-2.5f32, -1.0, -0.25, 0.0, 0.5, 1.0, 3.75];
+        for a in values {
+            for b in values {
+                let (fa, fb) = (Fixed::from_f32(a), Fixed::from_f32(b));
+                assert_eq!(fa.min(fb), Fixed::from_f32(a.min(b)));
+                assert_eq!(fa.max(fb), Fixed::from_f32(a.max(b)));
+            }
+            assert_eq!(Fixed::from_f32(a).clamp(Fixed::from_f32(-1.0), Fixed::from_f32(1.0)), Fixed::from_f32(a.clamp(-1.0, 1.0)));
+        }
+
+        // Degenerate range is allowed
+        assert_eq!(Fixed::from_f32(0.5).clamp(Fixed::from_i16(1), Fixed::from_i16(1)), Fixed::from_i16(1));
+    } // fn clamp_min_max_match_f32
+
+    #[test]
+    #[should_panic(expected = "Fixed::clamp requires lo <= hi")]
+    fn clamp_panics_on_inverted_range() {
+        // f32::clamp panics in this case too
+        _ = Fixed::from_f32(0.5).clamp(Fixed::from_i16(1), Fixed::from_i16(0));
+    } // fn clamp_panics_on_inverted_range
+
+    #[test]
+    fn lerp_and_lerp_shortest() {
+        let (a, b) = (Fixed::from_f32(2.0), Fixed::from_f32(10.0));
+        assert_eq!(a.lerp(b, Fixed::zero()), a);
+        assert_eq!(a.lerp(b, Fixed::from_i16(1)), b);
+        assert_eq!(a.lerp(b, Fixed::from_f32(0.25)), Fixed::from_f32(4.0));
+        assert_eq!(b.lerp(a, Fixed::from_f32(0.5)), Fixed::from_f32(6.0));
+
+        let half = Fixed::from_f32(0.5);
+
+        // Interpolation across 0/65535 boundary goes through zero in both directions
+        assert_eq!(Angle::from_bits(65535).lerp_shortest(Angle::from_bits(1), half).to_bits(), 0);
+        assert_eq!(Angle::from_bits(1).lerp_shortest(Angle::from_bits(65535), half).to_bits(), 0);
+        assert_eq!(Angle::from_bits(65000).lerp_shortest(Angle::from_bits(1000), half).to_bits(), 232);
+        assert_eq!(Angle::from_bits(1000).lerp_shortest(Angle::from_bits(65000), half).to_bits(), 232);
+
+        // 350 to 10 degrees goes through 0 degrees
+        let from = Angle::from_bits((350u32 * 65536 / 360) as u16);
+        let to = Angle::from_bits((10u32 * 65536 / 360) as u16);
+        for i in 0..=16 {
+            let angle = from.lerp_shortest(to, Fixed::from_f32(i as f32 / 16.0)).to_bits();
+            assert!(angle >= from.to_bits() || angle <= to.to_bits(), "angle {} isn't on the shortest arc", angle);
+        }
+        assert_eq!(from.lerp_shortest(to, Fixed::zero()), from);
+        assert_eq!(from.lerp_shortest(to, Fixed::from_i16(1)), to);
+
+        // Interpolation without wrap-around
+        assert_eq!(Angle::from_bits(1000).lerp_shortest(Angle::from_bits(3000), half).to_bits(), 2000);
+    } // fn lerp_and_lerp_shortest
+
+    #[test]
+    fn atan2_round_trip() {
+        // Axis cases
+        let (one, minus_one) = (Fixed::from_f32(1.0), Fixed::from_f32(-1.0));
+        assert_eq!(Angle::atan2(Fixed::zero(), Fixed::zero()).to_bits(), 0);
+        assert_eq!(Angle::atan2(Fixed::zero(), one).to_bits(), 0);
+        assert_eq!(Angle::atan2(one, Fixed::zero()).to_bits(), 16384);
+        assert_eq!(Angle::atan2(Fixed::zero(), minus_one).to_bits(), 32768);
+        assert_eq!(Angle::atan2(minus_one, Fixed::zero()).to_bits(), 49152);
+
+        // Circle sweep through all quadrants, error is measured in angle steps (1/65536 of turn)
+        let mut max_error = 0;
+        for bits in 0..=u16::MAX {
+            let angle = Angle::from_bits(bits);
+            let result = Angle::atan2(angle.sin(), angle.cos());
+
+            max_error = max_error.max((result.to_bits().wrapping_sub(bits) as i16).unsigned_abs());
+        }
+        assert!(max_error <= 1, "atan2 round-trip error is {} angle steps", max_error);
+    } // fn atan2_round_trip
+
+    #[test]
+    fn sin_cos_match_previous_implementation() {
+        // Previous implementation: sine folding on angle value, cosine is sine shifted by quarter turn
+        let previous_sin = |mut value: u16| -> Fixed {
+            let fixed_xor_mask = ((value as i16) >> 15) as i32;
+            value &= 0x7FFF;
+            if value > 0x3FFF {
+                value = 0x7FFFu16.wrapping_sub(value);
+            }
+            Fixed { value: SIN_QUART[value as usize].value ^ fixed_xor_mask }
+        };
+        let previous_cos = |value: u16| previous_sin(value.wrapping_neg().wrapping_add(0x3FFF));
+
+        for bits in 0..=u16::MAX {
+            let angle = Angle::from_bits(bits);
+            assert_eq!(angle.sin().to_bits(), previous_sin(bits).to_bits(), "sin differs at {}", bits);
+            assert_eq!(angle.cos().to_bits(), previous_cos(bits).to_bits(), "cos differs at {}", bits);
+            assert_eq!(Fixed::sin_lookup(bits).to_bits(), previous_sin(bits).to_bits(), "sin lookup differs at {}", bits);
+        }
+    } // fn sin_cos_match_previous_implementation
+
+    #[test]
+    fn saturating_arithmetic_clamps_to_range() {
+        let large = Fixed::from_f32(30000.0);
+
+        // Wrapping multiplication gives garbage, saturating one gives MAX
+        assert!(large * large < Fixed::from_i16(30000), "wrapping product is {}", large * large);
+        assert_eq!(large.saturating_mul(large), MAX);
+        assert_eq!(MAX.saturating_mul(MAX), MAX);
+        assert_eq!(MAX.saturating_mul(MIN), MIN);
+        assert_eq!(large.saturating_mul(Fixed::from_f32(-30000.0)), MIN);
+
+        assert_eq!(large.saturating_add(large), MAX);
+        assert_eq!(MAX.saturating_add(EPSILON), MAX);
+        assert_eq!(MIN.saturating_sub(EPSILON), MIN);
+        assert_eq!(Fixed::from_f32(-30000.0).saturating_sub(large), MIN);
+
+        // Results in range are same as non-saturating ones
+        let (a, b) = (Fixed::from_f32(12.5), Fixed::from_f32(-3.25));
+        assert_eq!(a.saturating_add(b), a + b);
+        assert_eq!(a.saturating_sub(b), a - b);
+        assert_eq!(a.saturating_mul(b), a * b);
+    } // fn saturating_arithmetic_clamps_to_range
+} // mod tests
+
 // file mod.rs
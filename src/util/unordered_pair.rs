@@ -1,13 +1,13 @@
 
 /// Unordered pair representation strucutre 
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 pub struct UnorderedPair<T> {
     first: T,
     second: T,
 } // struct UnorderedPair
 
 
-impl<T: std::cmp::PartialOrd> UnorderedPair<T> {
+impl<T: core::cmp::PartialOrd> UnorderedPair<T> {
     pub fn new(first: T, second: T) -> Self {
         if first < second {
             Self {
@@ -31,9 +31,27 @@ impl<T> UnorderedPair<T> {
     pub fn second(&self) -> &T {
         &self.second
     }
+
+    /// Lesser pair element getting function
+    /// * Returns reference to lesser element (pair elements are ordered on construction)
+    pub fn min(&self) -> &T {
+        &self.first
+    }
+
+    /// Greater pair element getting function
+    /// * Returns reference to greater element (pair elements are ordered on construction)
+    pub fn max(&self) -> &T {
+        &self.second
+    }
 } // impl UnorderedPair
 
-impl<T: std::cmp::PartialOrd> From<(T, T)> for UnorderedPair<T> {
+impl<T: core::fmt::Display> core::fmt::Display for UnorderedPair<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{{{}, {}}}", self.first, self.second)
+    }
+} // impl Display for UnorderedPair
+
+impl<T: core::cmp::PartialOrd> From<(T, T)> for UnorderedPair<T> {
     fn from(value: (T, T)) -> Self {
         Self::new(value.0, value.1)
     }
@@ -45,4 +63,29 @@ impl<T> Into<(T, T)> for UnorderedPair<T> {
     }
 } // impl From for UnorderedPair
 
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn pair_is_printed_in_ascending_order() {
+        let pair = UnorderedPair::new(2, 1);
+
+        assert_eq!(format!("{:?}", pair), "UnorderedPair { first: 1, second: 2 }");
+        assert_eq!(format!("{}", pair), "{1, 2}");
+        assert_eq!(format!("{}", UnorderedPair::new(1, 2)), "{1, 2}");
+
+        assert_eq!((*pair.min(), *pair.max()), (1, 2));
+        assert_eq!((*pair.first(), *pair.second()), (1, 2));
+        assert_eq!(pair, UnorderedPair::from((1, 2)));
+        assert_eq!(Into::<(i32, i32)>::into(pair), (1, 2));
+
+        let equal = UnorderedPair::new(3, 3);
+        assert_eq!((*equal.min(), *equal.max()), (3, 3));
+    } // fn pair_is_printed_in_ascending_order
+} // mod tests
+
 // file unordered_pair.rs